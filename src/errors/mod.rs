@@ -4,4 +4,4 @@
 
 mod vpn_error;
 
-pub use vpn_error::{VpnError, VpnResult};
+pub use vpn_error::{VpnError, VpnErrorKind, VpnResult};
@@ -1,9 +1,15 @@
 //! VPN error type definitions.
 
-use core::fmt;
+use core::{
+    fmt,
+    num::{ParseFloatError, ParseIntError},
+};
+use std::{io, net::AddrParseError};
 
 /// VPN operation errors.
-#[derive(Debug)]
+///
+/// Errors compare equal when both the variant and the message match.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VpnError {
     /// Connection error.
     Connection(String),
@@ -19,6 +25,61 @@ pub enum VpnError {
     Network(String),
 }
 
+/// Category of a [`VpnError`], without its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VpnErrorKind {
+    /// Connection error.
+    Connection,
+    /// Key exchange error.
+    KeyExchange,
+    /// Tunnel error.
+    Tunnel,
+    /// Authentication error.
+    Authentication,
+    /// Configuration error.
+    Configuration,
+    /// Network error.
+    Network,
+}
+
+impl VpnErrorKind {
+    /// Get the stable snake_case code for telemetry.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Connection => "connection",
+            Self::KeyExchange => "key_exchange",
+            Self::Tunnel => "tunnel",
+            Self::Authentication => "authentication",
+            Self::Configuration => "configuration",
+            Self::Network => "network",
+        }
+    }
+}
+
+impl VpnError {
+    /// Get the error's category.
+    #[must_use]
+    pub fn kind(&self) -> VpnErrorKind {
+        match self {
+            Self::Connection(_) => VpnErrorKind::Connection,
+            Self::KeyExchange(_) => VpnErrorKind::KeyExchange,
+            Self::Tunnel(_) => VpnErrorKind::Tunnel,
+            Self::Authentication(_) => VpnErrorKind::Authentication,
+            Self::Configuration(_) => VpnErrorKind::Configuration,
+            Self::Network(_) => VpnErrorKind::Network,
+        }
+    }
+
+    /// Get a stable machine-readable code, e.g. `"key_exchange"`.
+    ///
+    /// Codes never change between releases, unlike the display text.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        self.kind().as_str()
+    }
+}
+
 impl fmt::Display for VpnError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -34,5 +95,101 @@ impl fmt::Display for VpnError {
 
 impl std::error::Error for VpnError {}
 
+impl From<io::Error> for VpnError {
+    fn from(err: io::Error) -> Self {
+        Self::Network(format!("I/O error: {err}"))
+    }
+}
+
+impl From<ParseIntError> for VpnError {
+    fn from(err: ParseIntError) -> Self {
+        Self::Configuration(format!("invalid integer: {err}"))
+    }
+}
+
+impl From<ParseFloatError> for VpnError {
+    fn from(err: ParseFloatError) -> Self {
+        Self::Configuration(format!("invalid number: {err}"))
+    }
+}
+
+impl From<AddrParseError> for VpnError {
+    fn from(err: AddrParseError) -> Self {
+        Self::Configuration(format!("invalid address: {err}"))
+    }
+}
+
 /// Result type for VPN operations.
 pub type VpnResult<T> = Result<T, VpnError>;
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_std_errors() {
+        let err = VpnError::from(io::Error::new(io::ErrorKind::TimedOut, "probe timed out"));
+        assert!(matches!(&err, VpnError::Network(msg) if msg == "I/O error: probe timed out"));
+
+        let parse = |s: &str| -> VpnResult<u16> { Ok(s.parse()?) };
+        assert!(matches!(
+            parse("99999"),
+            Err(VpnError::Configuration(msg)) if msg.starts_with("invalid integer: ")
+        ));
+        assert_eq!(parse("443").ok(), Some(443));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let message = String::from("detail");
+        let cases = [
+            (
+                VpnError::Connection(message.clone()),
+                VpnErrorKind::Connection,
+                "connection",
+            ),
+            (
+                VpnError::KeyExchange(message.clone()),
+                VpnErrorKind::KeyExchange,
+                "key_exchange",
+            ),
+            (
+                VpnError::Tunnel(message.clone()),
+                VpnErrorKind::Tunnel,
+                "tunnel",
+            ),
+            (
+                VpnError::Authentication(message.clone()),
+                VpnErrorKind::Authentication,
+                "authentication",
+            ),
+            (
+                VpnError::Configuration(message.clone()),
+                VpnErrorKind::Configuration,
+                "configuration",
+            ),
+            (VpnError::Network(message), VpnErrorKind::Network, "network"),
+        ];
+        for (error, kind, code) in cases {
+            assert_eq!(error.kind(), kind);
+            assert_eq!(error.code(), code);
+        }
+    }
+
+    #[test]
+    fn test_errors_compare_by_variant_and_message() {
+        let active = || VpnError::Tunnel("Tunnel already active".into());
+        assert_eq!(active(), active());
+        assert_eq!(
+            VpnResult::<()>::Err(active()),
+            Err(VpnError::Tunnel(String::from("Tunnel already active")))
+        );
+
+        assert_ne!(active(), VpnError::Tunnel("Unknown tunnel 1".into()));
+        assert_ne!(
+            active(),
+            VpnError::Connection("Tunnel already active".into())
+        );
+        assert_eq!(active().to_string(), "Tunnel error: Tunnel already active");
+    }
+}
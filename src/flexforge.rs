@@ -9,12 +9,20 @@
 //! - Real-time connection status streaming
 //! - Bandwidth metrics display
 
+use std::net::IpAddr;
+
 use essentia_traits::plugin_contracts::{
     ConfigField, ConfigSchema, FlexForgeCapability, FlexForgeIntegration, FlexForgePanelCategory,
     FlexForgePanelInfo, StreamingCapable, UiConfigurable,
 };
 
-use crate::types::KeyExchangeProtocol;
+use crate::{
+    implementation::{DEFAULT_DNS_SERVERS, DEFAULT_MTU, MTU_RANGE},
+    types::{
+        ConnectionStats, KeyExchangeProtocol, SecurityLevel, ServerRegion, TransportMode,
+        TunnelState,
+    },
+};
 
 /// VPN Plugin FlexForge integration.
 #[derive(Debug)]
@@ -25,6 +33,33 @@ pub struct VpnPluginFlexForge {
     next_id:          u64,
     /// Connection state for UI display
     connection_state: ConnectionState,
+    /// Profile label shown on the dashboard
+    label:            Option<String>,
+    /// Emit a frame on the next refresh regardless of cadence
+    immediate_frame:  bool,
+    /// Security of the live connection, weakest hop for chains
+    security_level:   Option<SecurityLevel>,
+    /// Latest stats pushed by the VPN core
+    stats:            ConnectionStats,
+    /// Server of the live connection
+    server_id:        Option<String>,
+    /// Frame produced by the last successful render
+    last_frame:       StatusFrame,
+}
+
+/// Connection status emitted on each rendered frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatusFrame {
+    /// Connection state (see [`ConnectionState::as_str`])
+    pub state:         String,
+    /// Send rate (bytes/s)
+    pub send_rate_bps: f64,
+    /// Receive rate (bytes/s)
+    pub recv_rate_bps: f64,
+    /// Rolling average latency (ms)
+    pub latency_ms:    u32,
+    /// Server of the live connection
+    pub server_id:     Option<String>,
 }
 
 /// Configuration exposed through FlexForge UI.
@@ -35,13 +70,205 @@ pub struct VpnUiConfig {
     /// Auto-connect on startup
     pub auto_connect:   bool,
     /// Preferred server region
-    pub server_region:  String,
+    pub server_region:  ServerRegion,
+    /// Transport carrying the tunnel
+    pub transport:      TransportMode,
     /// Key exchange protocol
     pub key_exchange:   KeyExchangeProtocol,
     /// DNS leak protection
     pub dns_protection: bool,
+    /// DNS resolvers used for leak protection
+    pub dns_servers:    Vec<IpAddr>,
     /// Split tunneling enabled
     pub split_tunnel:   bool,
+    /// Tunnel MTU in bytes
+    pub mtu:            u16,
+}
+
+/// Value constraint of a configuration field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldConstraint {
+    /// `"true"` or `"false"`.
+    Boolean,
+    /// One of the listed options.
+    OneOf(Vec<&'static str>),
+    /// Comma-separated IP addresses.
+    IpList,
+    /// Whole number within the inclusive bounds.
+    Integer {
+        /// Smallest accepted value
+        min: u64,
+        /// Largest accepted value
+        max: u64,
+    },
+}
+
+/// Configuration field metadata together with its current value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFieldView {
+    /// Configuration key
+    pub key:         &'static str,
+    /// Display label
+    pub label:       &'static str,
+    /// Help text
+    pub description: &'static str,
+    /// Settings group
+    pub group:       &'static str,
+    /// Current value, as reported by `get_current_config`
+    pub value:       String,
+    /// Accepted values
+    pub constraint:  FieldConstraint,
+}
+
+/// Static description of a configuration field.
+struct FieldSpec {
+    key:         &'static str,
+    label:       &'static str,
+    description: &'static str,
+    group:       &'static str,
+    kind:        FieldKind,
+}
+
+/// Widget kind of a configuration field.
+enum FieldKind {
+    Toggle(bool),
+    Select(&'static [&'static str]),
+    IpList(&'static [IpAddr]),
+    Number {
+        default: u16,
+        min:     u16,
+        max:     u16,
+    },
+}
+
+/// Format addresses the way the `dns_servers` field displays them.
+fn format_ip_list(ips: &[IpAddr]) -> String {
+    let ips: Vec<String> = ips.iter().map(ToString::to_string).collect();
+    ips.join(", ")
+}
+
+/// Error for enabling DNS protection with no resolvers, worded like
+/// `VpnConfig::validate_dns`.
+const NO_DNS_SERVERS: &str = "dns_leak_protection requires at least one DNS server";
+
+/// Options of the `server_region` select, generated from
+/// [`ServerRegion::ALL`] so they cannot drift from the enum.
+const SERVER_REGIONS: [&str; ServerRegion::ALL.len()] = {
+    let mut names = [""; ServerRegion::ALL.len()];
+    let mut i = 0;
+    while i < names.len() {
+        names[i] = ServerRegion::ALL[i].as_str();
+        i += 1;
+    }
+    names
+};
+
+/// Every configuration field, in display order.
+///
+/// Single source for the schema, the current config and the field views.
+const CONFIG_FIELDS: &[FieldSpec] = &[
+    FieldSpec {
+        key:         "kill_switch",
+        label:       "Kill Switch",
+        description: "Block all traffic if VPN disconnects",
+        group:       "Security",
+        kind:        FieldKind::Toggle(true),
+    },
+    FieldSpec {
+        key:         "dns_protection",
+        label:       "DNS Leak Protection",
+        description: "Prevent DNS queries outside VPN tunnel",
+        group:       "Security",
+        kind:        FieldKind::Toggle(true),
+    },
+    FieldSpec {
+        key:         "dns_servers",
+        label:       "DNS Servers",
+        description: "Comma-separated resolvers used for DNS leak protection",
+        group:       "Security",
+        kind:        FieldKind::IpList(&DEFAULT_DNS_SERVERS),
+    },
+    FieldSpec {
+        key:         "key_exchange",
+        label:       "Key Exchange Protocol",
+        description: "Post-quantum key exchange algorithm",
+        group:       "Security",
+        kind:        FieldKind::Select(&["ml_kem", "hybrid_ml_kem", "x25519"]),
+    },
+    FieldSpec {
+        key:         "auto_connect",
+        label:       "Auto-Connect",
+        description: "Connect automatically on application start",
+        group:       "Connection",
+        kind:        FieldKind::Toggle(false),
+    },
+    FieldSpec {
+        key:         "server_region",
+        label:       "Server Region",
+        description: "Preferred server region for connection",
+        group:       "Connection",
+        kind:        FieldKind::Select(&SERVER_REGIONS),
+    },
+    FieldSpec {
+        key:         "transport",
+        label:       "Transport",
+        description: "Disguise tunnel traffic on restrictive networks",
+        group:       "Connection",
+        kind:        FieldKind::Select(&["standard", "obfuscated_tls", "websocket"]),
+    },
+    FieldSpec {
+        key:         "split_tunnel",
+        label:       "Split Tunneling",
+        description: "Allow some apps to bypass VPN",
+        group:       "Advanced",
+        kind:        FieldKind::Toggle(false),
+    },
+    FieldSpec {
+        key:         "mtu",
+        label:       "MTU",
+        description: "Largest tunnel packet in bytes; lower it if connections stall",
+        group:       "Advanced",
+        kind:        FieldKind::Number {
+            default: DEFAULT_MTU,
+            min:     *MTU_RANGE.start(),
+            max:     *MTU_RANGE.end(),
+        },
+    },
+];
+
+impl FieldSpec {
+    fn to_config_field(&self) -> ConfigField {
+        let field = match self.kind {
+            FieldKind::Toggle(default) => ConfigField::toggle(self.key, self.label, default),
+            FieldKind::Select(options) => ConfigField::select(
+                self.key,
+                self.label,
+                options.iter().map(|o| String::from(*o)).collect(),
+            ),
+            FieldKind::IpList(default) => {
+                ConfigField::text(self.key, self.label, &format_ip_list(default))
+            },
+            FieldKind::Number { default, min, max } => ConfigField::number(
+                self.key,
+                self.label,
+                f64::from(default),
+                f64::from(min),
+                f64::from(max),
+            ),
+        };
+        field.with_description(self.description).with_group(self.group)
+    }
+
+    fn constraint(&self) -> FieldConstraint {
+        match self.kind {
+            FieldKind::Toggle(_) => FieldConstraint::Boolean,
+            FieldKind::Select(options) => FieldConstraint::OneOf(options.to_vec()),
+            FieldKind::IpList(_) => FieldConstraint::IpList,
+            FieldKind::Number { min, max, .. } => {
+                FieldConstraint::Integer { min: u64::from(min), max: u64::from(max) }
+            },
+        }
+    }
 }
 
 /// Connection state for streaming updates.
@@ -73,19 +300,150 @@ impl ConnectionState {
     }
 }
 
+/// Map the plugin's tunnel state to the panel's coarser state, so
+/// [`VpnPluginFlexForge::set_connection_state`] can be fed
+/// `VpnPlugin::state` directly.
+///
+/// `KeyExchange` is part of connecting. `Disconnecting` stays `Connected`:
+/// the tunnel still carries draining traffic and the kill switch is still
+/// engaged until teardown finishes. `Standby` is `Disconnected`, since a
+/// standby tunnel carries no traffic until promoted.
+impl From<TunnelState> for ConnectionState {
+    fn from(state: TunnelState) -> Self {
+        match state {
+            TunnelState::Disconnected | TunnelState::Standby => Self::Disconnected,
+            TunnelState::Connecting | TunnelState::KeyExchange => Self::Connecting,
+            TunnelState::Connected | TunnelState::Disconnecting => Self::Connected,
+            TunnelState::Reconnecting => Self::Reconnecting,
+            TunnelState::Error => Self::Error,
+        }
+    }
+}
+
 impl Default for VpnUiConfig {
     fn default() -> Self {
         Self {
             kill_switch:    true,
             auto_connect:   false,
-            server_region:  String::from("auto"),
+            server_region:  ServerRegion::Auto,
+            transport:      TransportMode::Standard,
             key_exchange:   KeyExchangeProtocol::MlKem,
             dns_protection: true,
+            dns_servers:    DEFAULT_DNS_SERVERS.to_vec(),
             split_tunnel:   false,
+            mtu:            DEFAULT_MTU,
         }
     }
 }
 
+impl VpnUiConfig {
+    /// Check if the selected settings are post-quantum safe.
+    ///
+    /// The panel does not expose the cipher, which is always
+    /// `Aes256GcmPqc`, so this depends only on the key exchange: `MlKem` and
+    /// `HybridMlKem` are quantum-safe, `X25519` is classical. See
+    /// [`VpnConfig::is_quantum_safe`](crate::VpnConfig::is_quantum_safe).
+    #[must_use]
+    pub fn is_quantum_safe(&self) -> bool {
+        self.key_exchange.is_post_quantum()
+    }
+
+    /// Read a single configuration value by key.
+    fn get_field(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "kill_switch" => self.kill_switch.to_string(),
+            "dns_protection" => self.dns_protection.to_string(),
+            "dns_servers" => format_ip_list(&self.dns_servers),
+            "key_exchange" => self.key_exchange.as_str().to_string(),
+            "auto_connect" => self.auto_connect.to_string(),
+            "server_region" => self.server_region.as_str().to_string(),
+            "transport" => self.transport.as_str().to_string(),
+            "split_tunnel" => self.split_tunnel.to_string(),
+            "mtu" => self.mtu.to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Check the settings that span fields, once every change is applied.
+    fn validate(&self) -> Result<(), String> {
+        if self.dns_protection && self.dns_servers.is_empty() {
+            return Err(NO_DNS_SERVERS.to_string());
+        }
+        Ok(())
+    }
+
+    /// Apply a single configuration value by key.
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "kill_switch" => {
+                self.kill_switch = value == "true";
+                Ok(())
+            },
+            "dns_protection" => {
+                self.dns_protection = value == "true";
+                Ok(())
+            },
+            "dns_servers" => {
+                self.dns_servers = parse_ip_list(value)?;
+                Ok(())
+            },
+            "key_exchange" => {
+                self.key_exchange = match value {
+                    "ml_kem" => KeyExchangeProtocol::MlKem,
+                    "hybrid_ml_kem" => KeyExchangeProtocol::HybridMlKem,
+                    "x25519" => KeyExchangeProtocol::X25519,
+                    _ => return Err(format!("Unknown key exchange: {value}")),
+                };
+                Ok(())
+            },
+            "auto_connect" => {
+                self.auto_connect = value == "true";
+                Ok(())
+            },
+            "server_region" => {
+                self.server_region = value
+                    .parse::<ServerRegion>()
+                    .map_err(|_| format!("Unknown server region: {value}"))?;
+                Ok(())
+            },
+            "transport" => {
+                self.transport = value
+                    .parse::<TransportMode>()
+                    .map_err(|_| format!("Unknown transport: {value}"))?;
+                Ok(())
+            },
+            "split_tunnel" => {
+                self.split_tunnel = value == "true";
+                Ok(())
+            },
+            "mtu" => {
+                let mtu = value.trim().parse().map_err(|_| format!("Invalid MTU: {value}"))?;
+                if !MTU_RANGE.contains(&mtu) {
+                    return Err(format!(
+                        "MTU must be between {} and {}",
+                        MTU_RANGE.start(),
+                        MTU_RANGE.end()
+                    ));
+                }
+                self.mtu = mtu;
+                Ok(())
+            },
+            _ => Err(format!("Unknown configuration key: {key}")),
+        }
+    }
+}
+
+/// Parse a comma-separated list of IP addresses, ignoring blank entries.
+fn parse_ip_list(value: &str) -> Result<Vec<IpAddr>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| entry.parse().map_err(|_| format!("Invalid DNS server: {entry}")))
+        .collect()
+}
+
 impl VpnPluginFlexForge {
     /// Creates a new FlexForge integration wrapper.
     #[must_use]
@@ -96,6 +454,15 @@ impl VpnPluginFlexForge {
             stream_id:        None,
             next_id:          1,
             connection_state: ConnectionState::Disconnected,
+            label:            None,
+            immediate_frame:  false,
+            security_level:   None,
+            stats:            ConnectionStats::default(),
+            server_id:        None,
+            last_frame:       StatusFrame {
+                state: ConnectionState::Disconnected.as_str().to_string(),
+                ..StatusFrame::default()
+            },
         }
     }
 
@@ -116,6 +483,39 @@ impl VpnPluginFlexForge {
         }
     }
 
+    /// Returns every configuration field with its metadata and current value.
+    #[must_use]
+    pub fn config_view(&self) -> Vec<ConfigFieldView> {
+        CONFIG_FIELDS
+            .iter()
+            .map(|spec| ConfigFieldView {
+                key:         spec.key,
+                label:       spec.label,
+                description: spec.description,
+                group:       spec.group,
+                value:       self.config.get_field(spec.key).unwrap_or_default(),
+                constraint:  spec.constraint(),
+            })
+            .collect()
+    }
+
+    /// Returns the entries where `other` differs from `get_current_config`.
+    ///
+    /// Entries of `other` whose value differs, or whose key is not a current
+    /// field, come first in `other`'s order. Current fields missing from
+    /// `other` follow with their current value.
+    #[must_use]
+    pub fn config_diff(&self, other: &[(String, String)]) -> Vec<(String, String)> {
+        let current = self.get_current_config();
+        let mut diff: Vec<(String, String)> = other
+            .iter()
+            .filter(|(key, value)| !current.iter().any(|(k, v)| k == key && v == value))
+            .cloned()
+            .collect();
+        diff.extend(current.into_iter().filter(|(key, _)| !other.iter().any(|(k, _)| k == key)));
+        diff
+    }
+
     /// Gets the current connection state.
     #[must_use]
     pub fn connection_state(&self) -> ConnectionState {
@@ -127,6 +527,69 @@ impl VpnPluginFlexForge {
         self.connection_state = state;
     }
 
+    /// Sets the live connection's security level (called by VPN core with
+    /// `VpnPlugin::chain_security_level`).
+    pub fn set_security_level(&mut self, level: Option<SecurityLevel>) {
+        self.security_level = level;
+    }
+
+    /// Returns the security badge text, or `None` when disconnected.
+    ///
+    /// For multi-hop chains this reflects the weakest hop, not the first.
+    #[must_use]
+    pub fn security_badge(&self) -> Option<&'static str> {
+        self.security_level.map(|level| match level {
+            SecurityLevel::Classical => "Classical",
+            SecurityLevel::PostQuantum => "Quantum-safe",
+            SecurityLevel::Hybrid => "Quantum-safe (hybrid)",
+        })
+    }
+
+    /// Gets the profile label.
+    #[must_use]
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Sets the profile label (mirrors `VpnConfig::label`).
+    pub fn set_label(&mut self, label: Option<String>) {
+        self.label = label;
+    }
+
+    /// Returns the dashboard title, including the profile label if set.
+    #[must_use]
+    pub fn dashboard_title(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{} \u{2014} {label}", self.display_name()),
+            None => self.display_name().to_string(),
+        }
+    }
+
+    /// Pushes the latest connection stats (called by VPN core).
+    pub fn update_stats(&mut self, stats: &ConnectionStats) {
+        self.stats = stats.clone();
+    }
+
+    /// Sets the server of the live connection (called by VPN core).
+    pub fn set_server_id(&mut self, server_id: Option<String>) {
+        self.server_id = server_id;
+    }
+
+    /// Returns the frame produced by the last successful render.
+    #[must_use]
+    pub fn latest_frame(&self) -> StatusFrame {
+        self.last_frame.clone()
+    }
+
+    /// Requests that the next refresh emits a status frame immediately.
+    ///
+    /// Use after user actions such as connect so the UI updates without
+    /// waiting for the 5 fps cadence. Cleared by the next refresh or render,
+    /// whether or not a status stream is active, so a request never lingers.
+    pub fn request_immediate_frame(&mut self) {
+        self.immediate_frame = true;
+    }
+
     fn next_stream_id(&mut self) -> u64 {
         let id = self.next_id;
         self.next_id = self.next_id.wrapping_add(1);
@@ -181,8 +644,9 @@ impl FlexForgeIntegration for VpnPluginFlexForge {
     }
 
     fn on_refresh(&mut self) -> bool {
-        // Always refresh when streaming connection status
-        self.stream_active
+        // Always refresh when streaming connection status or on request
+        let requested = std::mem::take(&mut self.immediate_frame);
+        self.stream_active || requested
     }
 }
 
@@ -192,122 +656,36 @@ impl FlexForgeIntegration for VpnPluginFlexForge {
 
 impl UiConfigurable for VpnPluginFlexForge {
     fn config_schema(&self) -> ConfigSchema {
-        ConfigSchema::new()
-            .with_field(
-                ConfigField::toggle("kill_switch", "Kill Switch", true)
-                    .with_description("Block all traffic if VPN disconnects")
-                    .with_group("Security"),
-            )
-            .with_field(
-                ConfigField::toggle("dns_protection", "DNS Leak Protection", true)
-                    .with_description("Prevent DNS queries outside VPN tunnel")
-                    .with_group("Security"),
-            )
-            .with_field(
-                ConfigField::select("key_exchange", "Key Exchange Protocol", vec![
-                    String::from("ml_kem"),
-                    String::from("hybrid_ml_kem"),
-                    String::from("x25519"),
-                ])
-                .with_description("Post-quantum key exchange algorithm")
-                .with_group("Security"),
-            )
-            .with_field(
-                ConfigField::toggle("auto_connect", "Auto-Connect", false)
-                    .with_description("Connect automatically on application start")
-                    .with_group("Connection"),
-            )
-            .with_field(
-                ConfigField::select("server_region", "Server Region", vec![
-                    String::from("auto"),
-                    String::from("us-east"),
-                    String::from("us-west"),
-                    String::from("eu-west"),
-                    String::from("eu-central"),
-                    String::from("asia-pacific"),
-                ])
-                .with_description("Preferred server region for connection")
-                .with_group("Connection"),
-            )
-            .with_field(
-                ConfigField::toggle("split_tunnel", "Split Tunneling", false)
-                    .with_description("Allow some apps to bypass VPN")
-                    .with_group("Advanced"),
-            )
+        CONFIG_FIELDS.iter().fold(ConfigSchema::new(), |schema, spec| {
+            schema.with_field(spec.to_config_field())
+        })
     }
 
     fn on_config_changed(&mut self, key: &str, value: &str) -> Result<(), String> {
-        match key {
-            "kill_switch" => {
-                self.config.kill_switch = value == "true";
-                Ok(())
-            },
-            "dns_protection" => {
-                self.config.dns_protection = value == "true";
-                Ok(())
-            },
-            "key_exchange" => {
-                self.config.key_exchange = match value {
-                    "ml_kem" => KeyExchangeProtocol::MlKem,
-                    "hybrid_ml_kem" => KeyExchangeProtocol::HybridMlKem,
-                    "x25519" => KeyExchangeProtocol::X25519,
-                    _ => return Err(format!("Unknown key exchange: {value}")),
-                };
-                Ok(())
-            },
-            "auto_connect" => {
-                self.config.auto_connect = value == "true";
-                Ok(())
-            },
-            "server_region" => {
-                self.config.server_region = value.to_string();
-                Ok(())
-            },
-            "split_tunnel" => {
-                self.config.split_tunnel = value == "true";
-                Ok(())
-            },
-            _ => Err(format!("Unknown configuration key: {key}")),
-        }
+        self.apply_config(&[(key.to_string(), value.to_string())])
     }
 
     fn apply_config(&mut self, config: &[(String, String)]) -> Result<(), String> {
+        // Stage every change on a copy so a bad entry leaves the config untouched
+        let mut staged = self.config.clone();
         for (key, value) in config {
-            self.on_config_changed(key, value)?;
+            staged.set_field(key, value)?;
         }
+        staged.validate()?;
+        self.config = staged;
         Ok(())
     }
 
     fn get_current_config(&self) -> Vec<(String, String)> {
-        let key_exchange_str = match self.config.key_exchange {
-            KeyExchangeProtocol::MlKem => "ml_kem",
-            KeyExchangeProtocol::HybridMlKem => "hybrid_ml_kem",
-            KeyExchangeProtocol::X25519 => "x25519",
-        };
-
-        vec![
-            (
-                String::from("kill_switch"),
-                self.config.kill_switch.to_string(),
-            ),
-            (
-                String::from("dns_protection"),
-                self.config.dns_protection.to_string(),
-            ),
-            (String::from("key_exchange"), key_exchange_str.to_string()),
-            (
-                String::from("auto_connect"),
-                self.config.auto_connect.to_string(),
-            ),
-            (
-                String::from("server_region"),
-                self.config.server_region.clone(),
-            ),
-            (
-                String::from("split_tunnel"),
-                self.config.split_tunnel.to_string(),
-            ),
-        ]
+        CONFIG_FIELDS
+            .iter()
+            .map(|spec| {
+                (
+                    spec.key.to_string(),
+                    self.config.get_field(spec.key).unwrap_or_default(),
+                )
+            })
+            .collect()
     }
 
     fn reset_to_defaults(&mut self) {
@@ -357,12 +735,18 @@ impl StreamingCapable for VpnPluginFlexForge {
     }
 
     fn render_frame(&mut self, stream_id: u64, _delta_ms: f64) -> bool {
+        self.immediate_frame = false;
         if !self.stream_active || self.stream_id != Some(stream_id) {
             return false;
         }
 
-        // Emit status frame with connection state, bandwidth, etc.
-        // In production, this would serialize to ERSP status frame
+        self.last_frame = StatusFrame {
+            state:         self.connection_state.as_str().to_string(),
+            send_rate_bps: self.stats.send_rate_bps,
+            recv_rate_bps: self.stats.recv_rate_bps,
+            latency_ms:    self.stats.avg_latency(),
+            server_id:     self.server_id.clone(),
+        };
         true
     }
 }
@@ -406,4 +790,277 @@ mod tests {
         plugin.stop_stream(stream_id).expect("Should stop streaming");
         assert!(!plugin.is_streaming());
     }
+
+    #[test]
+    fn test_apply_config_rolls_back_on_error() {
+        let mut plugin = VpnPluginFlexForge::new();
+        let before = plugin.get_current_config();
+
+        let result = plugin.apply_config(&[
+            (String::from("kill_switch"), String::from("false")),
+            (String::from("auto_connect"), String::from("true")),
+            (String::from("key_exchange"), String::from("rsa")),
+            (String::from("split_tunnel"), String::from("true")),
+        ]);
+
+        assert!(result.is_err());
+        assert_eq!(plugin.get_current_config(), before);
+    }
+
+    #[test]
+    fn test_config_view_matches_current_config() {
+        let mut plugin = VpnPluginFlexForge::new();
+        plugin.on_config_changed("key_exchange", "x25519").expect("Should apply");
+
+        let view = plugin.config_view();
+        let current = plugin.get_current_config();
+        assert_eq!(view.len(), current.len());
+        for (field, (key, value)) in view.iter().zip(&current) {
+            assert_eq!(field.key, key);
+            assert_eq!(&field.value, value);
+        }
+
+        let key_exchange = view.iter().find(|f| f.key == "key_exchange").expect("Should exist");
+        assert_eq!(key_exchange.value, "x25519");
+        assert!(
+            matches!(&key_exchange.constraint, FieldConstraint::OneOf(o) if o.contains(&"ml_kem"))
+        );
+    }
+
+    #[test]
+    fn test_request_immediate_frame() {
+        let mut plugin = VpnPluginFlexForge::new();
+        assert!(!plugin.on_refresh());
+
+        plugin.request_immediate_frame();
+        assert!(plugin.on_refresh());
+        assert!(!plugin.on_refresh(), "Request cleared by the refresh");
+
+        plugin.request_immediate_frame();
+        let stream_id = plugin.start_stream().expect("Should start streaming");
+        assert!(plugin.render_frame(stream_id, 0.0));
+        plugin.stop_stream(stream_id).expect("Should stop streaming");
+        assert!(!plugin.on_refresh(), "Request cleared by rendered frame");
+    }
+
+    #[test]
+    fn test_immediate_frame_cleared_without_stream() {
+        let mut plugin = VpnPluginFlexForge::new();
+        plugin.request_immediate_frame();
+        assert!(!plugin.render_frame(0, 0.0), "No stream to render into");
+        assert!(
+            !plugin.on_refresh(),
+            "Request cleared by the render attempt"
+        );
+    }
+
+    #[test]
+    fn test_dns_servers_field() {
+        let mut plugin = VpnPluginFlexForge::new();
+        assert_eq!(
+            plugin.config.get_field("dns_servers").as_deref(),
+            Some("1.1.1.1, 9.9.9.9")
+        );
+
+        plugin
+            .on_config_changed("dns_servers", " 8.8.8.8,2001:4860:4860::8888 , ")
+            .expect("Should parse");
+        assert_eq!(plugin.config.dns_servers.len(), 2);
+        assert_eq!(
+            plugin.config.get_field("dns_servers").as_deref(),
+            Some("8.8.8.8, 2001:4860:4860::8888")
+        );
+
+        let result = plugin.on_config_changed("dns_servers", "8.8.8.8, 8.8.4");
+        assert_eq!(result, Err(String::from("Invalid DNS server: 8.8.4")));
+        assert_eq!(plugin.config.dns_servers.len(), 2);
+
+        let no_servers = Err(String::from(
+            "dns_leak_protection requires at least one DNS server",
+        ));
+        assert_eq!(plugin.on_config_changed("dns_servers", " , "), no_servers);
+        assert_eq!(plugin.config.dns_servers.len(), 2);
+
+        plugin.on_config_changed("dns_protection", "false").expect("Should disable");
+        plugin.on_config_changed("dns_servers", "").expect("Should clear");
+        assert!(plugin.config.dns_servers.is_empty());
+        assert_eq!(
+            plugin.on_config_changed("dns_protection", "true"),
+            no_servers
+        );
+        assert!(!plugin.config.dns_protection);
+
+        plugin
+            .apply_config(&[
+                (String::from("dns_protection"), String::from("true")),
+                (String::from("dns_servers"), String::from("1.1.1.1")),
+            ])
+            .expect("Should apply servers with protection");
+        assert!(plugin.config.dns_protection);
+    }
+
+    #[test]
+    fn test_render_frame_reflects_pushed_stats() {
+        let mut plugin = VpnPluginFlexForge::new();
+        assert_eq!(plugin.latest_frame().state, "disconnected");
+
+        let mut stats = ConnectionStats::default();
+        stats.update(0, 0, 100);
+        stats.update(2_000, 8_000, 102);
+        stats.record_latency(30);
+        stats.record_latency(50);
+        plugin.update_stats(&stats);
+        plugin.set_connection_state(ConnectionState::Connected);
+        plugin.set_server_id(Some(String::from("srv-1")));
+        assert_eq!(
+            plugin.latest_frame().state,
+            "disconnected",
+            "Not rendered yet"
+        );
+
+        assert!(!plugin.render_frame(7, 0.0));
+        let stream_id = plugin.start_stream().expect("Should start streaming");
+        assert!(plugin.render_frame(stream_id, 200.0));
+        assert_eq!(plugin.latest_frame(), StatusFrame {
+            state:         String::from("connected"),
+            send_rate_bps: 1_000.0,
+            recv_rate_bps: 4_000.0,
+            latency_ms:    40,
+            server_id:     Some(String::from("srv-1")),
+        });
+    }
+
+    #[test]
+    fn test_server_region_validation() {
+        let mut plugin = VpnPluginFlexForge::new();
+        plugin
+            .on_config_changed("server_region", "eu-central")
+            .expect("Should accept region");
+        assert_eq!(plugin.config.server_region, ServerRegion::EuCentral);
+
+        plugin.on_config_changed("server_region", "auto").expect("Should accept auto");
+        assert_eq!(plugin.config.server_region, ServerRegion::Auto);
+
+        let result = plugin.on_config_changed("server_region", "us-esat");
+        assert_eq!(result, Err(String::from("Unknown server region: us-esat")));
+        assert_eq!(plugin.config.server_region, ServerRegion::Auto);
+    }
+
+    #[test]
+    fn test_server_region_round_trip() {
+        let spec = CONFIG_FIELDS
+            .iter()
+            .find(|f| f.key == "server_region")
+            .expect("Should find field");
+        let options: Vec<&str> = ServerRegion::ALL.iter().map(ServerRegion::as_str).collect();
+        assert_eq!(spec.constraint(), FieldConstraint::OneOf(options));
+
+        let mut plugin = VpnPluginFlexForge::new();
+        for region in ServerRegion::ALL {
+            plugin
+                .on_config_changed("server_region", region.as_str())
+                .expect("Should accept every variant");
+            assert_eq!(plugin.config.server_region, region);
+            assert_eq!(
+                plugin.config.get_field("server_region").as_deref(),
+                Some(region.as_str())
+            );
+            assert_eq!(region.as_str().parse::<ServerRegion>(), Ok(region));
+        }
+    }
+
+    #[test]
+    fn test_config_diff() {
+        let plugin = VpnPluginFlexForge::new();
+        let mut edited = plugin.get_current_config();
+        assert!(plugin.config_diff(&edited).is_empty());
+
+        for (key, value) in &mut edited {
+            match key.as_str() {
+                "kill_switch" => *value = String::from("false"),
+                "server_region" => *value = String::from("eu-west"),
+                _ => {},
+            }
+        }
+        edited.retain(|(key, _)| key != "split_tunnel");
+        edited.push((String::from("theme"), String::from("dark")));
+
+        let pair = |k: &str, v: &str| (k.to_string(), v.to_string());
+        assert_eq!(plugin.config_diff(&edited), [
+            pair("kill_switch", "false"),
+            pair("server_region", "eu-west"),
+            pair("theme", "dark"),
+            pair("split_tunnel", "false"),
+        ]);
+    }
+
+    #[test]
+    fn test_transport_field() {
+        let mut plugin = VpnPluginFlexForge::new();
+        assert_eq!(plugin.config.transport, TransportMode::Standard);
+
+        plugin
+            .on_config_changed("transport", "obfuscated_tls")
+            .expect("Should accept transport");
+        assert_eq!(plugin.config.transport, TransportMode::ObfuscatedTls);
+        assert!(
+            plugin
+                .get_current_config()
+                .contains(&(String::from("transport"), String::from("obfuscated_tls")))
+        );
+
+        let result = plugin.on_config_changed("transport", "quic");
+        assert_eq!(result, Err(String::from("Unknown transport: quic")));
+        assert_eq!(plugin.config.transport, TransportMode::ObfuscatedTls);
+    }
+
+    #[test]
+    fn test_mtu_field() {
+        let mut plugin = VpnPluginFlexForge::new();
+        assert_eq!(plugin.config.mtu, 1420);
+        let spec = CONFIG_FIELDS.iter().find(|f| f.key == "mtu").expect("Should find field");
+        assert_eq!(spec.constraint(), FieldConstraint::Integer {
+            min: 576,
+            max: 1500,
+        });
+
+        plugin.on_config_changed("mtu", "1280").expect("Should accept MTU");
+        assert_eq!(plugin.config.mtu, 1280);
+
+        for (value, error) in [
+            ("575", "MTU must be between 576 and 1500"),
+            ("1501", "MTU must be between 576 and 1500"),
+            ("jumbo", "Invalid MTU: jumbo"),
+        ] {
+            assert_eq!(
+                plugin.on_config_changed("mtu", value),
+                Err(String::from(error))
+            );
+        }
+        assert_eq!(plugin.config.mtu, 1280);
+    }
+
+    #[test]
+    fn test_connection_state_from_tunnel_state() {
+        let table = [
+            (TunnelState::Disconnected, ConnectionState::Disconnected),
+            (TunnelState::Connecting, ConnectionState::Connecting),
+            (TunnelState::KeyExchange, ConnectionState::Connecting),
+            (TunnelState::Connected, ConnectionState::Connected),
+            (TunnelState::Reconnecting, ConnectionState::Reconnecting),
+            (TunnelState::Disconnecting, ConnectionState::Connected),
+            (TunnelState::Error, ConnectionState::Error),
+            (TunnelState::Standby, ConnectionState::Disconnected),
+        ];
+        let mut plugin = VpnPluginFlexForge::new();
+        for (tunnel_state, expected) in table {
+            assert_eq!(
+                ConnectionState::from(tunnel_state),
+                expected,
+                "{tunnel_state}"
+            );
+            plugin.set_connection_state(tunnel_state.into());
+            assert_eq!(plugin.connection_state(), expected);
+        }
+    }
 }
@@ -0,0 +1,63 @@
+//! Reconnect backoff delays.
+
+use std::time::Duration;
+
+/// Delay policy between reconnect attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackoffStrategy {
+    /// Wait the same time before every attempt.
+    Fixed {
+        /// Delay (seconds).
+        delay_secs: u64,
+    },
+    /// Double the delay after every attempt, up to a cap.
+    Exponential {
+        /// Delay before the first attempt (seconds).
+        base_secs: u64,
+        /// Upper bound on any delay (seconds).
+        max_secs:  u64,
+    },
+}
+
+impl BackoffStrategy {
+    /// Get the delay before the zero-based `attempt`.
+    ///
+    /// Exponential delays are `base_secs * 2^attempt` clamped to `max_secs`;
+    /// the arithmetic saturates, so any `attempt` is safe.
+    #[must_use]
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        match *self {
+            Self::Fixed { delay_secs } => Duration::from_secs(delay_secs),
+            Self::Exponential { base_secs, max_secs } => {
+                let secs = 1u64
+                    .checked_shl(attempt)
+                    .and_then(|factor| base_secs.checked_mul(factor))
+                    .map_or(max_secs, |secs| secs.min(max_secs));
+                Duration::from_secs(secs)
+            },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exponential_delay_saturates() {
+        let backoff = BackoffStrategy::Exponential { base_secs: 5, max_secs: 300 };
+
+        assert_eq!(backoff.delay_for(0), Duration::from_secs(5));
+        assert_eq!(backoff.delay_for(3), Duration::from_secs(40));
+        for attempt in [6, 62, 63, 64, 200, u32::MAX] {
+            assert_eq!(backoff.delay_for(attempt), Duration::from_secs(300));
+        }
+
+        let unbounded = BackoffStrategy::Exponential { base_secs: 1, max_secs: u64::MAX };
+        assert_eq!(unbounded.delay_for(63), Duration::from_secs(1 << 63));
+        assert_eq!(unbounded.delay_for(u32::MAX), Duration::from_secs(u64::MAX));
+
+        let fixed = BackoffStrategy::Fixed { delay_secs: 5 };
+        assert_eq!(fixed.delay_for(u32::MAX), Duration::from_secs(5));
+    }
+}
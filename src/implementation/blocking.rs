@@ -0,0 +1,201 @@
+//! Async adapter for blocking connections.
+
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    traits::{AsyncVpnConnection, VpnConnection},
+    types::{TunnelState, VpnServer},
+};
+
+/// Runs a blocking [`VpnConnection`] behind [`AsyncVpnConnection`].
+///
+/// `connect` and `disconnect` run on a dedicated thread, so they never
+/// block the executor; the returned futures are runtime-agnostic. The
+/// status getters lock the connection and return immediately unless a
+/// call is still running on its thread.
+#[derive(Debug)]
+pub struct BlockingConnection<C> {
+    inner: Arc<Mutex<C>>,
+}
+
+impl<C: VpnConnection + 'static> BlockingConnection<C> {
+    /// Wrap a blocking connection.
+    #[must_use]
+    pub fn new(connection: C) -> Self {
+        Self { inner: Arc::new(Mutex::new(connection)) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, C> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `call` on the connection on its own thread.
+    fn run<T: Send + 'static>(
+        &self,
+        call: impl FnOnce(&mut C) -> VpnResult<T> + Send + 'static,
+    ) -> BlockingTask<T> {
+        let inner = Arc::clone(&self.inner);
+        spawn_blocking(move || call(&mut inner.lock().unwrap_or_else(PoisonError::into_inner)))
+    }
+}
+
+impl<C: VpnConnection + 'static> AsyncVpnConnection for BlockingConnection<C> {
+    fn connect(&mut self, server: &VpnServer) -> impl Future<Output = VpnResult<()>> + Send {
+        let server = server.clone();
+        self.run(move |connection| connection.connect(&server))
+    }
+
+    fn disconnect(&mut self) -> impl Future<Output = VpnResult<()>> + Send {
+        self.run(|connection| connection.disconnect())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.lock().is_connected()
+    }
+
+    fn connection_state(&self) -> TunnelState {
+        self.lock().connection_state()
+    }
+}
+
+/// Result slot shared between a blocking thread and its future.
+struct TaskState<T> {
+    result: Option<VpnResult<T>>,
+    waker:  Option<Waker>,
+}
+
+/// Future resolving to the result of a call run on its own thread.
+struct BlockingTask<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+}
+
+/// Run `call` on a new thread, reporting a panic as a connection error.
+fn spawn_blocking<T: Send + 'static>(
+    call: impl FnOnce() -> VpnResult<T> + Send + 'static,
+) -> BlockingTask<T> {
+    let state = Arc::new(Mutex::new(TaskState { result: None, waker: None }));
+    let shared = Arc::clone(&state);
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|_| {
+            Err(VpnError::Connection(
+                "Blocking connection call panicked".to_string(),
+            ))
+        });
+        let mut state = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+    BlockingTask { state }
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = VpnResult<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use std::{sync::mpsc, task::Wake, time::Duration};
+
+    use super::*;
+
+    /// Minimal executor: poll on this thread, parking until woken.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct ThreadWaker(thread::Thread);
+
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    /// Blocking connection that reports which thread served each call.
+    struct SlowConnection {
+        state:   TunnelState,
+        threads: mpsc::Sender<thread::ThreadId>,
+    }
+
+    impl VpnConnection for SlowConnection {
+        fn connect(&mut self, server: &VpnServer) -> VpnResult<()> {
+            let _ = self.threads.send(thread::current().id());
+            thread::sleep(Duration::from_millis(20));
+            if !server.pqc_enabled {
+                return Err(VpnError::Connection(format!("{} lacks PQC", server.id)));
+            }
+            self.state = TunnelState::Connected;
+            Ok(())
+        }
+
+        fn disconnect(&mut self) -> VpnResult<()> {
+            if self.state != TunnelState::Connected {
+                panic!("Not connected");
+            }
+            self.state = TunnelState::Disconnected;
+            Ok(())
+        }
+
+        fn is_connected(&self) -> bool {
+            self.state == TunnelState::Connected
+        }
+
+        fn connection_state(&self) -> TunnelState {
+            self.state
+        }
+    }
+
+    #[test]
+    fn test_blocking_connection_runs_off_thread() {
+        let (threads, calls) = mpsc::channel();
+        let mut connection =
+            BlockingConnection::new(SlowConnection { state: TunnelState::Disconnected, threads });
+        let server = VpnServer { id: String::from("srv-1"), ..VpnServer::default() };
+
+        assert_eq!(
+            block_on(connection.connect(&server)),
+            Err(VpnError::Connection(String::from("srv-1 lacks PQC")))
+        );
+        let server = VpnServer { pqc_enabled: true, ..server };
+        block_on(connection.connect(&server)).expect("Should connect");
+        assert!(connection.is_connected());
+        assert_eq!(connection.connection_state(), TunnelState::Connected);
+        assert!(calls.try_iter().all(|id| id != thread::current().id()));
+
+        block_on(connection.disconnect()).expect("Should disconnect");
+        assert!(!connection.is_connected());
+        assert!(matches!(
+            block_on(connection.disconnect()),
+            Err(VpnError::Connection(_))
+        ));
+    }
+}
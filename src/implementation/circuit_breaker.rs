@@ -0,0 +1,129 @@
+//! Reconnect circuit breaker.
+
+use std::collections::VecDeque;
+
+use crate::types::CircuitState;
+
+/// Circuit breaker guarding against rapid reconnect loops.
+///
+/// Trips open when more than `threshold` attempts are recorded within
+/// `window_secs`, rejects attempts for `cooldown_secs`, then allows a single
+/// trial attempt (half-open). A success closes the circuit again; another
+/// attempt before success re-opens it.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold:     u32,
+    window_secs:   u64,
+    cooldown_secs: u64,
+    attempts:      VecDeque<u64>,
+    opened_at:     Option<u64>,
+    trial_pending: bool,
+}
+
+impl CircuitBreaker {
+    /// Create a new closed circuit breaker.
+    #[must_use]
+    pub fn new(threshold: u32, window_secs: u64, cooldown_secs: u64) -> Self {
+        Self {
+            threshold,
+            window_secs,
+            cooldown_secs,
+            attempts: VecDeque::new(),
+            opened_at: None,
+            trial_pending: false,
+        }
+    }
+
+    /// Get the circuit state at `now_secs`.
+    #[must_use]
+    pub fn state(&self, now_secs: u64) -> CircuitState {
+        match self.opened_at {
+            Some(opened) if now_secs < opened.saturating_add(self.cooldown_secs) => {
+                CircuitState::Open
+            },
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed,
+        }
+    }
+
+    /// Check if an attempt may be made at `now_secs`.
+    #[must_use]
+    pub fn allows_attempt(&self, now_secs: u64) -> bool {
+        match self.state(now_secs) {
+            CircuitState::Closed => true,
+            CircuitState::HalfOpen => !self.trial_pending,
+            CircuitState::Open => false,
+        }
+    }
+
+    /// Record an attempt at `now_secs` and return the resulting state.
+    pub fn record_attempt(&mut self, now_secs: u64) -> CircuitState {
+        match self.state(now_secs) {
+            CircuitState::Closed => {
+                self.attempts.push_back(now_secs);
+                let window_start = now_secs.saturating_sub(self.window_secs);
+                while self.attempts.front().is_some_and(|&t| t < window_start) {
+                    self.attempts.pop_front();
+                }
+                if self.attempts.len() > self.threshold as usize {
+                    self.open(now_secs);
+                }
+            },
+            CircuitState::HalfOpen if self.trial_pending => self.open(now_secs),
+            CircuitState::HalfOpen => self.trial_pending = true,
+            CircuitState::Open => {},
+        }
+        self.state(now_secs)
+    }
+
+    /// Record a successful connection, closing the circuit.
+    pub fn record_success(&mut self) {
+        self.attempts.clear();
+        self.opened_at = None;
+        self.trial_pending = false;
+    }
+
+    fn open(&mut self, now_secs: u64) {
+        self.attempts.clear();
+        self.opened_at = Some(now_secs);
+        self.trial_pending = false;
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trips_after_threshold() {
+        let mut breaker = CircuitBreaker::new(3, 60, 300);
+        for t in 0..3 {
+            assert_eq!(breaker.record_attempt(t), CircuitState::Closed);
+        }
+        assert_eq!(breaker.record_attempt(3), CircuitState::Open);
+        assert!(!breaker.allows_attempt(100));
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let mut breaker = CircuitBreaker::new(2, 10, 300);
+        breaker.record_attempt(0);
+        breaker.record_attempt(5);
+        assert_eq!(breaker.record_attempt(20), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_trial() {
+        let mut breaker = CircuitBreaker::new(0, 60, 30);
+        assert_eq!(breaker.record_attempt(0), CircuitState::Open);
+        assert_eq!(breaker.state(30), CircuitState::HalfOpen);
+        assert!(breaker.allows_attempt(30));
+
+        breaker.record_attempt(30);
+        assert!(!breaker.allows_attempt(31));
+        assert_eq!(breaker.record_attempt(31), CircuitState::Open);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(32), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,56 @@
+//! Clock implementations.
+
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::traits::Clock;
+
+/// Wall clock backed by [`SystemTime`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Manually driven clock for deterministic tests.
+///
+/// Time only moves through [`advance`](Self::advance) or `sleep`, which
+/// returns immediately after advancing.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Default)]
+pub struct MockClock {
+    millis: AtomicU64,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl MockClock {
+    /// Create a mock clock starting at `start_millis`.
+    #[must_use]
+    pub fn new(start_millis: u64) -> Self {
+        Self { millis: AtomicU64::new(start_millis) }
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.millis.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
@@ -1,39 +1,1082 @@
 //! VPN plugin configuration.
 
-use crate::types::{EncryptionAlgorithm, KeyExchangeProtocol};
+use core::str::FromStr;
+use std::{
+    fmt::Write,
+    net::{IpAddr, Ipv4Addr},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    implementation::{
+        BackoffStrategy, json,
+        toml::{self, Value},
+    },
+    types::{
+        EncryptionAlgorithm, ExclusionReason, IpPreference, KeyExchangeProtocol,
+        KillSwitchExemptions, SplitTunnelRules, TransportMode, VpnServer,
+    },
+};
+
+/// Maximum length of a connection label, in characters.
+pub(crate) const MAX_LABEL_LEN: usize = 64;
+
+/// Longest accepted initial reconnect delay (seconds).
+pub(crate) const MAX_RECONNECT_DELAY_SECS: u64 = 3600;
+
+/// Accepted tunnel MTUs (bytes): the IPv4 minimum up to Ethernet.
+pub(crate) const MTU_RANGE: RangeInclusive<u16> = 576..=1500;
+
+/// Default tunnel MTU (bytes), leaving room for tunnel overhead on Ethernet.
+pub(crate) const DEFAULT_MTU: u16 = 1420;
+
+/// Resolvers used when DNS leak protection is on: Cloudflare and Quad9.
+pub(crate) const DEFAULT_DNS_SERVERS: [IpAddr; 2] = [
+    IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+    IpAddr::V4(Ipv4Addr::new(9, 9, 9, 9)),
+];
 
 /// Configuration for the VPN plugin.
 #[derive(Debug, Clone)]
 pub struct VpnConfig {
     /// Enable kill switch.
-    pub kill_switch:            bool,
+    pub kill_switch:              bool,
+    /// Abort connecting when the kill switch cannot be engaged; when unset
+    /// the kill switch is best-effort.
+    pub kill_switch_required:     bool,
+    /// Engage the kill switch when a DNS leak is detected while connected,
+    /// disconnecting if it cannot be engaged.
+    pub kill_on_dns_leak:         bool,
+    /// Traffic allowed past the engaged kill switch.
+    pub kill_switch_exemptions:   KillSwitchExemptions,
     /// Enable DNS leak protection.
-    pub dns_leak_protection:    bool,
+    pub dns_leak_protection:      bool,
+    /// Resolvers DNS traffic is forced through when leak protection is on.
+    pub dns_servers:              Vec<IpAddr>,
     /// Preferred encryption algorithm.
-    pub encryption:             EncryptionAlgorithm,
+    pub encryption:               EncryptionAlgorithm,
     /// Preferred key exchange protocol.
-    pub key_exchange:           KeyExchangeProtocol,
+    pub key_exchange:             KeyExchangeProtocol,
     /// Auto-reconnect on disconnect.
-    pub auto_reconnect:         bool,
+    pub auto_reconnect:           bool,
     /// Maximum reconnect attempts.
-    pub max_reconnect_attempts: u32,
+    pub max_reconnect_attempts:   u32,
     /// Reconnect delay (seconds).
-    pub reconnect_delay_secs:   u64,
+    pub reconnect_delay_secs:     u64,
+    /// Cap on the reconnect delay as it backs off (seconds).
+    pub reconnect_cap_secs:       u64,
+    /// Time allowed for a connect to reach `Connected` (seconds).
+    pub connect_timeout_secs:     u64,
+    /// Expected heartbeat interval (seconds); 0 disables liveness checks.
+    pub keepalive_interval_secs:  u64,
     /// Enable split tunneling.
-    pub split_tunneling:        bool,
+    pub split_tunneling:          bool,
+    /// Traffic included in or excluded from the tunnel when split.
+    pub split_tunnel_rules:       SplitTunnelRules,
+    /// Reconnects allowed within the circuit window before the breaker opens.
+    pub circuit_max_reconnects:   u32,
+    /// Circuit breaker sliding window (seconds).
+    pub circuit_window_secs:      u64,
+    /// Circuit breaker cooldown once open (seconds).
+    pub circuit_cooldown_secs:    u64,
+    /// Run the post-connect verifier after every connect.
+    pub verify_after_connect:     bool,
+    /// Record a redacted handshake trace for debugging key exchange.
+    pub debug_handshake:          bool,
+    /// Address family preference used when resolving servers.
+    pub ip_preference:            IpPreference,
+    /// Window for coalescing identical consecutive errors (seconds).
+    pub error_coalesce_secs:      u64,
+    /// Human-facing profile name, e.g. "Work VPN". Not used for routing.
+    pub label:                    Option<String>,
+    /// Upload rate (bytes/s) above which observers are alerted.
+    pub upload_alert_bps:         Option<f64>,
+    /// Download rate (bytes/s) above which observers are alerted.
+    pub download_alert_bps:       Option<f64>,
+    /// Packet loss ratio (0.0–1.0) above which the connection is degraded.
+    /// The builder and TOML loader clamp it into range.
+    pub packet_loss_threshold:    f32,
+    /// Reconnect when packet loss exceeds `packet_loss_threshold`.
+    pub reconnect_on_packet_loss: bool,
+    /// Only accept servers with post-quantum key exchange.
+    pub require_pqc:              bool,
+    /// Country codes servers must be in; empty allows any country.
+    pub allowed_countries:        Vec<String>,
+    /// Transport used to reach servers.
+    pub transport:                TransportMode,
+    /// Tunnel MTU (bytes), leaving room for PQC handshake overhead.
+    pub mtu:                      u16,
 }
 
 impl Default for VpnConfig {
     fn default() -> Self {
         Self {
-            kill_switch:            true,
-            dns_leak_protection:    true,
-            encryption:             EncryptionAlgorithm::Aes256GcmPqc,
-            key_exchange:           KeyExchangeProtocol::HybridMlKem,
-            auto_reconnect:         true,
-            max_reconnect_attempts: 5,
-            reconnect_delay_secs:   5,
-            split_tunneling:        false,
+            kill_switch:              true,
+            kill_switch_required:     true,
+            kill_on_dns_leak:         false,
+            kill_switch_exemptions:   KillSwitchExemptions::default(),
+            dns_leak_protection:      true,
+            dns_servers:              DEFAULT_DNS_SERVERS.to_vec(),
+            encryption:               EncryptionAlgorithm::Aes256GcmPqc,
+            key_exchange:             KeyExchangeProtocol::HybridMlKem,
+            auto_reconnect:           true,
+            max_reconnect_attempts:   5,
+            reconnect_delay_secs:     5,
+            reconnect_cap_secs:       300,
+            connect_timeout_secs:     30,
+            keepalive_interval_secs:  25,
+            split_tunneling:          false,
+            split_tunnel_rules:       SplitTunnelRules::default(),
+            circuit_max_reconnects:   10,
+            circuit_window_secs:      60,
+            circuit_cooldown_secs:    300,
+            verify_after_connect:     false,
+            debug_handshake:          false,
+            ip_preference:            IpPreference::PreferV4,
+            error_coalesce_secs:      30,
+            label:                    None,
+            upload_alert_bps:         None,
+            download_alert_bps:       None,
+            packet_loss_threshold:    0.05,
+            reconnect_on_packet_loss: false,
+            require_pqc:              true,
+            allowed_countries:        Vec::new(),
+            transport:                TransportMode::Standard,
+            mtu:                      DEFAULT_MTU,
+        }
+    }
+}
+
+impl VpnConfig {
+    /// Start building a configuration from the defaults.
+    #[must_use]
+    pub fn builder() -> VpnConfigBuilder {
+        VpnConfigBuilder::default()
+    }
+
+    /// Validate the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if the label is longer than 64
+    /// characters, auto-reconnect is enabled with zero attempts, the
+    /// reconnect delay exceeds an hour, the connect timeout is zero,
+    /// split-tunnel rules conflict, a
+    /// kill-switch exemption is invalid, or [`validate_dns`](Self::validate_dns)
+    /// fails.
+    pub fn validate(&self) -> VpnResult<()> {
+        if let Some(label) = &self.label
+            && label.chars().count() > MAX_LABEL_LEN
+        {
+            return Err(VpnError::Configuration(format!(
+                "label exceeds {MAX_LABEL_LEN} characters"
+            )));
+        }
+        if self.auto_reconnect && self.max_reconnect_attempts == 0 {
+            return Err(VpnError::Configuration(
+                "auto_reconnect requires max_reconnect_attempts > 0".to_string(),
+            ));
+        }
+        if self.reconnect_delay_secs > MAX_RECONNECT_DELAY_SECS {
+            return Err(VpnError::Configuration(format!(
+                "reconnect_delay_secs exceeds {MAX_RECONNECT_DELAY_SECS}"
+            )));
+        }
+        if self.connect_timeout_secs == 0 {
+            return Err(VpnError::Configuration(
+                "connect_timeout_secs must be at least 1".to_string(),
+            ));
+        }
+        if self.packet_loss_threshold.is_nan() {
+            return Err(VpnError::Configuration(
+                "packet_loss_threshold must be a number".to_string(),
+            ));
+        }
+        if !MTU_RANGE.contains(&self.mtu) {
+            return Err(VpnError::Configuration(format!(
+                "mtu must be between {} and {}",
+                MTU_RANGE.start(),
+                MTU_RANGE.end()
+            )));
+        }
+        if let Err(conflicts) = self.split_tunnel_rules.validate() {
+            let details: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+            return Err(VpnError::Configuration(format!(
+                "Conflicting split-tunnel rules: {}",
+                details.join("; ")
+            )));
+        }
+        self.kill_switch_exemptions.validate()?;
+        self.validate_dns()
+    }
+
+    /// Validate the DNS settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if DNS leak protection is enabled
+    /// with no resolvers to force queries through.
+    pub fn validate_dns(&self) -> VpnResult<()> {
+        if self.dns_leak_protection && self.dns_servers.is_empty() {
+            return Err(VpnError::Configuration(
+                "dns_leak_protection requires at least one DNS server".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that a server conforms to this configuration's policy.
+    ///
+    /// A server is rejected if PQC is required and it lacks it, its country
+    /// is not in `allowed_countries` (when non-empty), it advertises cipher
+    /// or key exchange lists that omit the configured ones, it cannot carry
+    /// the configured transport. Empty capability lists are treated as
+    /// unknown and accepted. Load is left to the router's scoring.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` naming the first failed check.
+    pub fn accepts_server(&self, server: &VpnServer) -> VpnResult<()> {
+        self.exclusion_reason(server)
+            .map_or(Ok(()), |reason| Err(self.rejection(server, reason)))
+    }
+
+    /// Check that a server supports the configured encryption, key exchange
+    /// and transport. Empty capability lists are treated as unknown and
+    /// accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` naming the unsupported algorithm or
+    /// transport.
+    pub fn check_protocol_support(&self, server: &VpnServer) -> VpnResult<()> {
+        self.protocol_mismatch(server)
+            .map_or(Ok(()), |reason| Err(self.rejection(server, reason)))
+    }
+
+    fn protocol_mismatch(&self, server: &VpnServer) -> Option<ExclusionReason> {
+        if !server.supports_encryption(self.encryption) {
+            return Some(ExclusionReason::UnsupportedEncryption);
+        }
+        if !server.supports_key_exchange(self.key_exchange) {
+            return Some(ExclusionReason::UnsupportedKeyExchange);
+        }
+        if !server.supports_transport(&self.transport) {
+            return Some(ExclusionReason::UnsupportedTransport);
+        }
+        None
+    }
+
+    pub(crate) fn rejection(&self, server: &VpnServer, reason: ExclusionReason) -> VpnError {
+        let detail = match reason {
+            ExclusionReason::NonPqc => "PQC not supported".to_string(),
+            ExclusionReason::Blocklisted => "server is blocklisted".to_string(),
+            ExclusionReason::Maintenance => "server is in maintenance".to_string(),
+            ExclusionReason::Unreachable => "server is unreachable".to_string(),
+            ExclusionReason::CountryRestricted => {
+                format!("country {} not allowed", server.country)
+            },
+            ExclusionReason::UnsupportedEncryption => {
+                format!("encryption {} not supported", self.encryption.as_str())
+            },
+            ExclusionReason::UnsupportedKeyExchange => {
+                format!("key exchange {} not supported", self.key_exchange.as_str())
+            },
+            ExclusionReason::UnsupportedTransport => {
+                format!("transport {} not supported", self.transport.as_str())
+            },
+            ExclusionReason::Overloaded => "server is at its connection cap".to_string(),
+            ExclusionReason::Cooldown => "server is cooling down".to_string(),
+        };
+        VpnError::Configuration(format!("Server {} rejected: {detail}", server.id))
+    }
+
+    /// Get the first policy check `server` fails, in the order documented
+    /// on [`accepts_server`](Self::accepts_server).
+    #[must_use]
+    pub fn exclusion_reason(&self, server: &VpnServer) -> Option<ExclusionReason> {
+        if self.require_pqc && !server.pqc_enabled {
+            return Some(ExclusionReason::NonPqc);
+        }
+        if !self.allowed_countries.is_empty()
+            && !self.allowed_countries.iter().any(|c| c.eq_ignore_ascii_case(&server.country))
+        {
+            return Some(ExclusionReason::CountryRestricted);
+        }
+        self.protocol_mismatch(server)
+    }
+
+    /// Check if the configuration is post-quantum safe.
+    ///
+    /// True only when both halves are post-quantum or hybrid:
+    ///
+    /// | `encryption`                     | `key_exchange`          | Result    |
+    /// |----------------------------------|-------------------------|-----------|
+    /// | `Aes256GcmPqc`                   | `MlKem` / `HybridMlKem` | safe      |
+    /// | `Aes256GcmPqc`                   | `X25519`                | classical |
+    /// | `Aes256Gcm` / `ChaCha20Poly1305` | any                     | classical |
+    #[must_use]
+    pub fn is_quantum_safe(&self) -> bool {
+        self.encryption.is_post_quantum() && self.key_exchange.is_post_quantum()
+    }
+
+    /// Get the reconnect backoff: doubling from `reconnect_delay_secs` up to
+    /// `reconnect_cap_secs` (or the base delay if the cap is lower).
+    #[must_use]
+    pub fn reconnect_backoff(&self) -> BackoffStrategy {
+        BackoffStrategy::Exponential {
+            base_secs: self.reconnect_delay_secs,
+            max_secs:  self.reconnect_cap_secs.max(self.reconnect_delay_secs),
+        }
+    }
+
+    /// Choose the encryption to use with `server`.
+    ///
+    /// Precedence, highest first:
+    ///
+    /// 1. The server's `preferred_encryption`, if the server lists it in
+    ///    `supported_encryption` (an empty list counts as supporting it) and
+    ///    it is post-quantum whenever `require_pqc` is set.
+    /// 2. The configured `encryption`.
+    #[must_use]
+    pub fn encryption_for(&self, server: &VpnServer) -> EncryptionAlgorithm {
+        server
+            .preferred_encryption
+            .filter(|&e| server.supports_encryption(e))
+            .filter(|e| !self.require_pqc || e.is_post_quantum())
+            .unwrap_or(self.encryption)
+    }
+
+    /// Names of the fields whose values differ between `self` and `other`.
+    ///
+    /// Keys are returned in declaration order and use the field names.
+    #[must_use]
+    pub fn changed_keys(&self, other: &Self) -> Vec<&'static str> {
+        let mut keys: Vec<&'static str> = Vec::new();
+        for ((key, ours), (_, theirs)) in self.fields().into_iter().zip(other.fields()) {
+            // Dotted keys report the field they belong to
+            let name = key.split('.').next().unwrap_or(key);
+            if ours != theirs && keys.last() != Some(&name) {
+                keys.push(name);
+            }
+        }
+        keys
+    }
+
+    /// Every setting as a `(key, value)` pair in declaration order.
+    ///
+    /// Values are TOML literals that are also valid JSON; `None` marks an
+    /// unset optional field. Split-tunnel rules and kill-switch exemptions
+    /// use dotted keys. This single list backs [`to_toml`](Self::to_toml),
+    /// [`changed_keys`](Self::changed_keys) and the diagnostics export, so
+    /// a new field only needs adding here.
+    pub(crate) fn fields(&self) -> Vec<(&'static str, Option<String>)> {
+        let mut fields = Vec::new();
+        let mut field = |key, value| fields.push((key, value));
+        field("kill_switch", Some(self.kill_switch.to_string()));
+        field(
+            "kill_switch_required",
+            Some(self.kill_switch_required.to_string()),
+        );
+        field("kill_on_dns_leak", Some(self.kill_on_dns_leak.to_string()));
+        field(
+            "kill_switch_exemptions.cidrs",
+            Some(toml_array(&self.kill_switch_exemptions.cidrs)),
+        );
+        field(
+            "kill_switch_exemptions.processes",
+            Some(toml_array(&self.kill_switch_exemptions.processes)),
+        );
+        field(
+            "dns_leak_protection",
+            Some(self.dns_leak_protection.to_string()),
+        );
+        field("dns_servers", Some(toml_array(&self.dns_servers)));
+        field("encryption", Some(json::string(self.encryption.as_str())));
+        field(
+            "key_exchange",
+            Some(json::string(self.key_exchange.as_str())),
+        );
+        field("auto_reconnect", Some(self.auto_reconnect.to_string()));
+        field(
+            "max_reconnect_attempts",
+            Some(self.max_reconnect_attempts.to_string()),
+        );
+        field(
+            "reconnect_delay_secs",
+            Some(self.reconnect_delay_secs.to_string()),
+        );
+        field(
+            "reconnect_cap_secs",
+            Some(self.reconnect_cap_secs.to_string()),
+        );
+        field(
+            "connect_timeout_secs",
+            Some(self.connect_timeout_secs.to_string()),
+        );
+        field(
+            "keepalive_interval_secs",
+            Some(self.keepalive_interval_secs.to_string()),
+        );
+        field("split_tunneling", Some(self.split_tunneling.to_string()));
+        field(
+            "split_tunnel_rules.include",
+            Some(toml_array(&self.split_tunnel_rules.include)),
+        );
+        field(
+            "split_tunnel_rules.exclude",
+            Some(toml_array(&self.split_tunnel_rules.exclude)),
+        );
+        field(
+            "circuit_max_reconnects",
+            Some(self.circuit_max_reconnects.to_string()),
+        );
+        field(
+            "circuit_window_secs",
+            Some(self.circuit_window_secs.to_string()),
+        );
+        field(
+            "circuit_cooldown_secs",
+            Some(self.circuit_cooldown_secs.to_string()),
+        );
+        field(
+            "verify_after_connect",
+            Some(self.verify_after_connect.to_string()),
+        );
+        field("debug_handshake", Some(self.debug_handshake.to_string()));
+        field(
+            "ip_preference",
+            Some(json::string(self.ip_preference.as_str())),
+        );
+        field(
+            "error_coalesce_secs",
+            Some(self.error_coalesce_secs.to_string()),
+        );
+        field("label", self.label.as_deref().map(json::string));
+        field(
+            "upload_alert_bps",
+            self.upload_alert_bps.map(|bps| format!("{bps:?}")),
+        );
+        field(
+            "download_alert_bps",
+            self.download_alert_bps.map(|bps| format!("{bps:?}")),
+        );
+        field(
+            "packet_loss_threshold",
+            Some(format!("{:?}", self.packet_loss_threshold)),
+        );
+        field(
+            "reconnect_on_packet_loss",
+            Some(self.reconnect_on_packet_loss.to_string()),
+        );
+        field("require_pqc", Some(self.require_pqc.to_string()));
+        field(
+            "allowed_countries",
+            Some(toml_array(&self.allowed_countries)),
+        );
+        field("transport", Some(json::string(self.transport.as_str())));
+        field("mtu", Some(self.mtu.to_string()));
+        fields
+    }
+
+    /// Serialize to TOML, one `key = value` line per field in declaration
+    /// order.
+    ///
+    /// Enums use their snake_case identifiers, split-tunnel rules and
+    /// kill-switch exemptions are written as dotted keys, and unset optional
+    /// fields are omitted.
+    #[must_use]
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in self.fields() {
+            if let Some(value) = value {
+                let _ = writeln!(out, "{key} = {value}");
+            }
+        }
+        out
+    }
+
+    /// Parse a TOML document written by [`to_toml`](Self::to_toml) or by
+    /// hand.
+    ///
+    /// Keys may appear in any order, under `[table]` headers or as dotted
+    /// keys; missing keys keep their default. The result is validated.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` on a syntax error, an unknown key,
+    /// a value of the wrong type, or a config that fails
+    /// [`validate`](Self::validate).
+    pub fn from_toml(input: &str) -> VpnResult<Self> {
+        let mut config = Self::default();
+        for (key, value) in toml::parse(input)? {
+            let key = key.as_str();
+            match key {
+                "kill_switch" => config.kill_switch = toml_bool(key, &value)?,
+                "kill_switch_required" => config.kill_switch_required = toml_bool(key, &value)?,
+                "kill_on_dns_leak" => config.kill_on_dns_leak = toml_bool(key, &value)?,
+                "kill_switch_exemptions.cidrs" => {
+                    config.kill_switch_exemptions.cidrs = toml_parsed_list(key, &value)?;
+                },
+                "kill_switch_exemptions.processes" => {
+                    config.kill_switch_exemptions.processes = toml_strings(key, &value)?;
+                },
+                "dns_leak_protection" => config.dns_leak_protection = toml_bool(key, &value)?,
+                "dns_servers" => config.dns_servers = toml_parsed_list(key, &value)?,
+                "encryption" => config.encryption = toml_parsed(key, &value)?,
+                "key_exchange" => config.key_exchange = toml_parsed(key, &value)?,
+                "auto_reconnect" => config.auto_reconnect = toml_bool(key, &value)?,
+                "max_reconnect_attempts" => {
+                    config.max_reconnect_attempts = toml_integer(key, &value)?;
+                },
+                "reconnect_delay_secs" => config.reconnect_delay_secs = toml_integer(key, &value)?,
+                "reconnect_cap_secs" => config.reconnect_cap_secs = toml_integer(key, &value)?,
+                "connect_timeout_secs" => config.connect_timeout_secs = toml_integer(key, &value)?,
+                "keepalive_interval_secs" => {
+                    config.keepalive_interval_secs = toml_integer(key, &value)?;
+                },
+                "split_tunneling" => config.split_tunneling = toml_bool(key, &value)?,
+                "split_tunnel_rules.include" => {
+                    config.split_tunnel_rules.include = toml_parsed_list(key, &value)?;
+                },
+                "split_tunnel_rules.exclude" => {
+                    config.split_tunnel_rules.exclude = toml_parsed_list(key, &value)?;
+                },
+                "circuit_max_reconnects" => {
+                    config.circuit_max_reconnects = toml_integer(key, &value)?;
+                },
+                "circuit_window_secs" => config.circuit_window_secs = toml_integer(key, &value)?,
+                "circuit_cooldown_secs" => {
+                    config.circuit_cooldown_secs = toml_integer(key, &value)?;
+                },
+                "verify_after_connect" => config.verify_after_connect = toml_bool(key, &value)?,
+                "debug_handshake" => config.debug_handshake = toml_bool(key, &value)?,
+                "ip_preference" => config.ip_preference = toml_parsed(key, &value)?,
+                "error_coalesce_secs" => config.error_coalesce_secs = toml_integer(key, &value)?,
+                "label" => config.label = Some(toml_string(key, &value)?),
+                "upload_alert_bps" => config.upload_alert_bps = Some(toml_float(key, &value)?),
+                "download_alert_bps" => config.download_alert_bps = Some(toml_float(key, &value)?),
+                "packet_loss_threshold" => {
+                    config.packet_loss_threshold =
+                        (toml_float(key, &value)? as f32).clamp(0.0, 1.0);
+                },
+                "reconnect_on_packet_loss" => {
+                    config.reconnect_on_packet_loss = toml_bool(key, &value)?;
+                },
+                "require_pqc" => config.require_pqc = toml_bool(key, &value)?,
+                "allowed_countries" => config.allowed_countries = toml_strings(key, &value)?,
+                "transport" => config.transport = toml_parsed(key, &value)?,
+                "mtu" => config.mtu = toml_integer(key, &value)?,
+                _ => {
+                    return Err(VpnError::Configuration(format!(
+                        "Unknown config key `{key}`"
+                    )));
+                },
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Fluent builder for [`VpnConfig`], validated on [`build`](Self::build).
+///
+/// Fields without a setter keep their default.
+#[derive(Debug, Clone, Default)]
+pub struct VpnConfigBuilder {
+    config: VpnConfig,
+}
+
+impl VpnConfigBuilder {
+    /// Enable the kill switch.
+    #[must_use]
+    pub fn kill_switch(mut self, kill_switch: bool) -> Self {
+        self.config.kill_switch = kill_switch;
+        self
+    }
+
+    /// Abort connecting when the kill switch cannot be engaged.
+    #[must_use]
+    pub fn kill_switch_required(mut self, kill_switch_required: bool) -> Self {
+        self.config.kill_switch_required = kill_switch_required;
+        self
+    }
+
+    /// Enable DNS leak protection.
+    #[must_use]
+    pub fn dns_leak_protection(mut self, dns_leak_protection: bool) -> Self {
+        self.config.dns_leak_protection = dns_leak_protection;
+        self
+    }
+
+    /// Set the resolvers DNS traffic is forced through.
+    #[must_use]
+    pub fn dns_servers(mut self, dns_servers: Vec<IpAddr>) -> Self {
+        self.config.dns_servers = dns_servers;
+        self
+    }
+
+    /// Set the preferred encryption algorithm.
+    #[must_use]
+    pub fn encryption(mut self, encryption: EncryptionAlgorithm) -> Self {
+        self.config.encryption = encryption;
+        self
+    }
+
+    /// Set the preferred key exchange protocol.
+    #[must_use]
+    pub fn key_exchange(mut self, key_exchange: KeyExchangeProtocol) -> Self {
+        self.config.key_exchange = key_exchange;
+        self
+    }
+
+    /// Auto-reconnect on disconnect.
+    #[must_use]
+    pub fn auto_reconnect(mut self, auto_reconnect: bool) -> Self {
+        self.config.auto_reconnect = auto_reconnect;
+        self
+    }
+
+    /// Set the maximum reconnect attempts.
+    #[must_use]
+    pub fn max_reconnect_attempts(mut self, max_reconnect_attempts: u32) -> Self {
+        self.config.max_reconnect_attempts = max_reconnect_attempts;
+        self
+    }
+
+    /// Set the initial reconnect delay (seconds).
+    #[must_use]
+    pub fn reconnect_delay_secs(mut self, reconnect_delay_secs: u64) -> Self {
+        self.config.reconnect_delay_secs = reconnect_delay_secs;
+        self
+    }
+
+    /// Set the cap on the reconnect delay (seconds).
+    #[must_use]
+    pub fn reconnect_cap_secs(mut self, reconnect_cap_secs: u64) -> Self {
+        self.config.reconnect_cap_secs = reconnect_cap_secs;
+        self
+    }
+
+    /// Set the time allowed for a connect to complete (seconds).
+    #[must_use]
+    pub fn connect_timeout_secs(mut self, connect_timeout_secs: u64) -> Self {
+        self.config.connect_timeout_secs = connect_timeout_secs;
+        self
+    }
+
+    /// Set the expected heartbeat interval (seconds); 0 disables liveness
+    /// checks.
+    #[must_use]
+    pub fn keepalive_interval_secs(mut self, keepalive_interval_secs: u64) -> Self {
+        self.config.keepalive_interval_secs = keepalive_interval_secs;
+        self
+    }
+
+    /// Enable split tunneling.
+    #[must_use]
+    pub fn split_tunneling(mut self, split_tunneling: bool) -> Self {
+        self.config.split_tunneling = split_tunneling;
+        self
+    }
+
+    /// Set the split-tunnel rules.
+    #[must_use]
+    pub fn split_tunnel_rules(mut self, split_tunnel_rules: SplitTunnelRules) -> Self {
+        self.config.split_tunnel_rules = split_tunnel_rules;
+        self
+    }
+
+    /// Run the post-connect verifier after every connect.
+    #[must_use]
+    pub fn verify_after_connect(mut self, verify_after_connect: bool) -> Self {
+        self.config.verify_after_connect = verify_after_connect;
+        self
+    }
+
+    /// Set the address family preference.
+    #[must_use]
+    pub fn ip_preference(mut self, ip_preference: IpPreference) -> Self {
+        self.config.ip_preference = ip_preference;
+        self
+    }
+
+    /// Only accept servers with post-quantum key exchange.
+    #[must_use]
+    pub fn require_pqc(mut self, require_pqc: bool) -> Self {
+        self.config.require_pqc = require_pqc;
+        self
+    }
+
+    /// Restrict servers to these country codes.
+    #[must_use]
+    pub fn allowed_countries(mut self, allowed_countries: Vec<String>) -> Self {
+        self.config.allowed_countries = allowed_countries;
+        self
+    }
+
+    /// Set the transport used to reach servers.
+    #[must_use]
+    pub fn transport(mut self, transport: TransportMode) -> Self {
+        self.config.transport = transport;
+        self
+    }
+
+    /// Set the packet loss ratio above which the connection is degraded,
+    /// clamped to 0.0–1.0.
+    #[must_use]
+    pub fn packet_loss_threshold(mut self, packet_loss_threshold: f32) -> Self {
+        self.config.packet_loss_threshold = packet_loss_threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Reconnect when packet loss exceeds the threshold.
+    #[must_use]
+    pub fn reconnect_on_packet_loss(mut self, reconnect_on_packet_loss: bool) -> Self {
+        self.config.reconnect_on_packet_loss = reconnect_on_packet_loss;
+        self
+    }
+
+    /// Set the tunnel MTU (bytes).
+    #[must_use]
+    pub fn mtu(mut self, mtu: u16) -> Self {
+        self.config.mtu = mtu;
+        self
+    }
+
+    /// Set the human-facing profile name.
+    #[must_use]
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.config.label = Some(label.into());
+        self
+    }
+
+    /// Finish the configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if the configuration fails
+    /// [`VpnConfig::validate`].
+    pub fn build(self) -> VpnResult<VpnConfig> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Format items as a TOML array of their display strings.
+fn toml_array<T: ToString>(items: &[T]) -> String {
+    let items: Vec<String> = items.iter().map(|i| json::string(&i.to_string())).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Error for a config value of the wrong type or range.
+fn toml_type_error(key: &str, expected: &str, value: &Value) -> VpnError {
+    VpnError::Configuration(format!(
+        "Config key `{key}` expects {expected}, got {}",
+        value.type_name()
+    ))
+}
+
+fn toml_bool(key: &str, value: &Value) -> VpnResult<bool> {
+    match value {
+        Value::Bool(b) => Ok(*b),
+        _ => Err(toml_type_error(key, "a boolean", value)),
+    }
+}
+
+fn toml_integer<T: TryFrom<i64>>(key: &str, value: &Value) -> VpnResult<T> {
+    match value {
+        Value::Integer(i) => {
+            T::try_from(*i).map_err(|_| toml_type_error(key, "an integer in range", value))
+        },
+        _ => Err(toml_type_error(key, "an integer", value)),
+    }
+}
+
+fn toml_float(key: &str, value: &Value) -> VpnResult<f64> {
+    match value {
+        Value::Float(f) => Ok(*f),
+        Value::Integer(i) => Ok(*i as f64),
+        _ => Err(toml_type_error(key, "a number", value)),
+    }
+}
+
+fn toml_string(key: &str, value: &Value) -> VpnResult<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(toml_type_error(key, "a string", value)),
+    }
+}
+
+fn toml_parsed<T: FromStr>(key: &str, value: &Value) -> VpnResult<T>
+where
+    VpnError: From<T::Err>,
+{
+    Ok(toml_string(key, value)?.parse()?)
+}
+
+fn toml_strings(key: &str, value: &Value) -> VpnResult<Vec<String>> {
+    match value {
+        Value::Array(items) => items.iter().map(|item| toml_string(key, item)).collect(),
+        _ => Err(toml_type_error(key, "an array", value)),
+    }
+}
+
+fn toml_parsed_list<T: FromStr>(key: &str, value: &Value) -> VpnResult<Vec<T>>
+where
+    VpnError: From<T::Err>,
+{
+    toml_strings(key, value)?.iter().map(|s| Ok(s.parse()?)).collect()
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::types::SplitTunnelRule;
+
+    #[test]
+    fn test_changed_keys() {
+        let base = VpnConfig::default();
+        assert!(base.changed_keys(&base.clone()).is_empty());
+
+        let updated = VpnConfig {
+            kill_switch: false,
+            key_exchange: KeyExchangeProtocol::X25519,
+            ..VpnConfig::default()
+        };
+        assert_eq!(base.changed_keys(&updated), vec![
+            "kill_switch",
+            "key_exchange"
+        ]);
+    }
+
+    #[test]
+    fn test_is_quantum_safe() {
+        assert!(VpnConfig::default().is_quantum_safe());
+
+        let classical_kex = VpnConfig {
+            key_exchange: KeyExchangeProtocol::X25519,
+            ..VpnConfig::default()
+        };
+        assert!(!classical_kex.is_quantum_safe());
+
+        let classical_cipher = VpnConfig {
+            encryption: EncryptionAlgorithm::Aes256Gcm,
+            ..VpnConfig::default()
+        };
+        assert!(!classical_cipher.is_quantum_safe());
+    }
+
+    #[test]
+    fn test_validate_label() {
+        let mut config = VpnConfig { label: Some("Work VPN".into()), ..VpnConfig::default() };
+        assert!(config.validate().is_ok());
+
+        config.label = Some("x".repeat(MAX_LABEL_LEN + 1));
+        assert!(matches!(config.validate(), Err(VpnError::Configuration(_))));
+    }
+
+    #[test]
+    fn test_validate_split_tunnel_conflicts() {
+        let mut config = VpnConfig::default();
+        let lan = SplitTunnelRule::Cidr("10.0.0.0/8".parse().expect("Should parse"));
+        config.split_tunnel_rules.include.push(lan.clone());
+        assert!(config.validate().is_ok());
+
+        config.split_tunnel_rules.exclude.push(lan);
+        assert!(matches!(config.validate(), Err(VpnError::Configuration(_))));
+    }
+
+    #[test]
+    fn test_accepts_server() {
+        let config = VpnConfig { allowed_countries: vec!["DE".into()], ..VpnConfig::default() };
+        let server = VpnServer {
+            id: "srv-1".into(),
+            country: "DE".into(),
+            load: 0.3,
+            pqc_enabled: true,
+            ..VpnServer::default()
+        };
+        assert!(config.accepts_server(&server).is_ok());
+
+        let rejected = [
+            VpnServer { pqc_enabled: false, ..server.clone() },
+            VpnServer { country: "US".into(), ..server.clone() },
+            VpnServer {
+                supported_key_exchange: vec![KeyExchangeProtocol::X25519],
+                ..server.clone()
+            },
+        ];
+        for server in &rejected {
+            assert!(matches!(
+                config.accepts_server(server),
+                Err(VpnError::Configuration(_))
+            ));
         }
+        assert!(config.accepts_server(&VpnServer { load: 1.0, ..server }).is_ok());
+    }
+
+    #[test]
+    fn test_encryption_for_server_preference() {
+        let config = VpnConfig::default();
+        let mut server = VpnServer {
+            preferred_encryption: Some(EncryptionAlgorithm::ChaCha20Poly1305),
+            ..VpnServer::default()
+        };
+        // Classical preference is ignored while PQC is required
+        assert_eq!(
+            config.encryption_for(&server),
+            EncryptionAlgorithm::Aes256GcmPqc
+        );
+
+        let relaxed = VpnConfig {
+            require_pqc: false,
+            encryption: EncryptionAlgorithm::Aes256Gcm,
+            ..VpnConfig::default()
+        };
+        assert_eq!(
+            relaxed.encryption_for(&server),
+            EncryptionAlgorithm::ChaCha20Poly1305
+        );
+
+        server.supported_encryption = vec![EncryptionAlgorithm::Aes256Gcm];
+        assert_eq!(
+            relaxed.encryption_for(&server),
+            EncryptionAlgorithm::Aes256Gcm
+        );
+
+        server.preferred_encryption = None;
+        assert_eq!(
+            relaxed.encryption_for(&server),
+            EncryptionAlgorithm::Aes256Gcm
+        );
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let default = VpnConfig::default();
+        let parsed = VpnConfig::from_toml(&default.to_toml()).expect("Should parse");
+        assert!(default.changed_keys(&parsed).is_empty());
+
+        let custom = VpnConfig {
+            key_exchange: KeyExchangeProtocol::MlKem,
+            ip_preference: IpPreference::V6Only,
+            label: Some(String::from("Work \"VPN\"")),
+            upload_alert_bps: Some(1.5e6),
+            allowed_countries: vec![String::from("DE")],
+            split_tunnel_rules: SplitTunnelRules {
+                include: vec![SplitTunnelRule::App(String::from("com.example.mail"))],
+                exclude: vec![SplitTunnelRule::Cidr(
+                    "192.168.0.0/16".parse().expect("Should parse"),
+                )],
+            },
+            ..VpnConfig::default()
+        };
+        let toml = custom.to_toml();
+        assert!(toml.contains("key_exchange = \"ml_kem\""));
+        let parsed = VpnConfig::from_toml(&toml).expect("Should parse");
+        assert!(custom.changed_keys(&parsed).is_empty());
+
+        let partial = VpnConfig::from_toml("[split_tunnel_rules]\nexclude = [\"10.0.0.0/8\"]")
+            .expect("Should parse");
+        assert_eq!(partial.changed_keys(&default), ["split_tunnel_rules"]);
+        assert!(VpnConfig::from_toml("kill_swtich = false").is_err());
+        assert!(VpnConfig::from_toml("max_reconnect_attempts = -1").is_err());
+        assert!(VpnConfig::from_toml("key_exchange = \"rsa\"").is_err());
+    }
+
+    #[test]
+    fn test_builder_validates() {
+        let config = VpnConfig::builder()
+            .kill_switch(false)
+            .key_exchange(KeyExchangeProtocol::MlKem)
+            .max_reconnect_attempts(3)
+            .label("Home")
+            .build()
+            .expect("Should build");
+        assert_eq!(config.changed_keys(&VpnConfig::default()), [
+            "kill_switch",
+            "key_exchange",
+            "max_reconnect_attempts",
+            "label",
+        ]);
+
+        let zero_attempts = VpnConfig::builder().max_reconnect_attempts(0).build();
+        assert!(matches!(zero_attempts, Err(VpnError::Configuration(_))));
+        assert!(
+            VpnConfig::builder()
+                .auto_reconnect(false)
+                .max_reconnect_attempts(0)
+                .build()
+                .is_ok()
+        );
+        assert!(
+            VpnConfig::builder()
+                .reconnect_delay_secs(MAX_RECONNECT_DELAY_SECS + 1)
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_builder_validates_mtu() {
+        assert_eq!(VpnConfig::default().mtu, 1420);
+        for mtu in [576, 1280, 1500] {
+            assert_eq!(
+                VpnConfig::builder().mtu(mtu).build().map(|c| c.mtu),
+                Ok(mtu)
+            );
+        }
+
+        let out_of_range = Err(VpnError::Configuration(
+            "mtu must be between 576 and 1500".to_string(),
+        ));
+        assert_eq!(
+            VpnConfig::builder().mtu(575).build().map(|c| c.mtu),
+            out_of_range
+        );
+        assert_eq!(
+            VpnConfig::builder().mtu(1501).build().map(|c| c.mtu),
+            out_of_range
+        );
+        assert!(VpnConfig::from_toml("mtu = 70000").is_err());
+    }
+
+    #[test]
+    fn test_packet_loss_threshold_is_clamped() {
+        for (threshold, clamped) in [(0.05, 0.05), (1.5, 1.0), (-0.1, 0.0)] {
+            let config = VpnConfig::builder()
+                .packet_loss_threshold(threshold)
+                .build()
+                .expect("Should build");
+            assert_eq!(config.packet_loss_threshold, clamped);
+        }
+        let config = VpnConfig::from_toml("packet_loss_threshold = 1.5").expect("Should parse");
+        assert_eq!(config.packet_loss_threshold, 1.0);
+        assert!(matches!(
+            VpnConfig::builder().packet_loss_threshold(f32::NAN).build(),
+            Err(VpnError::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_dns() {
+        let config = VpnConfig::from_toml("dns_servers = [\"1.1.1.1\", \"2606:4700:4700::1111\"]")
+            .expect("Should parse");
+        assert_eq!(config.dns_servers, [
+            IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)),
+            "2606:4700:4700::1111".parse::<IpAddr>().expect("Should parse"),
+        ]);
+        assert!(matches!(
+            VpnConfig::from_toml("dns_servers = [\"1.1.1\"]"),
+            Err(VpnError::Configuration(_))
+        ));
+
+        let mut config = VpnConfig { dns_servers: Vec::new(), ..VpnConfig::default() };
+        assert!(config.validate_dns().is_err());
+        assert!(config.validate().is_err());
+        config.dns_leak_protection = false;
+        assert!(config.validate_dns().is_ok());
     }
 }
@@ -0,0 +1,242 @@
+//! Configuration persistence.
+//!
+//! [`FileConfigStore`] keeps the serialized config (e.g.
+//! [`VpnConfig::to_toml`](crate::VpnConfig::to_toml)) in a file, and
+//! [`EncryptedConfigStore`] wraps any store so the bytes it holds are
+//! encrypted and authenticated with the AES-256-GCM from `essentia_pqc`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use essentia_pqc::{aead::Aes256Gcm, rng};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    traits::ConfigStore,
+};
+
+/// Magic bytes opening an encrypted config.
+const MAGIC: &[u8; 7] = b"EVPNCFG";
+
+/// Version of the encrypted format: magic, version, nonce, ciphertext, tag.
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of the magic and version header.
+const HEADER_LEN: usize = MAGIC.len() + 1;
+
+/// Required length of the encryption key in bytes.
+const KEY_LEN: usize = Aes256Gcm::KEY_LEN;
+
+/// Store that keeps the config in a single file.
+#[derive(Debug, Clone)]
+pub struct FileConfigStore {
+    path: PathBuf,
+}
+
+impl FileConfigStore {
+    /// Create a store backed by the file at `path`.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Get the path of the backing file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ConfigStore for FileConfigStore {
+    fn load(&self) -> VpnResult<Option<Vec<u8>>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(VpnError::Configuration(format!("config read failed: {e}"))),
+        }
+    }
+
+    /// Write to a sibling temporary file, then rename it over the config so
+    /// a crash never leaves a half-written file behind.
+    fn save(&self, bytes: &[u8]) -> VpnResult<()> {
+        let mut staging = self.path.clone().into_os_string();
+        staging.push(".tmp");
+        fs::write(&staging, bytes)
+            .and_then(|()| fs::rename(&staging, &self.path))
+            .map_err(|e| VpnError::Configuration(format!("config write failed: {e}")))
+    }
+}
+
+/// Store that encrypts the config before handing it to an inner store.
+///
+/// Each save seals the bytes with AES-256-GCM under a random nonce from
+/// the `essentia_pqc` RNG and writes `EVPNCFG`, a format version byte, the
+/// nonce, the ciphertext and the authentication tag. The header is bound
+/// as associated data. The key is supplied by the caller, e.g. from the
+/// platform keychain.
+pub struct EncryptedConfigStore<S: ConfigStore> {
+    inner:  S,
+    cipher: Aes256Gcm,
+}
+
+impl<S: ConfigStore> EncryptedConfigStore<S> {
+    /// Wrap `inner`, encrypting with the 32-byte `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if `key` is not 32 bytes.
+    pub fn new(inner: S, key: &[u8]) -> VpnResult<Self> {
+        if key.len() != KEY_LEN {
+            return Err(VpnError::Configuration(format!(
+                "config key must be {KEY_LEN} bytes"
+            )));
+        }
+        let cipher = Aes256Gcm::new(key)
+            .map_err(|e| VpnError::Configuration(format!("config key rejected: {e}")))?;
+        Ok(Self { inner, cipher })
+    }
+
+    /// Get the wrapped store.
+    #[must_use]
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: ConfigStore> ConfigStore for EncryptedConfigStore<S> {
+    /// Load and decrypt the config.
+    ///
+    /// A missing or garbled header, a wrong key and tampered contents all
+    /// fail with `VpnError::Configuration("config decrypt failed")`.
+    fn load(&self) -> VpnResult<Option<Vec<u8>>> {
+        let Some(stored) = self.inner.load()? else {
+            return Ok(None);
+        };
+        let decrypt_failed = || VpnError::Configuration("config decrypt failed".to_string());
+        if stored.len() < HEADER_LEN + Aes256Gcm::NONCE_LEN
+            || &stored[..MAGIC.len()] != MAGIC
+            || stored[MAGIC.len()] != FORMAT_VERSION
+        {
+            return Err(decrypt_failed());
+        }
+        let (header, rest) = stored.split_at(HEADER_LEN);
+        let (nonce, sealed) = rest.split_at(Aes256Gcm::NONCE_LEN);
+        self.cipher
+            .decrypt(nonce, header, sealed)
+            .map(Some)
+            .map_err(|_| decrypt_failed())
+    }
+
+    fn save(&self, bytes: &[u8]) -> VpnResult<()> {
+        let encrypt_failed = |e| VpnError::Configuration(format!("config encrypt failed: {e}"));
+        let mut nonce = [0; Aes256Gcm::NONCE_LEN];
+        rng::fill_bytes(&mut nonce).map_err(encrypt_failed)?;
+        let mut stored =
+            Vec::with_capacity(HEADER_LEN + nonce.len() + bytes.len() + Aes256Gcm::TAG_LEN);
+        stored.extend_from_slice(MAGIC);
+        stored.push(FORMAT_VERSION);
+        let sealed = self.cipher.encrypt(&nonce, &stored, bytes).map_err(encrypt_failed)?;
+        stored.extend_from_slice(&nonce);
+        stored.extend_from_slice(&sealed);
+        self.inner.save(&stored)
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use std::{process, sync::Mutex};
+
+    use super::*;
+
+    /// Store holding the last saved bytes in memory.
+    #[derive(Default)]
+    struct MemoryStore {
+        bytes: Mutex<Option<Vec<u8>>>,
+    }
+
+    impl MemoryStore {
+        fn stored(&self) -> Vec<u8> {
+            self.bytes
+                .lock()
+                .expect("Should lock store")
+                .clone()
+                .expect("Should hold bytes")
+        }
+
+        fn replace(&self, bytes: Vec<u8>) {
+            *self.bytes.lock().expect("Should lock store") = Some(bytes);
+        }
+    }
+
+    impl ConfigStore for MemoryStore {
+        fn load(&self) -> VpnResult<Option<Vec<u8>>> {
+            Ok(self.bytes.lock().expect("Should lock store").clone())
+        }
+
+        fn save(&self, bytes: &[u8]) -> VpnResult<()> {
+            self.replace(bytes.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let path = std::env::temp_dir().join(format!("essentia-vpn-{}.toml", process::id()));
+        let store = FileConfigStore::new(&path);
+        assert_eq!(store.load().expect("Should load"), None);
+
+        store.save(b"kill_switch = true\n").expect("Should save");
+        assert_eq!(
+            store.load().expect("Should load"),
+            Some(b"kill_switch = true\n".to_vec())
+        );
+        fs::remove_file(store.path()).expect("Should remove config");
+    }
+
+    #[test]
+    fn test_encrypted_store_round_trip() {
+        assert!(matches!(
+            EncryptedConfigStore::new(MemoryStore::default(), &[0; 16]),
+            Err(VpnError::Configuration(msg)) if msg == "config key must be 32 bytes"
+        ));
+
+        let store =
+            EncryptedConfigStore::new(MemoryStore::default(), &[7; 32]).expect("Should accept key");
+        assert_eq!(store.load().expect("Should load"), None);
+
+        let config = b"label = \"Work VPN\"\npsk = \"hunter2\"\n";
+        store.save(config).expect("Should save");
+        let first = store.inner().stored();
+        assert_eq!(&first[..HEADER_LEN], b"EVPNCFG\x01");
+        assert!(!first.windows(7).any(|w| w == b"hunter2"));
+        assert_eq!(store.load().expect("Should load"), Some(config.to_vec()));
+
+        store.save(config).expect("Should save");
+        assert_ne!(store.inner().stored(), first, "Fresh nonce per save");
+    }
+
+    #[test]
+    fn test_encrypted_store_rejects_tampering() {
+        let decrypt_failed = |result: VpnResult<Option<Vec<u8>>>| matches!(result, Err(VpnError::Configuration(msg)) if msg == "config decrypt failed");
+        let store =
+            EncryptedConfigStore::new(MemoryStore::default(), &[7; 32]).expect("Should accept key");
+        store.save(b"mtu = 1400\n").expect("Should save");
+        let stored = store.inner().stored();
+
+        let other_key =
+            EncryptedConfigStore::new(MemoryStore::default(), &[8; 32]).expect("Should accept key");
+        other_key.inner().replace(stored.clone());
+        assert!(decrypt_failed(other_key.load()));
+
+        for index in [0, MAGIC.len(), HEADER_LEN, stored.len() - 1] {
+            let mut tampered = stored.clone();
+            tampered[index] ^= 1;
+            store.inner().replace(tampered);
+            assert!(decrypt_failed(store.load()), "Byte {index} flipped");
+        }
+        store.inner().replace(b"mtu = 1400\n".to_vec());
+        assert!(decrypt_failed(store.load()));
+    }
+}
@@ -0,0 +1,103 @@
+//! Connection establishment timing statistics.
+
+use std::collections::VecDeque;
+
+/// Maximum number of connects kept for statistics.
+const SAMPLE_LIMIT: usize = 128;
+
+/// Summary statistics for one connect phase, in milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PhaseStats {
+    /// Number of samples.
+    pub count:   usize,
+    /// Fastest sample.
+    pub min_ms:  u64,
+    /// Slowest sample.
+    pub max_ms:  u64,
+    /// Mean of the samples.
+    pub mean_ms: f64,
+    /// 95th percentile (nearest rank).
+    pub p95_ms:  u64,
+}
+
+impl PhaseStats {
+    fn from_samples(samples: impl Iterator<Item = u64>) -> Self {
+        let mut sorted: Vec<u64> = samples.collect();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+        sorted.sort_unstable();
+
+        let count = sorted.len();
+        let sum: u64 = sorted.iter().sum();
+        let p95_rank = (count * 95).div_ceil(100).max(1);
+        Self {
+            count,
+            min_ms: sorted[0],
+            max_ms: sorted[count - 1],
+            mean_ms: sum as f64 / count as f64,
+            p95_ms: sorted[p95_rank - 1],
+        }
+    }
+}
+
+/// Connect-time statistics over recent successful connects.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ConnectMetrics {
+    /// Whole connect, from tunnel creation to `Connected`.
+    pub total:      PhaseStats,
+    /// Tunnel creation (`Connecting`).
+    pub connecting: PhaseStats,
+    /// Key exchange (`KeyExchange`).
+    pub handshake:  PhaseStats,
+}
+
+/// Bounded record of per-phase connect durations.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectTimings {
+    samples: VecDeque<(u64, u64)>,
+}
+
+impl ConnectTimings {
+    /// Record one successful connect's phase durations (ms).
+    pub(crate) fn record(&mut self, connecting_ms: u64, handshake_ms: u64) {
+        if self.samples.len() == SAMPLE_LIMIT {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((connecting_ms, handshake_ms));
+    }
+
+    /// Compute statistics over the recorded connects.
+    pub(crate) fn metrics(&self) -> ConnectMetrics {
+        ConnectMetrics {
+            total:      PhaseStats::from_samples(
+                self.samples.iter().map(|(c, h)| c.saturating_add(*h)),
+            ),
+            connecting: PhaseStats::from_samples(self.samples.iter().map(|(c, _)| *c)),
+            handshake:  PhaseStats::from_samples(self.samples.iter().map(|(_, h)| *h)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_statistics() {
+        let mut timings = ConnectTimings::default();
+        assert_eq!(timings.metrics().total.count, 0);
+
+        for ms in 1..=20 {
+            timings.record(ms, 10);
+        }
+        let metrics = timings.metrics();
+        assert_eq!(metrics.connecting.count, 20);
+        assert_eq!(metrics.connecting.min_ms, 1);
+        assert_eq!(metrics.connecting.max_ms, 20);
+        assert!((metrics.connecting.mean_ms - 10.5).abs() < f64::EPSILON);
+        assert_eq!(metrics.connecting.p95_ms, 19);
+        assert_eq!(metrics.handshake.p95_ms, 10);
+        assert_eq!(metrics.total.max_ms, 30);
+    }
+}
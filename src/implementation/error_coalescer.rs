@@ -0,0 +1,62 @@
+//! Coalescing of repeated errors for observers.
+
+use crate::errors::VpnError;
+
+/// Result of reporting an error to the coalescer.
+#[derive(Debug)]
+pub(crate) struct CoalescedReport {
+    /// Summary of a burst that just ended: the error and how often it occurred.
+    pub ended:   Option<(VpnError, u32)>,
+    /// Whether the reported error should be forwarded to observers.
+    pub forward: bool,
+}
+
+/// Suppresses identical consecutive errors within a time window.
+///
+/// The first error of a burst is forwarded; repeats within `window_secs` of
+/// the previous occurrence are counted instead. When the burst ends (a
+/// different error arrives, the window lapses, or [`finish`](Self::finish)
+/// is called) a summary with the total occurrence count is produced if the
+/// error repeated.
+#[derive(Debug)]
+pub(crate) struct ErrorCoalescer {
+    window_secs: u64,
+    burst:       Option<Burst>,
+}
+
+#[derive(Debug)]
+struct Burst {
+    error:   VpnError,
+    message: String,
+    last_at: u64,
+    count:   u32,
+}
+
+impl ErrorCoalescer {
+    /// Create a coalescer with the given window.
+    pub(crate) fn new(window_secs: u64) -> Self {
+        Self { window_secs, burst: None }
+    }
+
+    /// Report an error occurring at `now_secs`.
+    pub(crate) fn report(&mut self, error: &VpnError, now_secs: u64) -> CoalescedReport {
+        let message = error.to_string();
+        if let Some(ref mut burst) = self.burst
+            && burst.message == message
+            && now_secs.saturating_sub(burst.last_at) <= self.window_secs
+        {
+            burst.count = burst.count.saturating_add(1);
+            burst.last_at = now_secs;
+            return CoalescedReport { ended: None, forward: false };
+        }
+
+        let ended = self.finish();
+        self.burst = Some(Burst { error: error.clone(), message, last_at: now_secs, count: 1 });
+        CoalescedReport { ended, forward: true }
+    }
+
+    /// End the current burst, returning its summary if the error repeated.
+    pub(crate) fn finish(&mut self) -> Option<(VpnError, u32)> {
+        self.burst.take().filter(|b| b.count > 1).map(|b| (b.error, b.count))
+    }
+}
@@ -0,0 +1,299 @@
+//! Minimal JSON helpers (std-only).
+//!
+//! Writes string literals for hand-built output and reads documents into a
+//! [`Value`] tree. Numbers are read as `f64`.
+
+use std::str::CharIndices;
+
+use crate::errors::{VpnError, VpnResult};
+
+/// Deepest nesting of arrays and objects accepted by [`parse`].
+const MAX_DEPTH: usize = 64;
+
+/// Parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Describe the value's type for error messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Bool(_) => "boolean",
+            Self::Number(_) => "number",
+            Self::String(_) => "string",
+            Self::Array(_) => "array",
+            Self::Object(_) => "object",
+        }
+    }
+
+    /// Look up a member of an object. `None` for other values.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a complete JSON document.
+///
+/// # Errors
+///
+/// Returns `VpnError::Configuration` naming the byte offset of the first
+/// syntax error, or of the array or object nested deeper than 64 levels.
+pub(crate) fn parse(input: &str) -> VpnResult<Value> {
+    let mut parser = Parser { input, pos: 0, depth: 0 };
+    let value = parser.value();
+    parser.skip_whitespace();
+    match value {
+        Some(value) if parser.pos == input.len() => Ok(value),
+        _ => Err(VpnError::Configuration(format!(
+            "Invalid JSON at byte {}",
+            parser.pos
+        ))),
+    }
+}
+
+/// Quote and escape a string as a JSON string literal.
+pub(crate) fn string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Format an optional string as a JSON string literal or `null`.
+pub(crate) fn opt_string(value: Option<&str>) -> String {
+    value.map_or_else(|| String::from("null"), string)
+}
+
+/// Recursive-descent parser over a JSON document.
+struct Parser<'a> {
+    input: &'a str,
+    pos:   usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start_matches([' ', '\t', '\n', '\r']);
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        if rest.starts_with('"') {
+            return self.string().map(Value::String);
+        }
+        let (is_array, is_object) = (rest.starts_with('['), rest.starts_with('{'));
+        if is_array || is_object {
+            if self.depth == MAX_DEPTH {
+                return None;
+            }
+            self.depth += 1;
+            let value = if is_array {
+                self.array()
+            } else {
+                self.object()
+            };
+            self.depth -= 1;
+            return value;
+        }
+        for (word, value) in [
+            ("null", Value::Null),
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+        ] {
+            if rest.starts_with(word) {
+                self.pos += word.len();
+                return Some(value);
+            }
+        }
+
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let number = rest[..len].parse().ok().filter(|n: &f64| n.is_finite())?;
+        self.pos += len;
+        Some(Value::Number(number))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Some(out);
+                },
+                '\\' => {
+                    let (_, escape) = chars.next()?;
+                    out.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let unit = hex_unit(&mut chars)?;
+                            if (0xD800..0xDC00).contains(&unit) {
+                                // High surrogate: must be followed by an escaped low one
+                                if chars.next()?.1 != '\\' || chars.next()?.1 != 'u' {
+                                    return None;
+                                }
+                                let low = hex_unit(&mut chars)?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return None;
+                                }
+                                char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))?
+                            } else {
+                                char::from_u32(unit)?
+                            }
+                        },
+                        _ => return None,
+                    });
+                },
+                c if (c as u32) < 0x20 => return None,
+                c => out.push(c),
+            }
+        }
+        None
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if self.eat(']') {
+            return Some(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if !self.eat(',') {
+                return self.eat(']').then_some(Value::Array(items));
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        if self.eat('}') {
+            return Some(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if !self.rest().starts_with('"') {
+                return None;
+            }
+            let key = self.string()?;
+            if !self.eat(':') {
+                return None;
+            }
+            members.push((key, self.value()?));
+            if !self.eat(',') {
+                return self.eat('}').then_some(Value::Object(members));
+            }
+        }
+    }
+}
+
+/// Read the four hex digits of a `\u` escape as a UTF-16 code unit.
+fn hex_unit(chars: &mut CharIndices<'_>) -> Option<u32> {
+    (0..4).try_fold(0, |unit, _| Some(unit * 16 + chars.next()?.1.to_digit(16)?))
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_round_trips_strings() {
+        let value = parse(&format!(
+            "{{\"name\": {}, \"ports\": [443, -1.5e2], \"on\": true, \"x\": null}}",
+            string("Work \"VPN\"\n")
+        ))
+        .expect("Should parse");
+        assert_eq!(
+            value.get("name"),
+            Some(&Value::String(String::from("Work \"VPN\"\n")))
+        );
+        assert_eq!(
+            value.get("ports"),
+            Some(&Value::Array(vec![
+                Value::Number(443.0),
+                Value::Number(-150.0)
+            ]))
+        );
+        assert_eq!(value.get("on"), Some(&Value::Bool(true)));
+        assert_eq!(value.get("x"), Some(&Value::Null));
+
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("[1] 2").is_err());
+        assert!(parse("[1,]").is_err());
+    }
+
+    #[test]
+    fn test_parse_limits_nesting_depth() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(MAX_DEPTH + 1)).err(),
+            Some(VpnError::Configuration(String::from(
+                "Invalid JSON at byte 64"
+            )))
+        );
+        assert!(parse(&"{\"a\":".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_parse_combines_surrogate_pairs() {
+        assert_eq!(
+            parse("\"\\ud83d\\ude00 \\u00e9\"").expect("Should parse"),
+            Value::String(String::from("\u{1F600} \u{e9}"))
+        );
+        assert!(parse("\"\\ud83d\"").is_err());
+        assert!(parse("\"\\ud83d\\u0041\"").is_err());
+        assert!(parse("\"\\ude00\"").is_err());
+        assert!(parse("\"\\u+041\"").is_err());
+    }
+}
@@ -1,22 +1,258 @@
 //! Post-quantum cryptographic key exchange.
 
+use std::{
+    fmt,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use essentia_pqc::{kdf, rng};
+
 use crate::{
     errors::{VpnError, VpnResult},
-    types::KeyExchangeProtocol,
+    traits::Zeroize,
+    types::{KeyExchangeProtocol, MlKemVariant, Role, Zeroizing},
 };
 
+/// X25519 public key size in bytes.
+const X25519_KEY_LEN: usize = 32;
+
+/// Shared secret size in bytes.
+const SHARED_SECRET_LEN: usize = 32;
+
+/// Default lifetime of an exported session ticket.
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(3600);
+
+/// HKDF label for the resumption secret carried by a session ticket.
+const RESUMPTION_LABEL: &[u8] = b"essentia-vpn resumption";
+
+/// HKDF label for the shared secret of a resumed session.
+const RESUMED_SESSION_LABEL: &[u8] = b"essentia-vpn resumed session";
+
+/// Minimum length of the per-session resumption nonce in bytes.
+const MIN_RESUMPTION_NONCE_LEN: usize = 16;
+
+/// Length of the resumption nonce drawn by
+/// [`PqcKeyExchange::resume_session`].
+pub(crate) const RESUMPTION_NONCE_LEN: usize = 32;
+
+/// Step reached by a key exchange handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KexState {
+    /// Key pair generated; the message is the public key.
+    KeypairGenerated,
+    /// Shared secret encapsulated; the message is the ciphertext.
+    Encapsulated,
+    /// Shared secret decapsulated; the message is the received ciphertext.
+    Decapsulated,
+    /// A step was rejected.
+    Failed,
+    /// Key material wiped.
+    Cleared,
+}
+
+/// One recorded handshake step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// State reached.
+    pub state:       KexState,
+    /// Size of the message produced or consumed (bytes), if any.
+    pub message_len: Option<usize>,
+    /// Time since the trace started.
+    pub elapsed:     Duration,
+}
+
+/// Record of a handshake for debugging, without any key material.
+///
+/// Only states, message sizes and timings are kept, so a trace is safe to
+/// attach to bug reports.
+#[derive(Debug, Clone)]
+pub struct HandshakeTrace {
+    protocol: KeyExchangeProtocol,
+    role:     Role,
+    started:  Instant,
+    steps:    Vec<TraceStep>,
+}
+
+impl HandshakeTrace {
+    /// Start an empty trace now.
+    fn new(protocol: KeyExchangeProtocol, role: Role) -> Self {
+        Self { protocol, role, started: Instant::now(), steps: Vec::new() }
+    }
+
+    /// Get the negotiated protocol.
+    #[must_use]
+    pub fn protocol(&self) -> KeyExchangeProtocol {
+        self.protocol
+    }
+
+    /// Get the side of the handshake traced.
+    #[must_use]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Get the recorded steps (oldest first).
+    #[must_use]
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    /// Append a step stamped with the time since the trace started.
+    fn record(&mut self, state: KexState, message_len: Option<usize>) {
+        let elapsed = self.started.elapsed();
+        self.steps.push(TraceStep { state, message_len, elapsed });
+    }
+}
+
+/// Traffic keys derived from a shared secret by
+/// [`PqcKeyExchange::derive_keys`]. Zeroed when dropped.
+///
+/// Both sides derive the same material; the initiator's `send_key` is the
+/// responder's `recv_key` and vice versa.
+pub struct DerivedKeys {
+    /// Key for traffic this side sends.
+    pub send_key: [u8; 32],
+    /// Key for traffic this side receives.
+    pub recv_key: [u8; 32],
+    /// Salt mixed into per-packet nonces.
+    pub salt:     [u8; 16],
+}
+
+impl fmt::Debug for DerivedKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DerivedKeys(<redacted>)")
+    }
+}
+
+impl Drop for DerivedKeys {
+    fn drop(&mut self) {
+        self.send_key.zeroize();
+        self.recv_key.zeroize();
+        self.salt.zeroize();
+    }
+}
+
+/// Opaque resumption ticket for a completed key exchange.
+///
+/// Created by [`PqcKeyExchange::export_session`] and consumed by
+/// [`PqcKeyExchange::resume_session`]. Holds a resumption secret derived
+/// from the shared secret rather than the secret itself, scrubbed when the
+/// ticket is dropped, and the id of the server it was issued for.
+#[derive(Debug)]
+pub struct SessionTicket {
+    protocol:          KeyExchangeProtocol,
+    variant:           MlKemVariant,
+    role:              Role,
+    server_id:         String,
+    resumption_secret: Zeroizing<Vec<u8>>,
+    created_secs:      u64,
+    ttl:               Duration,
+}
+
+impl SessionTicket {
+    /// Get the id of the server the ticket was issued for.
+    #[must_use]
+    pub fn server_id(&self) -> &str {
+        &self.server_id
+    }
+
+    /// Get when the ticket was issued (seconds since the Unix epoch).
+    #[must_use]
+    pub fn created_secs(&self) -> u64 {
+        self.created_secs
+    }
+
+    /// Check if the ticket has outlived its TTL at `now_secs`.
+    #[must_use]
+    pub fn is_expired(&self, now_secs: u64) -> bool {
+        now_secs.saturating_sub(self.created_secs) > self.ttl.as_secs()
+    }
+}
+
 /// PQC key exchange handler.
+///
+/// Key material is held in [`Zeroizing`] buffers, scrubbed by
+/// [`clear`](Self::clear) and on drop. An empty buffer means not yet set.
 pub struct PqcKeyExchange {
-    protocol:      KeyExchangeProtocol,
-    public_key:    Option<Vec<u8>>,
-    shared_secret: Option<Vec<u8>>,
+    protocol:         KeyExchangeProtocol,
+    variant:          MlKemVariant,
+    role:             Role,
+    peer:             String,
+    public_key:       Zeroizing<Vec<u8>>,
+    shared_secret:    Zeroizing<Vec<u8>>,
+    resumption_nonce: Vec<u8>,
+    session_ttl:      Duration,
+    trace:            Option<HandshakeTrace>,
 }
 
 impl PqcKeyExchange {
-    /// Create a new key exchange handler.
+    /// Create a new key exchange handler for one side of the handshake.
+    #[must_use]
+    pub fn new(protocol: KeyExchangeProtocol, role: Role) -> Self {
+        Self {
+            protocol,
+            variant: MlKemVariant::default(),
+            role,
+            peer: String::new(),
+            public_key: Zeroizing::default(),
+            shared_secret: Zeroizing::default(),
+            resumption_nonce: Vec::new(),
+            session_ttl: DEFAULT_SESSION_TTL,
+            trace: None,
+        }
+    }
+
+    /// Use the given ML-KEM parameter set instead of ML-KEM-768.
     #[must_use]
-    pub fn new(protocol: KeyExchangeProtocol) -> Self {
-        Self { protocol, public_key: None, shared_secret: None }
+    pub fn with_variant(mut self, variant: MlKemVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Set the id of the server on the other side, which exported session
+    /// tickets are bound to.
+    #[must_use]
+    pub fn with_peer(mut self, server_id: impl Into<String>) -> Self {
+        self.peer = server_id.into();
+        self
+    }
+
+    /// Get the id of the server on the other side; empty if not set.
+    #[must_use]
+    pub fn peer(&self) -> &str {
+        &self.peer
+    }
+
+    /// Get the nonce this handle was resumed with, to send to the server.
+    ///
+    /// `None` unless the handle came from a session ticket.
+    #[must_use]
+    pub fn resumption_nonce(&self) -> Option<&[u8]> {
+        (!self.resumption_nonce.is_empty()).then_some(self.resumption_nonce.as_slice())
+    }
+
+    /// Set how long exported session tickets stay valid (default one hour).
+    #[must_use]
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Get the ML-KEM parameter set.
+    #[must_use]
+    pub fn variant(&self) -> MlKemVariant {
+        self.variant
+    }
+
+    /// Start recording a [`HandshakeTrace`] of subsequent steps.
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(HandshakeTrace::new(self.protocol, self.role));
+    }
+
+    /// Get the handshake trace, if tracing is enabled.
+    #[must_use]
+    pub fn trace(&self) -> Option<&HandshakeTrace> {
+        self.trace.as_ref()
     }
 
     /// Get the key exchange protocol.
@@ -25,6 +261,12 @@ impl PqcKeyExchange {
         self.protocol
     }
 
+    /// Get the handshake role.
+    #[must_use]
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
     /// Generate key pair.
     ///
     /// # Errors
@@ -33,8 +275,10 @@ impl PqcKeyExchange {
     pub fn generate_keypair(&mut self) -> VpnResult<Vec<u8>> {
         // In production, this would use essentia_pqc ML-KEM
         // Placeholder key generation
-        let public_key = vec![0u8; 1184]; // ML-KEM-768 public key size
-        self.public_key = Some(public_key.clone());
+        let public_key = vec![0u8; self.public_key_len()];
+        self.public_key.zeroize();
+        self.public_key.extend_from_slice(&public_key);
+        self.trace_step(KexState::KeypairGenerated, Some(public_key.len()));
         Ok(public_key)
     }
 
@@ -42,17 +286,29 @@ impl PqcKeyExchange {
     ///
     /// # Errors
     ///
-    /// Returns `VpnError::KeyExchange` if encapsulation fails.
-    pub fn encapsulate(&mut self, server_public_key: &[u8]) -> VpnResult<(Vec<u8>, Vec<u8>)> {
+    /// Returns `VpnError::KeyExchange` if called on a `Responder` or
+    /// encapsulation fails.
+    pub fn encapsulate(
+        &mut self,
+        server_public_key: &[u8],
+    ) -> VpnResult<(Vec<u8>, Zeroizing<Vec<u8>>)> {
+        self.require_role(Role::Initiator)?;
         if server_public_key.is_empty() {
+            self.trace_step(KexState::Failed, Some(0));
             return Err(VpnError::KeyExchange("Empty server public key".to_string()));
         }
+        self.require_len(
+            "server public key",
+            server_public_key,
+            self.public_key_len(),
+        )?;
 
         // In production, uses ML-KEM encapsulation
-        let ciphertext = vec![0u8; 1088]; // ML-KEM-768 ciphertext size
-        let shared_secret = vec![0u8; 32]; // 256-bit shared secret
+        let ciphertext = vec![0u8; self.ciphertext_len()];
+        let shared_secret = Zeroizing::new(vec![0u8; SHARED_SECRET_LEN]);
 
-        self.shared_secret = Some(shared_secret.clone());
+        self.set_shared_secret(&shared_secret);
+        self.trace_step(KexState::Encapsulated, Some(ciphertext.len()));
         Ok((ciphertext, shared_secret))
     }
 
@@ -60,34 +316,204 @@ impl PqcKeyExchange {
     ///
     /// # Errors
     ///
-    /// Returns `VpnError::KeyExchange` if decapsulation fails.
-    pub fn decapsulate(&mut self, ciphertext: &[u8]) -> VpnResult<Vec<u8>> {
+    /// Returns `VpnError::KeyExchange` if called on an `Initiator` or
+    /// decapsulation fails.
+    pub fn decapsulate(&mut self, ciphertext: &[u8]) -> VpnResult<Zeroizing<Vec<u8>>> {
+        self.require_role(Role::Responder)?;
         if ciphertext.is_empty() {
+            self.trace_step(KexState::Failed, Some(0));
             return Err(VpnError::KeyExchange("Empty ciphertext".to_string()));
         }
+        self.require_len("ciphertext", ciphertext, self.ciphertext_len())?;
 
         // In production, uses ML-KEM decapsulation
-        let shared_secret = vec![0u8; 32];
-        self.shared_secret = Some(shared_secret.clone());
+        let shared_secret = Zeroizing::new(vec![0u8; SHARED_SECRET_LEN]);
+        self.set_shared_secret(&shared_secret);
+        self.trace_step(KexState::Decapsulated, Some(ciphertext.len()));
         Ok(shared_secret)
     }
 
     /// Get shared secret.
     #[must_use]
     pub fn shared_secret(&self) -> Option<&[u8]> {
-        self.shared_secret.as_deref()
+        (!self.shared_secret.is_empty()).then_some(self.shared_secret.as_slice())
     }
 
-    /// Clear sensitive data.
+    /// Derive traffic keys from the shared secret with HKDF-SHA256.
+    ///
+    /// `info` binds the keys to a context (e.g. a protocol label and
+    /// session id). The 80 bytes of output are split into the
+    /// initiator-to-responder key, the responder-to-initiator key and the
+    /// nonce salt, and the keys are assigned by role.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::KeyExchange` if no shared secret is established.
+    pub fn derive_keys(&self, info: &[u8]) -> VpnResult<DerivedKeys> {
+        let secret = self
+            .shared_secret()
+            .ok_or_else(|| VpnError::KeyExchange("No shared secret".to_string()))?;
+        let okm = hkdf(&[], secret, info, 80)?;
+
+        let mut keys = DerivedKeys { send_key: [0; 32], recv_key: [0; 32], salt: [0; 16] };
+        let (outbound, inbound) = match self.role {
+            Role::Initiator => (&okm[..32], &okm[32..64]),
+            Role::Responder => (&okm[32..64], &okm[..32]),
+        };
+        keys.send_key.copy_from_slice(outbound);
+        keys.recv_key.copy_from_slice(inbound);
+        keys.salt.copy_from_slice(&okm[64..]);
+        Ok(keys)
+    }
+
+    /// Export a resumption ticket for the completed exchange, issued now.
+    ///
+    /// The ticket is bound to the handle's [`peer`](Self::peer) and carries
+    /// a resumption secret derived from the shared secret under its own HKDF
+    /// label, so it never exposes the keys of the original session.
+    ///
+    /// Returns `None` until a shared secret has been established.
+    #[must_use]
+    pub fn export_session(&self) -> Option<SessionTicket> {
+        self.export_session_at(unix_now_secs())
+    }
+
+    /// Like [`export_session`](Self::export_session), but issued at
+    /// `now_secs` (seconds since the Unix epoch).
+    #[must_use]
+    pub fn export_session_at(&self, now_secs: u64) -> Option<SessionTicket> {
+        let secret = self.shared_secret()?;
+        let resumption_secret = hkdf(&[], secret, RESUMPTION_LABEL, SHARED_SECRET_LEN).ok()?;
+        Some(SessionTicket {
+            protocol: self.protocol,
+            variant: self.variant,
+            role: self.role,
+            server_id: self.peer.clone(),
+            resumption_secret,
+            created_secs: now_secs,
+            ttl: self.session_ttl,
+        })
+    }
+
+    /// Rebuild a handle from a ticket, skipping key generation and
+    /// encapsulation.
+    ///
+    /// A fresh nonce is drawn from the `essentia_pqc` RNG and kept as
+    /// [`resumption_nonce`](Self::resumption_nonce) for the caller to send to
+    /// the server. See [`resume_session_at`](Self::resume_session_at).
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::KeyExchange` if the ticket is older than its TTL
+    /// or no nonce could be drawn.
+    pub fn resume_session(ticket: SessionTicket) -> VpnResult<Self> {
+        let mut nonce = [0; RESUMPTION_NONCE_LEN];
+        rng::fill_bytes(&mut nonce)
+            .map_err(|e| VpnError::KeyExchange(format!("Resumption nonce failed: {e}")))?;
+        Self::resume_session_at(ticket, &nonce, unix_now_secs())
+    }
+
+    /// Rebuild a handle from a ticket with the given nonce, checking the TTL
+    /// at `now_secs`.
+    ///
+    /// `nonce` is the fresh per-session value both peers use for this
+    /// resumption, e.g. the one the server received from the client. It
+    /// salts the derivation of the new shared secret, and the ticket's
+    /// server id is part of the HKDF info, so every resumption yields
+    /// different traffic keys bound to that server. The handle's peer is
+    /// the ticket's server.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::KeyExchange` if the ticket is older than its TTL
+    /// at `now_secs` or `nonce` is shorter than 16 bytes.
+    pub fn resume_session_at(
+        ticket: SessionTicket,
+        nonce: &[u8],
+        now_secs: u64,
+    ) -> VpnResult<Self> {
+        if ticket.is_expired(now_secs) {
+            return Err(VpnError::KeyExchange("Session ticket expired".to_string()));
+        }
+        if nonce.len() < MIN_RESUMPTION_NONCE_LEN {
+            return Err(VpnError::KeyExchange(format!(
+                "Resumption nonce must be at least {MIN_RESUMPTION_NONCE_LEN} bytes"
+            )));
+        }
+        let info = [RESUMED_SESSION_LABEL, ticket.server_id.as_bytes()].concat();
+        let secret = hkdf(nonce, &ticket.resumption_secret, &info, SHARED_SECRET_LEN)?;
+        let mut handle = Self::new(ticket.protocol, ticket.role)
+            .with_variant(ticket.variant)
+            .with_session_ttl(ticket.ttl)
+            .with_peer(ticket.server_id.clone());
+        handle.set_shared_secret(&secret);
+        handle.resumption_nonce = nonce.to_vec();
+        Ok(handle)
+    }
+
+    /// Clear sensitive data, zeroing the buffers in place.
     pub fn clear(&mut self) {
-        if let Some(ref mut key) = self.public_key {
-            key.fill(0);
+        self.public_key.zeroize();
+        self.shared_secret.zeroize();
+        self.trace_step(KexState::Cleared, None);
+    }
+
+    /// Replace the stored shared secret, scrubbing the previous one.
+    fn set_shared_secret(&mut self, secret: &[u8]) {
+        self.shared_secret.zeroize();
+        self.shared_secret.extend_from_slice(secret);
+    }
+
+    /// Get the public key size for the protocol and variant.
+    ///
+    /// Hybrid keys carry the X25519 key after the ML-KEM key.
+    fn public_key_len(&self) -> usize {
+        match self.protocol {
+            KeyExchangeProtocol::X25519 => X25519_KEY_LEN,
+            KeyExchangeProtocol::MlKem => self.variant.public_key_len(),
+            KeyExchangeProtocol::HybridMlKem => self.variant.public_key_len() + X25519_KEY_LEN,
+        }
+    }
+
+    /// Get the ciphertext size for the protocol and variant.
+    ///
+    /// Hybrid ciphertexts carry the ephemeral X25519 key after the ML-KEM
+    /// ciphertext.
+    fn ciphertext_len(&self) -> usize {
+        match self.protocol {
+            KeyExchangeProtocol::X25519 => X25519_KEY_LEN,
+            KeyExchangeProtocol::MlKem => self.variant.ciphertext_len(),
+            KeyExchangeProtocol::HybridMlKem => self.variant.ciphertext_len() + X25519_KEY_LEN,
         }
-        if let Some(ref mut secret) = self.shared_secret {
-            secret.fill(0);
+    }
+
+    /// Fail unless `message` is exactly `expected` bytes.
+    fn require_len(&mut self, what: &str, message: &[u8], expected: usize) -> VpnResult<()> {
+        if message.len() == expected {
+            return Ok(());
+        }
+        self.trace_step(KexState::Failed, Some(message.len()));
+        Err(VpnError::KeyExchange(format!(
+            "Invalid {what} length {}, expected {expected}",
+            message.len()
+        )))
+    }
+
+    /// Record a step if tracing is enabled.
+    fn trace_step(&mut self, state: KexState, message_len: Option<usize>) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(state, message_len);
+        }
+    }
+
+    /// Fail unless this handler plays `role`.
+    fn require_role(&mut self, role: Role) -> VpnResult<()> {
+        if self.role == role {
+            Ok(())
+        } else {
+            self.trace_step(KexState::Failed, None);
+            Err(VpnError::KeyExchange("wrong role".to_string()))
         }
-        self.public_key = None;
-        self.shared_secret = None;
     }
 }
 
@@ -96,3 +522,224 @@ impl Drop for PqcKeyExchange {
         self.clear();
     }
 }
+
+/// Get the wall-clock time in seconds since the Unix epoch.
+fn unix_now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Derive `len` bytes from `ikm` with the HKDF-SHA256 of `essentia_pqc`.
+fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> VpnResult<Zeroizing<Vec<u8>>> {
+    let mut okm = Zeroizing::new(vec![0; len]);
+    kdf::hkdf_sha256(salt, ikm, info, &mut okm)
+        .map_err(|e| VpnError::KeyExchange(format!("HKDF failed: {e}")))?;
+    Ok(okm)
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_enforcement() {
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::HybridMlKem, Role::Initiator);
+        let mut responder = PqcKeyExchange::new(KeyExchangeProtocol::HybridMlKem, Role::Responder);
+
+        let public_key = responder.generate_keypair().expect("Should generate");
+        let (ciphertext, _) = initiator.encapsulate(&public_key).expect("Should encapsulate");
+        responder.decapsulate(&ciphertext).expect("Should decapsulate");
+
+        assert!(matches!(
+            initiator.decapsulate(&ciphertext),
+            Err(VpnError::KeyExchange(_))
+        ));
+        assert!(matches!(
+            responder.encapsulate(&public_key),
+            Err(VpnError::KeyExchange(_))
+        ));
+    }
+
+    #[test]
+    fn test_handshake_trace_records_steps_only() {
+        let mut untraced = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator);
+        untraced.generate_keypair().expect("Should generate");
+        assert!(untraced.trace().is_none());
+
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::HybridMlKem, Role::Initiator);
+        initiator.enable_trace();
+        let public_key = initiator.generate_keypair().expect("Should generate");
+        initiator.encapsulate(&public_key).expect("Should encapsulate");
+        assert!(initiator.encapsulate(&[]).is_err());
+        assert!(initiator.decapsulate(&[1]).is_err());
+
+        let trace = initiator.trace().expect("Should trace");
+        assert_eq!(trace.protocol(), KeyExchangeProtocol::HybridMlKem);
+        let steps: Vec<_> = trace.steps().iter().map(|s| (s.state, s.message_len)).collect();
+        assert_eq!(steps, [
+            (KexState::KeypairGenerated, Some(1216)),
+            (KexState::Encapsulated, Some(1120)),
+            (KexState::Failed, Some(0)),
+            (KexState::Failed, None),
+        ]);
+    }
+
+    #[test]
+    fn test_message_sizes_per_variant() {
+        let cases = [
+            (MlKemVariant::MlKem512, 800, 768),
+            (MlKemVariant::MlKem768, 1184, 1088),
+            (MlKemVariant::MlKem1024, 1568, 1568),
+        ];
+        for (variant, public_key_len, ciphertext_len) in cases {
+            for (protocol, extra) in [
+                (KeyExchangeProtocol::MlKem, 0),
+                (KeyExchangeProtocol::HybridMlKem, 32),
+            ] {
+                let mut initiator =
+                    PqcKeyExchange::new(protocol, Role::Initiator).with_variant(variant);
+                let mut responder =
+                    PqcKeyExchange::new(protocol, Role::Responder).with_variant(variant);
+
+                let public_key = responder.generate_keypair().expect("Should generate");
+                assert_eq!(public_key.len(), public_key_len + extra);
+                let (ciphertext, secret) =
+                    initiator.encapsulate(&public_key).expect("Should encapsulate");
+                assert_eq!(ciphertext.len(), ciphertext_len + extra);
+                assert_eq!(secret.len(), 32);
+                assert!(
+                    responder.decapsulate(&ciphertext).expect("Should decapsulate").ct_eq(&secret)
+                );
+            }
+        }
+
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator)
+            .with_variant(MlKemVariant::MlKem1024);
+        assert!(matches!(
+            initiator.encapsulate(&[0; 1184]),
+            Err(VpnError::KeyExchange(_))
+        ));
+    }
+
+    #[test]
+    fn test_clear_scrubs_secret_in_place() {
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator);
+        let public_key = vec![0u8; MlKemVariant::default().public_key_len()];
+        initiator.encapsulate(&public_key).expect("Should encapsulate");
+        // Stand in for real key material, which the placeholder leaves zeroed
+        initiator.shared_secret.fill(0xA5);
+        let (ptr, len) = (
+            initiator.shared_secret.as_ptr(),
+            initiator.shared_secret.len(),
+        );
+
+        initiator.clear();
+        assert!(initiator.shared_secret().is_none());
+        // SAFETY: zeroizing keeps the allocation alive until drop
+        let scrubbed = unsafe { std::slice::from_raw_parts(ptr, len) };
+        assert!(scrubbed.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_session_resumption() {
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator)
+            .with_session_ttl(Duration::from_secs(600))
+            .with_peer("server-1");
+        assert!(initiator.export_session().is_none());
+
+        let public_key = vec![0u8; MlKemVariant::default().public_key_len()];
+        let (_, secret) = initiator.encapsulate(&public_key).expect("Should encapsulate");
+        let ticket = initiator.export_session().expect("Should export");
+        assert_eq!(ticket.server_id(), "server-1");
+
+        let resumed = PqcKeyExchange::resume_session(ticket).expect("Should resume");
+        assert!(resumed.shared_secret().is_some());
+        assert!(!secret.ct_eq(resumed.shared_secret().expect("Should hold secret")));
+        assert_eq!(resumed.protocol(), KeyExchangeProtocol::MlKem);
+        assert_eq!(resumed.role(), Role::Initiator);
+        assert_eq!(resumed.peer(), "server-1");
+        assert_eq!(resumed.resumption_nonce().map(<[u8]>::len), Some(32));
+        assert!(initiator.resumption_nonce().is_none());
+
+        let ticket = initiator.export_session_at(1_000).expect("Should export");
+        assert_eq!(ticket.created_secs(), 1_000);
+        assert_eq!(
+            PqcKeyExchange::resume_session_at(ticket, &[1; 8], 1_600).err(),
+            Some(VpnError::KeyExchange(String::from(
+                "Resumption nonce must be at least 16 bytes"
+            )))
+        );
+
+        let ticket = initiator.export_session_at(1_000).expect("Should export");
+        assert!(ticket.is_expired(1_601));
+        assert_eq!(
+            PqcKeyExchange::resume_session_at(ticket, &[1; 16], 1_601).err(),
+            Some(VpnError::KeyExchange(String::from(
+                "Session ticket expired"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_derive_keys_known_answer() {
+        let hex = |bytes: &[u8]| -> String { bytes.iter().map(|b| format!("{b:02x}")).collect() };
+        let mut initiator = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator);
+        assert!(matches!(
+            initiator.derive_keys(b"info"),
+            Err(VpnError::KeyExchange(_))
+        ));
+
+        let mut responder = PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Responder);
+        for handle in [&mut initiator, &mut responder] {
+            handle.set_shared_secret(&(0..32).collect::<Vec<u8>>());
+        }
+        let keys = initiator.derive_keys(b"essentia-vpn test").expect("Should derive");
+        assert_eq!(
+            hex(&keys.send_key),
+            "f70f01d5231eb36d500389e642b1ddca13c6c9a974f447b9ba8e2da4c537d605"
+        );
+        assert_eq!(
+            hex(&keys.recv_key),
+            "fc2159ca6ef3d248004f3586d59da5746a185b19f3a22ff325d26869cc6947ea"
+        );
+        assert_eq!(hex(&keys.salt), "22b13e007e0b1d6714f4bfa6cb03c4a7");
+
+        let peer = responder.derive_keys(b"essentia-vpn test").expect("Should derive");
+        assert_eq!(
+            (peer.send_key, peer.recv_key),
+            (keys.recv_key, keys.send_key)
+        );
+        assert_eq!(peer.salt, keys.salt);
+        assert_ne!(
+            initiator.derive_keys(b"other").expect("Should derive").send_key,
+            keys.send_key
+        );
+    }
+
+    #[test]
+    fn test_resumptions_derive_distinct_keys() {
+        let secret: Vec<u8> = (0..32).collect();
+        let session = |peer: &str| {
+            let mut handle =
+                PqcKeyExchange::new(KeyExchangeProtocol::MlKem, Role::Initiator).with_peer(peer);
+            handle.set_shared_secret(&secret);
+            handle
+        };
+        let original = session("server-1").derive_keys(b"info").expect("Should derive");
+
+        let resume = |peer: &str, nonce: &[u8]| {
+            let ticket = session(peer).export_session_at(1_000).expect("Should export");
+            PqcKeyExchange::resume_session_at(ticket, nonce, 1_000)
+                .expect("Should resume")
+                .derive_keys(b"info")
+                .expect("Should derive")
+        };
+        let first = resume("server-1", &[1; 16]);
+        let second = resume("server-1", &[2; 16]);
+        assert_ne!(first.send_key, second.send_key);
+        assert_ne!(first.recv_key, second.recv_key);
+        assert_ne!(first.send_key, original.send_key);
+        assert_ne!(second.send_key, original.send_key);
+        assert_eq!(resume("server-1", &[1; 16]).send_key, first.send_key);
+        assert_ne!(resume("server-2", &[1; 16]).send_key, first.send_key);
+    }
+}
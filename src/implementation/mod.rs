@@ -2,18 +2,50 @@
 //!
 //! This module contains all implementations for the VPN plugin:
 //! - Tunnel manager implementation
-//! - Key exchange implementation
+//! - Key exchange implementation and key derivation
 //! - Neural router implementation
 //! - Plugin core implementation
+//! - File and encrypted config stores
+//! - Reconnect circuit breaker and backoff
+//! - Clock implementations
+//! - Latency pingers
+//! - Traffic history
+//! - Connect-time metrics
 
+mod backoff;
+#[cfg(feature = "async")]
+mod blocking;
+mod circuit_breaker;
+mod clock;
 mod config;
+mod config_store;
+mod connect_metrics;
+mod error_coalescer;
+pub(crate) mod json;
 mod key_exchange;
+mod pinger;
 mod plugin;
 mod router;
+mod stats_history;
+pub(crate) mod toml;
 mod tunnel;
 
-pub use config::VpnConfig;
-pub use key_exchange::PqcKeyExchange;
+pub use backoff::BackoffStrategy;
+#[cfg(feature = "async")]
+pub use blocking::BlockingConnection;
+pub use circuit_breaker::CircuitBreaker;
+#[cfg(any(test, feature = "test-util"))]
+pub use clock::MockClock;
+pub use clock::SystemClock;
+pub(crate) use config::{DEFAULT_DNS_SERVERS, DEFAULT_MTU, MTU_RANGE};
+pub use config::{VpnConfig, VpnConfigBuilder};
+pub use config_store::{EncryptedConfigStore, FileConfigStore};
+pub use connect_metrics::{ConnectMetrics, PhaseStats};
+pub use key_exchange::{
+    DerivedKeys, HandshakeTrace, KexState, PqcKeyExchange, SessionTicket, TraceStep,
+};
+pub use pinger::TcpPinger;
 pub use plugin::VpnPlugin;
 pub use router::NeuralRouter;
+pub use stats_history::StatsHistory;
 pub use tunnel::TunnelManager;
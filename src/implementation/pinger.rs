@@ -0,0 +1,83 @@
+//! Latency measurement implementations.
+
+use std::{
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    traits::Pinger,
+    types::{IpPreference, VpnServer},
+};
+
+/// Default time allowed for a single TCP probe.
+const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Pinger that times a TCP connect to the server's `hostname:port`.
+#[derive(Debug, Clone, Copy)]
+pub struct TcpPinger {
+    timeout:    Duration,
+    preference: IpPreference,
+}
+
+impl TcpPinger {
+    /// Create a pinger giving up on a probe after `timeout`.
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, preference: IpPreference::default() }
+    }
+
+    /// Set the address family preference used to pick the probed address.
+    #[must_use]
+    pub fn with_ip_preference(mut self, preference: IpPreference) -> Self {
+        self.preference = preference;
+        self
+    }
+}
+
+impl Default for TcpPinger {
+    fn default() -> Self {
+        Self::new(DEFAULT_PING_TIMEOUT)
+    }
+}
+
+impl Pinger for TcpPinger {
+    fn measure(&self, server: &VpnServer) -> VpnResult<u32> {
+        let addrs = server.socket_addrs(self.preference)?;
+        let addr = addrs.first().ok_or_else(|| {
+            VpnError::Network(format!("{} resolved to no addresses", server.hostname))
+        })?;
+        let started = Instant::now();
+        TcpStream::connect_timeout(addr, self.timeout)
+            .map_err(|e| VpnError::Network(format!("Ping to {addr} failed: {e}")))?;
+        Ok(u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX))
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_tcp_pinger_measures_local_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Should bind");
+        let port = listener.local_addr().expect("Should have an address").port();
+        let server = VpnServer {
+            hostname: String::from("127.0.0.1"),
+            port,
+            ..Default::default()
+        };
+
+        let pinger = TcpPinger::new(Duration::from_secs(1));
+        assert!(pinger.measure(&server).is_ok_and(|ms| ms < 1_000));
+
+        drop(listener);
+        assert!(matches!(
+            pinger.measure(&server),
+            Err(VpnError::Network(msg)) if msg.starts_with("Ping to 127.0.0.1:")
+        ));
+    }
+}
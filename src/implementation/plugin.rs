@@ -1,32 +1,185 @@
 //! VPN plugin implementation.
 
-use std::rc::Rc;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt::Write,
+    net::{SocketAddr, TcpStream},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use essentia_pqc::rng;
 
 use crate::{
     errors::{VpnError, VpnResult},
-    implementation::{NeuralRouter, PqcKeyExchange, TunnelManager, VpnConfig},
-    types::{TunnelState, VpnServer},
+    implementation::{
+        CircuitBreaker, ConnectMetrics, HandshakeTrace, NeuralRouter, PqcKeyExchange,
+        SessionTicket, StatsHistory, SystemClock, TunnelManager, VpnConfig,
+        connect_metrics::ConnectTimings, error_coalescer::ErrorCoalescer, json,
+        key_exchange::RESUMPTION_NONCE_LEN,
+    },
+    traits::{
+        Authenticator, Clock, ConnectionObserver, DnsLeakChecker, HandshakeStep, KillSwitchBackend,
+        PostConnectVerifier, TunnelProvider,
+    },
+    types::{
+        Cidr, CircuitState, ConnectionAttempt, ConnectivityDiagnosis, Credentials, Direction,
+        DisconnectReason, EncryptionAlgorithm, ExclusionReason, HandshakeProgress,
+        KillSwitchExemptions, PreflightReport, Role, SecurityLevel, SessionSummary,
+        SplitTunnelMode, SplitTunnelRule, SplitTunnelRules, StateTransition, TrafficSample,
+        TunnelHandle, TunnelRole, TunnelState, UseCase, VerificationResult, VpnEvent, VpnServer,
+        VpnTunnel,
+    },
 };
 
+/// Maximum number of entries kept in each diagnostic history.
+const HISTORY_LIMIT: usize = 64;
+
+/// Number of traffic samples kept for throughput graphs.
+const STATS_HISTORY_LEN: usize = 300;
+
+/// Default interval between traffic samples.
+const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Timeout for the preflight reachability probe.
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often the drain check is polled while disconnecting.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How often the handshake step is polled while connecting.
+const HANDSHAKE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Reconnect waiting for its delay to elapse.
+struct PendingReconnect {
+    server:     Rc<VpnServer>,
+    due_millis: u64,
+}
+
+/// Multi-hop tunnel that traffic passes through before the exit hop.
+struct ChainHop {
+    tunnel_id:       u64,
+    provider_tunnel: Option<u64>,
+    key_exchange:    Option<PqcKeyExchange>,
+}
+
+/// Standby tunnel whose key exchange is already complete.
+struct StandbyTunnel {
+    provider_tunnel: Option<u64>,
+    key_exchange:    PqcKeyExchange,
+}
+
 /// Main VPN plugin interface.
 pub struct VpnPlugin {
     config:             VpnConfig,
     tunnel_manager:     TunnelManager,
+    tunnel_id:          Option<u64>,
+    connected_at:       Option<u64>,
+    last_heartbeat:     Option<u64>,
+    last_disconnect:    Option<DisconnectReason>,
+    drop_reason:        Option<DisconnectReason>,
     key_exchange:       Option<PqcKeyExchange>,
+    session_ticket:     Option<SessionTicket>,
+    standby_tunnels:    HashMap<u64, StandbyTunnel>,
+    hop_chain:          Vec<ChainHop>,
     router:             NeuralRouter,
     kill_switch_active: bool,
+    kill_switch:        Option<Box<dyn KillSwitchBackend>>,
+    exempt_endpoint:    Option<Cidr>,
+    dns_checker:        Option<Box<dyn DnsLeakChecker>>,
+    observers:          Vec<Box<dyn ConnectionObserver>>,
+    event_listener:     Option<Box<dyn FnMut(VpnEvent)>>,
+    circuit_breaker:    CircuitBreaker,
+    attempts:           VecDeque<ConnectionAttempt>,
+    transitions:        VecDeque<StateTransition>,
+    stats_history:      StatsHistory,
+    connect_timings:    ConnectTimings,
+    last_session:       Option<SessionSummary>,
+    bandwidth_alerts:   HashMap<Direction, bool>,
+    packet_loss_alert:  bool,
+    last_error:         Option<String>,
+    error_coalescer:    ErrorCoalescer,
+    clock:              Arc<dyn Clock>,
+    verifier:           Option<Box<dyn PostConnectVerifier>>,
+    handshake:          Option<Box<dyn HandshakeStep>>,
+    provider:           Option<Box<dyn TunnelProvider>>,
+    provider_tunnel:    Option<u64>,
+    pending_reconnect:  Option<PendingReconnect>,
+    last_server:        Option<Rc<VpnServer>>,
+    reconnect_attempt:  u32,
+    backgrounded:       bool,
+    network_up:         bool,
+    captive_portal:     bool,
+    authenticator:      Option<Box<dyn Authenticator>>,
+    credentials:        Option<Credentials>,
+    last_verification:  Option<VerificationResult>,
+    shut_down:          bool,
 }
 
 impl VpnPlugin {
     /// Create a new VPN plugin.
     #[must_use]
     pub fn new(config: VpnConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    /// Create a new VPN plugin driven by the given clock.
+    #[must_use]
+    pub fn with_clock(config: VpnConfig, clock: Arc<dyn Clock>) -> Self {
+        let circuit_breaker = CircuitBreaker::new(
+            config.circuit_max_reconnects,
+            config.circuit_window_secs,
+            config.circuit_cooldown_secs,
+        );
+        let error_coalescer = ErrorCoalescer::new(config.error_coalesce_secs);
+        let mut router = NeuralRouter::new();
+        router.set_clock(Arc::clone(&clock));
         Self {
             config,
             tunnel_manager: TunnelManager::new(),
+            tunnel_id: None,
+            connected_at: None,
+            last_heartbeat: None,
+            last_disconnect: None,
+            drop_reason: None,
             key_exchange: None,
-            router: NeuralRouter::new(),
+            session_ticket: None,
+            standby_tunnels: HashMap::new(),
+            hop_chain: Vec::new(),
+            router,
             kill_switch_active: false,
+            kill_switch: None,
+            exempt_endpoint: None,
+            dns_checker: None,
+            observers: Vec::new(),
+            event_listener: None,
+            circuit_breaker,
+            attempts: VecDeque::new(),
+            transitions: VecDeque::new(),
+            stats_history: StatsHistory::new(STATS_HISTORY_LEN, DEFAULT_SAMPLE_INTERVAL),
+            bandwidth_alerts: HashMap::new(),
+            packet_loss_alert: false,
+            connect_timings: ConnectTimings::default(),
+            last_session: None,
+            last_error: None,
+            error_coalescer,
+            clock,
+            verifier: None,
+            handshake: None,
+            provider: None,
+            provider_tunnel: None,
+            pending_reconnect: None,
+            last_server: None,
+            reconnect_attempt: 0,
+            backgrounded: false,
+            network_up: true,
+            captive_portal: false,
+            authenticator: None,
+            credentials: None,
+            last_verification: None,
+            shut_down: false,
         }
     }
 
@@ -47,142 +200,3468 @@ impl VpnPlugin {
         &mut self.router
     }
 
+    /// Register a connection observer.
+    pub fn add_observer(&mut self, observer: Box<dyn ConnectionObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Resolve a server's endpoints using the configured `ip_preference`,
+    /// on the port for the configured transport.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Network` if no address of an acceptable family is
+    /// available.
+    pub fn server_addrs(&self, server: &VpnServer) -> VpnResult<Vec<SocketAddr>> {
+        let port = server.effective_port(&self.config.transport);
+        let mut addrs = server.socket_addrs(self.config.ip_preference)?;
+        for addr in &mut addrs {
+            addr.set_port(port);
+        }
+        Ok(addrs)
+    }
+
+    /// Check that a server is reachable before connecting.
+    ///
+    /// Resolves the hostname and opens a TCP connection to the first
+    /// acceptable address with a short timeout, timing the handshake. An
+    /// unreachable server is reported with `reachable: false` rather than as
+    /// an error.
+    #[must_use]
+    pub fn preflight(&self, server: &VpnServer) -> PreflightReport {
+        let mut report = PreflightReport { server_id: server.id.clone(), ..Default::default() };
+
+        match self.server_addrs(server) {
+            Ok(addrs) => report.resolved_addrs = addrs,
+            Err(e) => {
+                report.error = Some(e.to_string());
+                return report;
+            },
+        }
+
+        let Some(addr) = report.resolved_addrs.first() else {
+            return report;
+        };
+        let started = Instant::now();
+        match TcpStream::connect_timeout(addr, PREFLIGHT_TIMEOUT) {
+            Ok(_) => {
+                report.reachable = true;
+                report.rtt_ms =
+                    Some(u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX));
+            },
+            Err(e) => report.error = Some(format!("probe to {addr} failed: {e}")),
+        }
+        report
+    }
+
+    /// Set the verifier run after connect when `verify_after_connect` is on.
+    pub fn set_post_connect_verifier(&mut self, verifier: Box<dyn PostConnectVerifier>) {
+        self.verifier = Some(verifier);
+    }
+
+    /// Set the step polled to complete the handshake after the key exchange.
+    ///
+    /// Without one the handshake completes as soon as keys are exchanged.
+    pub fn set_handshake_step(&mut self, step: Box<dyn HandshakeStep>) {
+        self.handshake = Some(step);
+    }
+
+    /// Set the provider that brings up the underlying transport.
+    ///
+    /// When set, every connect asks the provider for a tunnel before the key
+    /// exchange, and disconnect destroys it again.
+    pub fn set_tunnel_provider(&mut self, provider: Box<dyn TunnelProvider>) {
+        self.provider = Some(provider);
+    }
+
+    /// Set the callback notified of connection lifecycle events, replacing
+    /// any previous one.
+    ///
+    /// Called synchronously whenever the tunnel moves to `Connecting`,
+    /// `Connected`, `Reconnecting` or `Disconnected`, and for every error
+    /// forwarded to observers.
+    pub fn set_event_listener(&mut self, listener: Box<dyn FnMut(VpnEvent)>) {
+        self.event_listener = Some(listener);
+    }
+
+    /// Set the firewall backend that enforces the kill switch.
+    pub fn set_kill_switch_backend(&mut self, backend: Box<dyn KillSwitchBackend>) {
+        self.kill_switch = Some(backend);
+    }
+
+    /// Set the checker used by [`check_dns_leak`](Self::check_dns_leak).
+    pub fn set_dns_leak_checker(&mut self, checker: Box<dyn DnsLeakChecker>) {
+        self.dns_checker = Some(checker);
+    }
+
+    /// Allow a destination range past the kill switch.
+    ///
+    /// The entry is added to the configured `kill_switch_exemptions`. If
+    /// the kill switch is engaged, it is re-engaged with the new entry
+    /// straight away.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if `entry` is not a valid CIDR or
+    /// covers every address, or the engaged kill switch rejects the new
+    /// exemptions. The configuration is left unchanged on error.
+    pub fn add_allowlist_entry(&mut self, entry: &str) -> VpnResult<()> {
+        let cidr: Cidr = entry.parse()?;
+        let mut exemptions = self.config.kill_switch_exemptions.clone();
+        if exemptions.cidrs.contains(&cidr) {
+            return Ok(());
+        }
+        exemptions.cidrs.push(cidr);
+        exemptions.validate()?;
+        let previous = std::mem::replace(&mut self.config.kill_switch_exemptions, exemptions);
+        if self.kill_switch_active
+            && let Some(backend) = &self.kill_switch
+            && let Err(e) = backend.engage(&self.kill_switch_exemptions())
+        {
+            self.config.kill_switch_exemptions = previous;
+            return Err(VpnError::Configuration(format!(
+                "kill switch engage failed: {e}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the destination ranges reachable while the kill switch is
+    /// engaged: the configured exemptions plus, while the kill switch is
+    /// engaged for a connection, the server endpoint.
+    #[must_use]
+    pub fn allowlist(&self) -> Vec<Cidr> {
+        self.kill_switch_exemptions().cidrs
+    }
+
+    /// Add a split-tunnel rule to the `mode` list.
+    ///
+    /// Adding a rule that is already present does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if `split_tunneling` is disabled or
+    /// the rule conflicts with one in the other list.
+    pub fn add_split_rule(
+        &mut self,
+        mode: SplitTunnelMode,
+        rule: SplitTunnelRule,
+    ) -> VpnResult<()> {
+        if !self.config.split_tunneling {
+            return Err(VpnError::Configuration(
+                "Split tunneling is disabled".to_string(),
+            ));
+        }
+        if self.config.split_tunnel_rules.rules(mode).contains(&rule) {
+            return Ok(());
+        }
+        let mut config = self.config.clone();
+        config.split_tunnel_rules.rules_mut(mode).push(rule);
+        config.validate()?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// Remove a split-tunnel rule from the `mode` list.
+    ///
+    /// Returns `true` if the rule was present.
+    pub fn remove_split_rule(&mut self, mode: SplitTunnelMode, rule: &SplitTunnelRule) -> bool {
+        let rules = self.config.split_tunnel_rules.rules_mut(mode);
+        let before = rules.len();
+        rules.retain(|r| r != rule);
+        rules.len() != before
+    }
+
+    /// Get the configured split-tunnel rules.
+    #[must_use]
+    pub fn split_rules(&self) -> &SplitTunnelRules {
+        &self.config.split_tunnel_rules
+    }
+
+    /// Check the live tunnel for DNS leaks.
+    ///
+    /// A leak is reported to observers as a `VpnError::Network` error. With
+    /// `kill_on_dns_leak` set, the kill switch is then engaged, and if the
+    /// backend refuses the tunnel is disconnected instead. Returns `true` if
+    /// a leak was found; does nothing when disconnected or no checker is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the checker's error if the check itself fails.
+    pub fn check_dns_leak(&mut self) -> VpnResult<bool> {
+        let (Some(checker), Some(tunnel)) = (&self.dns_checker, self.current_tunnel()) else {
+            return Ok(false);
+        };
+        if !self.is_connected() {
+            return Ok(false);
+        }
+        let resolvers = checker.leaked_resolvers(tunnel)?;
+        if resolvers.is_empty() {
+            return Ok(false);
+        }
+
+        let resolvers: Vec<String> = resolvers.iter().map(ToString::to_string).collect();
+        let error = VpnError::Network(format!("DNS leak detected via {}", resolvers.join(", ")));
+        self.last_error = Some(error.to_string());
+        self.report_error(&error);
+
+        if self.config.kill_on_dns_leak {
+            let server = self.current_tunnel().map(|t| t.server.clone());
+            let exemptions = self.exempt_server(server.as_ref());
+            match self.kill_switch.as_ref().map_or(Ok(()), |b| b.engage(&exemptions)) {
+                Ok(()) => self.kill_switch_active = true,
+                Err(e) => {
+                    let message = format!("Kill switch engage failed after DNS leak: {e}");
+                    self.notify_observers(|o| o.on_warning(&message));
+                    self.disconnect_with_reason(DisconnectReason::KillSwitch);
+                },
+            }
+        }
+        Ok(true)
+    }
+
+    /// Set the authenticator used to re-authenticate live tunnels.
+    pub fn set_authenticator(&mut self, authenticator: Box<dyn Authenticator>) {
+        self.authenticator = Some(authenticator);
+    }
+
+    /// Get the stored credentials.
+    #[must_use]
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
+    /// Replace the stored credentials mid-session.
+    ///
+    /// When connected and an authenticator is set, the live tunnel is
+    /// re-authenticated in place, keeping its shared secret. If that fails
+    /// the tunnel moves to `Reconnecting` instead of carrying on with a
+    /// stale token.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Authentication` if re-authentication fails, or
+    /// `VpnError::Configuration` if the plugin has been shut down.
+    pub fn refresh_credentials(&mut self, credentials: Credentials) -> VpnResult<()> {
+        self.ensure_running()?;
+        self.credentials = Some(credentials);
+
+        if !self.is_connected() {
+            return Ok(());
+        }
+        let (Some(authenticator), Some(tunnel), Some(credentials)) = (
+            self.authenticator.as_ref(),
+            self.current_tunnel(),
+            self.credentials.as_ref(),
+        ) else {
+            return Ok(());
+        };
+
+        let error = match authenticator.reauthenticate(tunnel, credentials) {
+            Ok(()) => return Ok(()),
+            Err(error @ VpnError::Authentication(_)) => error,
+            Err(other) => VpnError::Authentication(other.to_string()),
+        };
+        if let Err(e) = self.set_state(TunnelState::Reconnecting) {
+            let message = format!("Could not mark tunnel for reconnect: {e}");
+            self.notify_observers(|o| o.on_warning(&message));
+        }
+        self.drop_reason = Some(DisconnectReason::AuthFailed);
+        self.report_error(&error);
+        Err(error)
+    }
+
+    /// Get the result of the most recent post-connect verification.
+    #[must_use]
+    pub fn last_verification(&self) -> Option<&VerificationResult> {
+        self.last_verification.as_ref()
+    }
+
     /// Connect to a specific server.
     ///
+    /// When `verify_after_connect` is set, the post-connect verifier runs once
+    /// the tunnel is up; if it fails the tunnel is torn down again.
+    ///
+    /// An explicit connect takes precedence over automatic recovery: any
+    /// scheduled reconnect is cancelled before connecting.
+    ///
     /// # Errors
     ///
-    /// Returns `VpnError::Connection` if already connected or connection fails.
+    /// Returns `VpnError::Connection` if already connected, connection fails,
+    /// the handshake outlasts `connect_timeout_secs` or post-connect
+    /// verification fails, or `VpnError::Configuration` if the
+    /// plugin has been shut down, verification is enabled without a verifier,
+    /// the server does not support the configured encryption, key exchange
+    /// or transport, or a required kill switch fails to engage.
     pub fn connect(&mut self, server: Rc<VpnServer>) -> VpnResult<()> {
+        self.connect_with_timeout(server, self.config.connect_timeout_secs)
+    }
+
+    /// Connect to a VPN server, allowing `timeout_secs` instead of the
+    /// configured `connect_timeout_secs` to reach `Connected`.
+    ///
+    /// The window is measured on the plugin clock from the start of the
+    /// connect and is enforced while polling the
+    /// [`HandshakeStep`](crate::HandshakeStep); the tunnel is torn down when
+    /// it elapses.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Connection("timeout")` if the handshake does not
+    /// complete in time, otherwise the same errors as
+    /// [`connect`](Self::connect).
+    pub fn connect_with_timeout(
+        &mut self,
+        server: Rc<VpnServer>,
+        timeout_secs: u64,
+    ) -> VpnResult<()> {
+        self.cancel_reconnect();
+        self.connect_server(server, timeout_secs)
+    }
+
+    /// Connect through two servers in sequence.
+    ///
+    /// The entry tunnel is established first and the provider creates the
+    /// exit tunnel inside it (see [`TunnelProvider::create_tunnel_via`]), so
+    /// the exit server never sees the client's address. The kill switch is
+    /// engaged for the entry hop only. The exit hop is the primary tunnel:
+    /// status, traffic and verification report on it, while the entry hop
+    /// holds [`TunnelRole::Hop`]. [`hops`](Self::hops) lists the chain in
+    /// order. Disconnecting tears the chain down exit first.
+    /// [`reconnect`](Self::reconnect) does not rebuild chains, so a dropped
+    /// chain is never silently replaced by a single hop; call this again
+    /// instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if `entry` and `exit` are the same
+    /// server, otherwise the same errors as [`connect`](Self::connect) for
+    /// either hop, including `VpnError::Tunnel` from a provider that cannot
+    /// chain tunnels. If the exit hop fails, the entry hop is torn down.
+    pub fn connect_multihop(&mut self, entry: Rc<VpnServer>, exit: Rc<VpnServer>) -> VpnResult<()> {
+        self.ensure_running()?;
         if self.is_connected() {
             return Err(VpnError::Connection("Already connected".to_string()));
         }
+        if entry.id == exit.id {
+            return Err(VpnError::Configuration(format!(
+                "Multi-hop entry and exit are both {}",
+                entry.id
+            )));
+        }
+        self.config.check_protocol_support(&exit)?;
 
-        // Enable kill switch if configured
-        if self.config.kill_switch {
-            self.activate_kill_switch();
+        self.cancel_reconnect();
+        let timeout_secs = self.config.connect_timeout_secs;
+        self.connect_server(entry, timeout_secs)?;
+        let result = self.push_hop().and_then(|()| self.connect_server(exit, timeout_secs));
+        if let Err(e) = &result {
+            self.close_active_tunnel(DisconnectReason::for_error(e));
         }
+        self.last_server = None;
+        result
+    }
 
-        // Create tunnel
-        self.tunnel_manager.create_tunnel(server)?;
+    /// Get the number of hops traffic passes through, zero when
+    /// disconnected.
+    #[must_use]
+    pub fn hop_count(&self) -> usize {
+        self.hops().len()
+    }
 
-        // Perform key exchange
-        let mut key_exchange = PqcKeyExchange::new(self.config.key_exchange);
-        let _public_key = key_exchange.generate_keypair()?;
+    /// Get the servers traffic passes through, entry first.
+    ///
+    /// A single-hop connection lists just its server; empty when
+    /// disconnected.
+    #[must_use]
+    pub fn hops(&self) -> Vec<&VpnServer> {
+        self.chain_tunnels().into_iter().map(|t| &t.server).collect()
+    }
 
-        // In production, would send public key to server and complete exchange
-        self.key_exchange = Some(key_exchange);
+    /// Connect without touching the scheduled reconnect.
+    fn connect_server(&mut self, server: Rc<VpnServer>, timeout_secs: u64) -> VpnResult<()> {
+        self.ensure_running()?;
 
-        // Update state
-        self.tunnel_manager.update_state(TunnelState::Connected);
+        if self.state() == TunnelState::Connected {
+            return Err(VpnError::Connection("Already connected".to_string()));
+        }
 
-        Ok(())
+        if self.config.verify_after_connect && self.verifier.is_none() {
+            return Err(VpnError::Configuration(
+                "No post-connect verifier set".to_string(),
+            ));
+        }
+        self.config.check_protocol_support(&server)?;
+
+        let server_id = server.id.clone();
+        self.last_server = Some(Rc::clone(&server));
+        let result = self.establish(server, timeout_secs).and_then(|phases| {
+            self.verify_connection()?;
+            Ok(phases)
+        });
+        if let Ok((connecting_ms, handshake_ms)) = result {
+            self.connect_timings.record(connecting_ms, handshake_ms);
+            self.connected_at = Some(self.clock.now_secs());
+            self.last_heartbeat = self.connected_at;
+            self.reconnect_attempt = 0;
+        }
+        self.record_attempt(server_id, result.as_ref().err());
+        result.map(|_| ())
+    }
+
+    /// Get the redacted trace of the current handshake.
+    ///
+    /// Only recorded when `debug_handshake` is set.
+    #[must_use]
+    pub fn handshake_trace(&self) -> Option<&HandshakeTrace> {
+        self.key_exchange.as_ref().and_then(PqcKeyExchange::trace)
+    }
+
+    /// Get connect-time statistics over recent successful connects.
+    #[must_use]
+    pub fn connect_metrics(&self) -> ConnectMetrics {
+        self.connect_timings.metrics()
     }
 
     /// Connect to optimal server.
     ///
+    /// Servers at their connection cap are skipped, as in
+    /// [`NeuralRouter::find_optimal_server_with_capacity`].
+    ///
     /// # Errors
     ///
-    /// Returns `VpnError::Connection` if no servers available or connection
-    /// fails.
+    /// Returns `VpnError::Connection` if no servers are loaded, none are
+    /// eligible, or connection fails, or `VpnError::Configuration` if the
+    /// plugin has been shut down.
     pub fn connect_optimal(&mut self) -> VpnResult<()> {
-        let server_rc = Rc::clone(
-            self.router
-                .find_optimal_server()
-                .ok_or_else(|| VpnError::Connection("No servers available".into()))?,
-        );
+        self.ensure_running()?;
 
-        let server = Rc::new((*server_rc).borrow().clone());
-        self.connect(server)
+        let Some(server_rc) = self.router.find_optimal_server_with_capacity().map(Rc::clone) else {
+            return Err(self.no_server_error());
+        };
+
+        self.connect_pooled(&server_rc)
+    }
+
+    /// Connect to the pooled server with the given id.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Connection` if no server has that id or connection
+    /// fails, or `VpnError::Configuration` if the plugin has been shut down.
+    pub fn connect_to_id(&mut self, id: &str) -> VpnResult<()> {
+        self.ensure_running()?;
+
+        let Some(server_rc) = self.router.find_by_id(id).map(Rc::clone) else {
+            return Err(VpnError::Connection(format!("Unknown server {id}")));
+        };
+
+        self.connect_pooled(&server_rc)
+    }
+
+    /// Connect to the best server for an intended use.
+    ///
+    /// Picks the best PQC server carrying the use case's tag. When no tagged
+    /// server exists, falls back to the optimal server and warns observers.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Connection` if no servers available or connection
+    /// fails, or `VpnError::Configuration` if the plugin has been shut down.
+    pub fn connect_for_use_case(&mut self, use_case: UseCase) -> VpnResult<()> {
+        let Some(tag) = use_case.required_tag() else {
+            return self.connect_optimal();
+        };
+
+        match self.router.find_best_with_tag(tag).map(Rc::clone) {
+            Some(server_rc) => self.connect_pooled(&server_rc),
+            None => {
+                let message = format!("No server tagged '{tag}', using optimal server");
+                self.notify_observers(|o| o.on_warning(&message));
+                self.connect_optimal()
+            },
+        }
+    }
+
+    /// Explain which pooled servers can be connected to and why not.
+    ///
+    /// Servers are checked the way [`connect_optimal`](Self::connect_optimal)
+    /// selects them: the router's blocklist, health, country exclusions and
+    /// cooldowns, then the connection cap. Network state is as last reported
+    /// through [`set_network_available`](Self::set_network_available) and
+    /// [`set_captive_portal`](Self::set_captive_portal).
+    #[must_use]
+    pub fn diagnose_connectivity(&self) -> ConnectivityDiagnosis {
+        let servers = self.router.servers();
+        let excluded: Vec<(String, ExclusionReason)> = servers
+            .iter()
+            .filter_map(|s| {
+                let server = s.borrow();
+                Some((server.id.clone(), self.selection_exclusion(&server)?))
+            })
+            .collect();
+
+        ConnectivityDiagnosis {
+            server_count: servers.len(),
+            eligible_count: servers.len() - excluded.len(),
+            excluded,
+            circuit: self.circuit_state(),
+            shut_down: self.shut_down,
+            network_up: self.network_up,
+            captive_portal: self.captive_portal,
+        }
     }
 
     /// Disconnect from current server.
+    ///
+    /// Pending traffic is flushed into the stats history and the final
+    /// totals are kept as [`last_session`](Self::last_session) before the
+    /// tunnel is cleared. Any scheduled reconnect is cancelled and the
+    /// session ticket is discarded, so the next connect runs a full key
+    /// exchange. The teardown is recorded as
+    /// [`DisconnectReason::UserRequested`].
     pub fn disconnect(&mut self) {
-        self.tunnel_manager.close_tunnel();
+        self.disconnect_with_reason(DisconnectReason::UserRequested);
+    }
 
-        // Clear key exchange
-        if let Some(ref mut ke) = self.key_exchange {
-            ke.clear();
-        }
-        self.key_exchange = None;
+    /// Disconnect from current server, recording why.
+    ///
+    /// Behaves like [`disconnect`](Self::disconnect); `reason` is kept as
+    /// [`last_disconnect_reason`](Self::last_disconnect_reason) and carried by
+    /// the [`VpnEvent::Disconnected`] event.
+    pub fn disconnect_with_reason(&mut self, reason: DisconnectReason) {
+        self.cancel_reconnect();
+        self.reconnect_attempt = 0;
+        self.session_ticket = None;
+        self.close_active_tunnel(reason);
 
         // Deactivate kill switch
         if self.config.kill_switch {
             self.deactivate_kill_switch();
         }
+        self.exempt_endpoint = None;
     }
 
-    /// Check if connected.
-    #[must_use]
-    pub fn is_connected(&self) -> bool {
-        self.tunnel_manager.is_connected()
+    /// Mark the app as backgrounded.
+    ///
+    /// The tunnel and kill switch are left intact and scheduled reconnects
+    /// are held back by [`poll_reconnect`](Self::poll_reconnect), so a brief
+    /// trip to the background causes no teardown. Call
+    /// [`foreground`](Self::foreground) on return.
+    pub fn background(&mut self) {
+        self.backgrounded = true;
     }
 
-    /// Get connection state.
+    /// Resume normal monitoring after [`background`](Self::background).
+    ///
+    /// If the OS killed the tunnel meanwhile, it is cleaned up with the kill
+    /// switch kept engaged and a reconnect to the same server is scheduled.
+    /// Returns `true` if a reconnect was scheduled.
+    pub fn foreground(&mut self) -> bool {
+        self.backgrounded = false;
+        let Some(server) = self.current_tunnel().map(|t| t.server.clone()) else {
+            return false;
+        };
+        if self.tunnel_alive() {
+            return false;
+        }
+
+        let message = format!("Tunnel to {} died while backgrounded", server.id);
+        self.notify_observers(|o| o.on_warning(&message));
+        self.close_active_tunnel(DisconnectReason::NetworkLost);
+        self.schedule_reconnect(Rc::new(server));
+        true
+    }
+
+    /// Check if the app is backgrounded.
     #[must_use]
-    pub fn state(&self) -> TunnelState {
-        self.tunnel_manager
-            .active_tunnel()
-            .map(|t| t.state)
-            .unwrap_or(TunnelState::Disconnected)
+    pub fn is_backgrounded(&self) -> bool {
+        self.backgrounded
     }
 
-    /// Activate kill switch.
-    fn activate_kill_switch(&mut self) {
-        // In production, would configure system firewall
-        self.kill_switch_active = true;
+    /// Record whether the device has network connectivity, as reported by
+    /// the platform's network monitor. Assumed up until reported otherwise.
+    pub fn set_network_available(&mut self, up: bool) {
+        self.network_up = up;
     }
 
-    /// Deactivate kill switch.
-    fn deactivate_kill_switch(&mut self) {
-        // In production, would restore firewall rules
-        self.kill_switch_active = false;
+    /// Record whether a captive portal was detected on the current network.
+    pub fn set_captive_portal(&mut self, present: bool) {
+        self.captive_portal = present;
     }
 
-    /// Check if kill switch is active.
+    /// Record a heartbeat from the server on the active tunnel.
+    ///
+    /// Does nothing while not connected.
+    pub fn record_heartbeat(&mut self) {
+        if self.is_connected() {
+            self.last_heartbeat = Some(self.clock.now_secs());
+        }
+    }
+
+    /// Get when the last heartbeat arrived (Unix seconds), or when the
+    /// tunnel came up if none has.
     #[must_use]
-    pub fn is_kill_switch_active(&self) -> bool {
-        self.kill_switch_active
+    pub fn last_heartbeat(&self) -> Option<u64> {
+        self.last_heartbeat
     }
-}
 
-impl Default for VpnPlugin {
-    fn default() -> Self {
-        Self::new(VpnConfig::default())
+    /// Check that the tunnel has not silently died.
+    ///
+    /// If no heartbeat arrived within twice `keepalive_interval_secs` before
+    /// `now_secs`, the tunnel is moved to `Reconnecting`, emitting
+    /// [`VpnEvent::Reconnecting`], and observers are warned; follow up with
+    /// [`reconnect`](Self::reconnect). Returns `true` if the tunnel was
+    /// found stale. Does nothing while not connected or when
+    /// `keepalive_interval_secs` is zero.
+    pub fn check_liveness(&mut self, now_secs: u64) -> bool {
+        let interval = self.config.keepalive_interval_secs;
+        if interval == 0 || !self.is_connected() {
+            return false;
+        }
+        let last_seen = self.last_heartbeat.or(self.connected_at).unwrap_or(now_secs);
+        if now_secs.saturating_sub(last_seen) <= interval.saturating_mul(2) {
+            return false;
+        }
+
+        if self.set_state(TunnelState::Reconnecting).is_err() {
+            return false;
+        }
+        let server_id = self.current_tunnel().map(|t| t.server.id.clone()).unwrap_or_default();
+        let message = format!("No heartbeat from {server_id} since {last_seen}");
+        self.notify_observers(|o| o.on_warning(&message));
+        self.drop_reason = Some(DisconnectReason::NetworkLost);
+        true
     }
-}
 
-impl Drop for VpnPlugin {
-    fn drop(&mut self) {
-        self.disconnect();
+    /// Check the active tunnel's packet loss against
+    /// `packet_loss_threshold`.
+    ///
+    /// When loss rises above the threshold, [`VpnEvent::PacketLossHigh`] is
+    /// emitted once, until it falls back below. With
+    /// `reconnect_on_packet_loss` set, the tunnel is then closed with the
+    /// kill switch kept engaged and a reconnect to the same server is
+    /// scheduled, as in [`foreground`](Self::foreground). Returns `true` if
+    /// loss is above the threshold. Does nothing while not connected.
+    pub fn evaluate_quality(&mut self) -> bool {
+        let Some(tunnel) = self.current_tunnel().filter(|t| t.state == TunnelState::Connected)
+        else {
+            self.packet_loss_alert = false;
+            return false;
+        };
+        let loss = tunnel.stats.packet_loss;
+        let server = tunnel.server.clone();
+        let above = loss / 100.0 > self.config.packet_loss_threshold;
+        let was_above = std::mem::replace(&mut self.packet_loss_alert, above);
+        if !above {
+            return false;
+        }
+        if !was_above {
+            self.emit(VpnEvent::PacketLossHigh);
+        }
+
+        if self.config.reconnect_on_packet_loss {
+            let message = format!("Packet loss {loss:.1}% on {}, reconnecting", server.id);
+            self.notify_observers(|o| o.on_warning(&message));
+            self.close_active_tunnel(DisconnectReason::NetworkLost);
+            self.schedule_reconnect(Rc::new(server));
+            self.packet_loss_alert = false;
+        }
+        true
     }
-}
 
-#[cfg(all(test, feature = "full-tests"))]
-mod tests {
-    use super::*;
+    /// Establish a standby tunnel for instant failover.
+    ///
+    /// The key exchange with `server` is completed up front and the tunnel is
+    /// kept in `Standby` without carrying traffic until
+    /// [`promote_standby`](Self::promote_standby) activates it.
+    ///
+    /// Each warm tunnel holds its key material in memory and, in production,
+    /// an open session on the server that must be kept alive, so keep the
+    /// pool to one or two backups.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider's error if it refuses the tunnel,
+    /// `VpnError::KeyExchange` if the key exchange fails, or
+    /// `VpnError::Configuration` if the plugin has been shut down.
+    pub fn warm_standby(&mut self, server: Rc<VpnServer>) -> VpnResult<TunnelHandle> {
+        self.ensure_running()?;
 
-    #[test]
-    fn test_plugin_creation() {
-        let plugin = VpnPlugin::default();
-        assert!(plugin.config().kill_switch);
+        let server = self.dial_target(server);
+        let provider_tunnel = match &self.provider {
+            Some(provider) => Some(provider.create_tunnel(&server)?.id),
+            None => None,
+        };
+        let key_exchange = match self.exchange_keys(&server.id, provider_tunnel) {
+            Ok(key_exchange) => key_exchange,
+            Err(e) => {
+                self.destroy_provider_tunnel(provider_tunnel);
+                return Err(e);
+            },
+        };
+
+        let encryption = self.config.encryption_for(&server);
+        let id = self.tunnel_manager.create_standby(server);
+        self.tunnel_manager.set_encryption(id, encryption)?;
+        self.standby_tunnels.insert(id, StandbyTunnel { provider_tunnel, key_exchange });
+        Ok(TunnelHandle::new(id))
     }
 
-    #[test]
-    fn test_not_connected_initially() {
-        let plugin = VpnPlugin::default();
-        assert!(!plugin.is_connected());
+    /// Activate a standby tunnel, replacing the current tunnel.
+    ///
+    /// Intended for failover when the primary tunnel drops: no key exchange
+    /// is needed, so the switch is immediate. The old tunnel is closed like
+    /// a dropped one, keeping its totals as
+    /// [`last_session`](Self::last_session), and the promoted server becomes
+    /// the one [`reconnect`](Self::reconnect) returns to.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Tunnel` if `handle` is not a standby tunnel, or
+    /// `VpnError::Configuration` if the plugin has been shut down or a
+    /// required kill switch fails to engage. Nothing changes on error.
+    pub fn promote_standby(&mut self, handle: TunnelHandle) -> VpnResult<()> {
+        self.ensure_running()?;
+
+        let id = handle.id();
+        let server = self
+            .tunnel_manager
+            .tunnel(id)
+            .filter(|t| t.state == TunnelState::Standby && self.standby_tunnels.contains_key(&id))
+            .map(|t| Rc::new(t.server.clone()))
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown standby tunnel {id}")))?;
+        if self.config.kill_switch {
+            self.activate_kill_switch(Some(&server))?;
+        }
+
+        let reason = self.drop_reason.unwrap_or(DisconnectReason::NetworkLost);
+        self.close_active_tunnel(reason);
+        self.tunnel_manager.promote_standby(id)?;
+        let Some(standby) = self.standby_tunnels.remove(&id) else {
+            return Err(VpnError::Tunnel(format!("Unknown standby tunnel {id}")));
+        };
+        self.tunnel_id = Some(id);
+        self.provider_tunnel = standby.provider_tunnel;
+        let now = self.clock.now_secs();
+        self.session_ticket = standby.key_exchange.export_session_at(now);
+        self.key_exchange = Some(standby.key_exchange);
+        self.connected_at = Some(now);
+        self.last_heartbeat = self.connected_at;
+        self.last_server = Some(Rc::clone(&server));
+        self.reconnect_attempt = 0;
+        self.record_transition(TunnelState::Disconnected, TunnelState::Connected);
+        self.circuit_breaker.record_success();
+        self.record_attempt(server.id.clone(), None);
+        Ok(())
     }
 
-    #[test]
-    fn test_initial_state() {
-        let plugin = VpnPlugin::default();
-        assert_eq!(plugin.state(), TunnelState::Disconnected);
+    /// Schedule a reconnect to `server` after the backoff delay.
+    ///
+    /// The delay starts at `reconnect_delay_secs` and doubles with each
+    /// reconnect scheduled since the last successful connect, capped at
+    /// `reconnect_cap_secs`. Call once the dropped tunnel has been cleaned
+    /// up, then drive the reconnect with
+    /// [`poll_reconnect`](Self::poll_reconnect). Replaces any reconnect
+    /// already scheduled.
+    pub fn schedule_reconnect(&mut self, server: Rc<VpnServer>) {
+        let delay = self.config.reconnect_backoff().delay_for(self.reconnect_attempt);
+        self.reconnect_attempt = self.reconnect_attempt.saturating_add(1);
+        let delay_millis = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+        let due_millis = self.clock.now_millis().saturating_add(delay_millis);
+        self.pending_reconnect = Some(PendingReconnect { server, due_millis });
     }
 
-    #[test]
-    fn test_connect_no_servers() {
-        let mut plugin = VpnPlugin::default();
-        let result = plugin.connect_optimal();
-        assert!(result.is_err());
+    /// Get the number of the reconnect attempt in progress, for "attempt 3
+    /// of 5" displays.
+    ///
+    /// Counts tries by [`reconnect`](Self::reconnect) and scheduled
+    /// reconnects since the last successful connect or explicit disconnect,
+    /// never exceeding `max_reconnect_attempts`. Zero when not reconnecting.
+    #[must_use]
+    pub fn current_reconnect_attempt(&self) -> u32 {
+        self.reconnect_attempt.min(self.config.max_reconnect_attempts)
+    }
+
+    /// Check if a reconnect is scheduled.
+    #[must_use]
+    pub fn is_reconnecting(&self) -> bool {
+        self.pending_reconnect.is_some()
+    }
+
+    /// Cancel the scheduled reconnect, if any.
+    pub fn cancel_reconnect(&mut self) {
+        self.pending_reconnect = None;
+    }
+
+    /// Run the scheduled reconnect once its delay has elapsed.
+    ///
+    /// Returns `true` if a reconnect was attempted and succeeded, `false`
+    /// if none was due or the app is backgrounded.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from [`begin_reconnect_attempt`](Self::begin_reconnect_attempt)
+    /// or from connecting. The reconnect is no longer scheduled afterwards.
+    pub fn poll_reconnect(&mut self) -> VpnResult<bool> {
+        if self.backgrounded {
+            return Ok(false);
+        }
+        let now = self.clock.now_millis();
+        let Some(pending) = self.pending_reconnect.take_if(|p| p.due_millis <= now) else {
+            return Ok(false);
+        };
+        self.begin_reconnect_attempt()?;
+        self.connect_server(pending.server, self.config.connect_timeout_secs)?;
+        Ok(true)
+    }
+
+    /// Reconnect to the last server after the connection dropped.
+    ///
+    /// The dead tunnel is moved to `Reconnecting` and closed with the kill
+    /// switch left engaged, then up to `max_reconnect_attempts` connects are
+    /// tried. Before each one `delay` is called with the backoff delay; pass
+    /// a real sleep in production or a recorder in tests. Stops at the first
+    /// success. Does nothing if still connected.
+    ///
+    /// Like scheduled reconnects, each attempt resumes the dropped session
+    /// from its ticket when the server accepts it, skipping the key exchange.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Connection` if nothing has been connected yet or
+    /// `max_reconnect_attempts` is zero, the circuit breaker's error if it
+    /// opens, or the last connect error once every attempt has failed, in
+    /// which case observers receive `on_reconnect_exhausted`.
+    pub fn reconnect(&mut self, mut delay: impl FnMut(Duration)) -> VpnResult<()> {
+        self.ensure_running()?;
+        if self.is_connected() {
+            return Ok(());
+        }
+        let Some(server) = self.last_server.clone() else {
+            return Err(VpnError::Connection(
+                "No server to reconnect to".to_string(),
+            ));
+        };
+
+        self.cancel_reconnect();
+        if self.current_tunnel().is_some() {
+            self.set_state(TunnelState::Reconnecting)?;
+            let reason = self.drop_reason.unwrap_or(DisconnectReason::NetworkLost);
+            self.close_active_tunnel(reason);
+        } else {
+            self.record_transition(TunnelState::Disconnected, TunnelState::Reconnecting);
+        }
+
+        let backoff = self.config.reconnect_backoff();
+        let mut last_error =
+            VpnError::Connection("Reconnect disabled: max_reconnect_attempts is 0".to_string());
+        for attempt in 0..self.config.max_reconnect_attempts {
+            delay(backoff.delay_for(attempt));
+            self.reconnect_attempt = attempt + 1;
+            self.begin_reconnect_attempt()?;
+            match self.connect_server(Rc::clone(&server), self.config.connect_timeout_secs) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    if self.current_tunnel().is_some() {
+                        self.close_active_tunnel(DisconnectReason::for_error(&e));
+                    }
+                    last_error = e;
+                },
+            }
+        }
+
+        if self.config.max_reconnect_attempts > 0 {
+            let message = format!(
+                "Reconnect to {} failed after {} attempts",
+                server.id, self.config.max_reconnect_attempts
+            );
+            self.notify_observers(|o| o.on_reconnect_exhausted(&message));
+        }
+        Err(last_error)
+    }
+
+    /// Record a reconnect attempt against the circuit breaker.
+    ///
+    /// Call before each reconnect. When too many reconnects happen within
+    /// the configured window the circuit opens, observers receive
+    /// `on_reconnect_exhausted`, and attempts are refused until the cooldown
+    /// has elapsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Connection` if the circuit is open.
+    pub fn begin_reconnect_attempt(&mut self) -> VpnResult<()> {
+        let now = self.clock.now_secs();
+        if !self.circuit_breaker.allows_attempt(now) {
+            return Err(VpnError::Connection("circuit open".to_string()));
+        }
+
+        if self.circuit_breaker.record_attempt(now) == CircuitState::Open {
+            self.notify_observers(|o| o.on_reconnect_exhausted("circuit open"));
+            return Err(VpnError::Connection("circuit open".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Get the reconnect circuit breaker state.
+    #[must_use]
+    pub fn circuit_state(&self) -> CircuitState {
+        self.circuit_breaker.state(self.clock.now_secs())
+    }
+
+    /// Disconnect after letting in-flight traffic drain.
+    ///
+    /// Moves the tunnel to `Disconnecting` and polls `drained` until it
+    /// reports that in-flight work is complete or `grace` elapses on the
+    /// plugin clock, then closes the tunnel. When the grace period expires the
+    /// tunnel is force-closed and observers receive a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if the plugin has been shut down.
+    pub fn disconnect_with_drain(
+        &mut self,
+        grace: Duration,
+        mut drained: impl FnMut() -> bool,
+    ) -> VpnResult<()> {
+        self.ensure_running()?;
+        if self.current_tunnel().is_none() {
+            return Ok(());
+        }
+
+        self.set_state(TunnelState::Disconnecting)?;
+        let deadline = self.clock.now_millis().saturating_add(grace.as_millis() as u64);
+        while !drained() {
+            let now = self.clock.now_millis();
+            if now >= deadline {
+                self.notify_observers(|o| {
+                    o.on_warning("Drain grace period expired, forcing disconnect")
+                });
+                break;
+            }
+            self.clock.sleep(DRAIN_POLL_INTERVAL.min(Duration::from_millis(deadline - now)));
+        }
+
+        self.disconnect();
+        Ok(())
+    }
+
+    /// Shut the plugin down.
+    ///
+    /// Unlike [`disconnect`](Self::disconnect), this is terminal: the tunnel is
+    /// closed, the kill switch is released regardless of configuration, the
+    /// final session summary is delivered to observers, and every further
+    /// connection attempt fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if the plugin was already shut down.
+    pub fn shutdown(&mut self) -> VpnResult<SessionSummary> {
+        self.ensure_running()?;
+
+        let summary = self.session_summary();
+        self.end_error_burst();
+        self.disconnect();
+        for (_, mut standby) in std::mem::take(&mut self.standby_tunnels) {
+            standby.key_exchange.clear();
+            self.destroy_provider_tunnel(standby.provider_tunnel);
+        }
+        self.tunnel_manager.clear_standby();
+        self.deactivate_kill_switch();
+
+        self.notify_observers(|o| o.on_session_end(&summary));
+        self.shut_down = true;
+
+        Ok(summary)
+    }
+
+    /// Get recent connection attempts (oldest first).
+    pub fn attempt_history(&self) -> impl Iterator<Item = &ConnectionAttempt> {
+        self.attempts.iter()
+    }
+
+    /// Get recent tunnel state transitions (oldest first).
+    pub fn state_history(&self) -> impl Iterator<Item = &StateTransition> {
+        self.transitions.iter()
+    }
+
+    /// Get the most recent connection error.
+    #[must_use]
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// One-line human-readable status, prefixed with the config label.
+    ///
+    /// For example `Work VPN: connected (srv-1)`.
+    #[must_use]
+    pub fn status_line(&self) -> String {
+        let mut line = String::new();
+        if let Some(label) = &self.config.label {
+            let _ = write!(line, "{label}: ");
+        }
+        line.push_str(self.state().as_str());
+        if let Some(tunnel) = self.current_tunnel() {
+            let _ = write!(line, " ({})", tunnel.server.id);
+        }
+        line
+    }
+
+    /// Current status as a single JSON object.
+    ///
+    /// Fields: `label`, `state` and `server_id`; absent values are `null`.
+    #[must_use]
+    pub fn status_json(&self) -> String {
+        let tunnel = self.current_tunnel();
+        format!(
+            "{{\"label\":{},\"state\":\"{}\",\"server_id\":{}}}",
+            json::opt_string(self.config.label.as_deref()),
+            self.state().as_str(),
+            json::opt_string(tunnel.map(|t| t.server.id.as_str())),
+        )
+    }
+
+    /// Export diagnostics as newline-delimited JSON.
+    ///
+    /// Each line is a self-describing object with a `type` field: one
+    /// `config` and one `state` record, then `attempt` and `transition`
+    /// records oldest first, and a final `error` record if a connection has
+    /// failed. The `config` record carries every setting under its TOML key,
+    /// unset optional ones as `null`. Secrets are never included.
+    #[must_use]
+    pub fn export_diagnostics(&self) -> String {
+        let mut out = String::from("{\"type\":\"config\"");
+        for (key, value) in self.config.fields() {
+            let value = value.as_deref().unwrap_or("null");
+            let _ = write!(out, ",{}:{value}", json::string(key));
+        }
+        out.push_str("}\n");
+
+        let tunnel = self.current_tunnel();
+        let _ = writeln!(
+            out,
+            "{{\"type\":\"state\",\"state\":\"{}\",\"server_id\":{},\"kill_switch_active\":{}}}",
+            self.state().as_str(),
+            json::opt_string(tunnel.map(|t| t.server.id.as_str())),
+            self.kill_switch_active,
+        );
+
+        for attempt in &self.attempts {
+            let _ = writeln!(
+                out,
+                "{{\"type\":\"attempt\",\"at\":{},\"server_id\":{},\"success\":{},\"error\":{}}}",
+                attempt.at_secs,
+                json::string(&attempt.server_id),
+                attempt.error.is_none(),
+                json::opt_string(attempt.error.as_deref()),
+            );
+        }
+
+        for transition in &self.transitions {
+            let _ = writeln!(
+                out,
+                "{{\"type\":\"transition\",\"at\":{},\"from\":\"{}\",\"to\":\"{}\"}}",
+                transition.at_secs,
+                transition.from.as_str(),
+                transition.to.as_str(),
+            );
+        }
+
+        if let Some(ref error) = self.last_error {
+            let _ = writeln!(
+                out,
+                "{{\"type\":\"error\",\"message\":{}}}",
+                json::string(error)
+            );
+        }
+
+        out
+    }
+
+    /// Check if the plugin has been shut down.
+    #[must_use]
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    /// Record traffic carried by the active tunnel.
+    ///
+    /// Updates the tunnel's byte counters and feeds the stats history, which
+    /// only takes a sample once per sample interval. Each sample is checked
+    /// against the configured bandwidth alert thresholds. Ignored when no
+    /// tunnel is active.
+    pub fn record_traffic(&mut self, bytes_sent: u64, bytes_received: u64) {
+        let Some(id) = self.tunnel_id else {
+            return;
+        };
+        self.tunnel_manager.add_traffic(id, bytes_sent, bytes_received);
+        if self.stats_history.record(bytes_sent, bytes_received, self.clock.now_millis())
+            && let Some(sample) = self.stats_history.latest().copied()
+        {
+            self.check_bandwidth_alerts(&sample);
+        }
+    }
+
+    /// Pull the provider's byte counters for the active tunnel and record
+    /// any traffic since the last sync.
+    ///
+    /// Does nothing without a provider or an active tunnel.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider's error if the stats cannot be read.
+    pub fn sync_traffic(&mut self) -> VpnResult<()> {
+        let (Some(provider), Some(id), Some(tunnel)) =
+            (&self.provider, self.provider_tunnel, self.current_tunnel())
+        else {
+            return Ok(());
+        };
+        let stats = provider.tunnel_stats(id)?;
+        let sent = stats.bytes_sent.saturating_sub(tunnel.stats.bytes_sent);
+        let received = stats.bytes_received.saturating_sub(tunnel.stats.bytes_received);
+        if sent > 0 || received > 0 {
+            self.record_traffic(sent, received);
+        }
+        Ok(())
+    }
+
+    /// Get why the most recent tunnel was torn down, or `None` if no tunnel
+    /// has ended yet.
+    #[must_use]
+    pub fn last_disconnect_reason(&self) -> Option<DisconnectReason> {
+        self.last_disconnect
+    }
+
+    /// Get the seconds since the current connection was established, on
+    /// the plugin's clock. Zero when disconnected.
+    #[must_use]
+    pub fn uptime(&self) -> u64 {
+        match (self.connected_at, self.current_tunnel()) {
+            (Some(started), Some(_)) => self.clock.now_secs().saturating_sub(started),
+            _ => 0,
+        }
+    }
+
+    /// Get the summary of the most recently closed tunnel.
+    #[must_use]
+    pub fn last_session(&self) -> Option<&SessionSummary> {
+        self.last_session.as_ref()
+    }
+
+    /// Get the traffic history.
+    #[must_use]
+    pub fn stats_history(&self) -> &StatsHistory {
+        &self.stats_history
+    }
+
+    /// Set how often the traffic history takes a sample.
+    pub fn set_sample_interval(&mut self, interval: Duration) {
+        self.stats_history.set_sample_interval(interval);
+    }
+
+    /// Get the security level of the connection's weakest hop.
+    ///
+    /// A chain is only as strong as its weakest link, so one classical hop
+    /// makes the whole chain classical. Returns `None` when disconnected.
+    #[must_use]
+    pub fn chain_security_level(&self) -> Option<SecurityLevel> {
+        weakest_security_level(self.chain_tunnels())
+    }
+
+    /// Check if connected.
+    #[must_use]
+    pub fn is_connected(&self) -> bool {
+        self.tunnel_manager.is_connected()
+    }
+
+    /// Get connection state.
+    #[must_use]
+    pub fn state(&self) -> TunnelState {
+        self.current_tunnel().map(|t| t.state).unwrap_or(TunnelState::Disconnected)
+    }
+
+    /// Get the connected tunnels traffic passes through, entry first.
+    fn chain_tunnels(&self) -> Vec<&VpnTunnel> {
+        if !self.is_connected() {
+            return Vec::new();
+        }
+        self.hop_chain
+            .iter()
+            .filter_map(|hop| self.tunnel_manager.tunnel(hop.tunnel_id))
+            .chain(self.current_tunnel())
+            .collect()
+    }
+
+    /// Move the current tunnel into the hop chain, so the next connect is
+    /// chained through it.
+    fn push_hop(&mut self) -> VpnResult<()> {
+        self.set_hop_role()?;
+        if let Some(tunnel_id) = self.tunnel_id.take() {
+            self.hop_chain.push(ChainHop {
+                tunnel_id,
+                provider_tunnel: self.provider_tunnel.take(),
+                key_exchange: self.key_exchange.take(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Mark the current tunnel as the next hop of the chain.
+    fn set_hop_role(&mut self) -> VpnResult<()> {
+        match self.tunnel_id {
+            Some(id) => self.tunnel_manager.set_role(id, TunnelRole::Hop(self.hop_chain.len())),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the tunnel this plugin brought up, in any state.
+    fn current_tunnel(&self) -> Option<&VpnTunnel> {
+        self.tunnel_id.and_then(|id| self.tunnel_manager.tunnel(id))
+    }
+
+    /// Build a summary of the current session.
+    fn session_summary(&self) -> SessionSummary {
+        self.current_tunnel()
+            .map(|t| SessionSummary {
+                tunnel_id:      Some(t.id),
+                server_id:      Some(t.server.id.clone()),
+                final_state:    t.state,
+                bytes_sent:     t.stats.bytes_sent,
+                bytes_received: t.stats.bytes_received,
+                uptime_secs:    self.uptime(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Bring up a tunnel to `server` and complete the key exchange.
+    ///
+    /// Returns the time spent connecting and in the handshake (ms).
+    fn establish(&mut self, server: Rc<VpnServer>, timeout_secs: u64) -> VpnResult<(u64, u64)> {
+        if self.tunnel_id.is_some() {
+            return Err(VpnError::Tunnel("Tunnel already active".to_string()));
+        }
+        let started = self.clock.now_millis();
+        let server = self.dial_target(server);
+
+        // Enable kill switch if configured; later hops travel inside the
+        // entry hop, which it already exempts
+        let was_engaged = self.kill_switch_active;
+        if self.config.kill_switch && self.hop_chain.is_empty() {
+            self.activate_kill_switch(Some(&server))?;
+        }
+
+        // Create tunnel, inside the previous hop when chaining. If the
+        // provider fails, release a kill switch engaged just for this
+        // attempt; one already held across a reconnect stays engaged.
+        if let Some(provider) = &self.provider {
+            let created = match self.hop_chain.last().and_then(|hop| hop.provider_tunnel) {
+                Some(via) => provider.create_tunnel_via(&server, via),
+                None => provider.create_tunnel(&server),
+            };
+            match created {
+                Ok(tunnel) => self.provider_tunnel = Some(tunnel.id),
+                Err(e) => {
+                    if !was_engaged {
+                        self.deactivate_kill_switch();
+                    }
+                    return Err(e);
+                },
+            }
+        }
+        let encryption = self.config.encryption_for(&server);
+        let server_id = server.id.clone();
+        let id = self.tunnel_manager.create_tunnel(server);
+        self.tunnel_id = Some(id);
+        self.record_transition(TunnelState::Disconnected, TunnelState::Connecting);
+
+        // Perform key exchange, tearing the tunnel down if any step fails
+        let handshake_started = self.clock.now_millis();
+        if let Err(e) = self.complete_handshake(id, &server_id, encryption, started, timeout_secs) {
+            self.close_active_tunnel(DisconnectReason::for_error(&e));
+            return Err(e);
+        }
+        self.circuit_breaker.record_success();
+
+        let finished = self.clock.now_millis();
+        Ok((
+            handshake_started.saturating_sub(started),
+            finished.saturating_sub(handshake_started),
+        ))
+    }
+
+    /// Get `server` with the port matching the configured transport.
+    fn dial_target(&self, server: Rc<VpnServer>) -> Rc<VpnServer> {
+        let port = server.effective_port(&self.config.transport);
+        if port == server.port {
+            server
+        } else {
+            Rc::new(VpnServer { port, ..(*server).clone() })
+        }
+    }
+
+    /// Agree on a shared secret for the new tunnel `id` to `server_id` and
+    /// move it to `Connected`.
+    fn complete_handshake(
+        &mut self,
+        id: u64,
+        server_id: &str,
+        encryption: EncryptionAlgorithm,
+        started_millis: u64,
+        timeout_secs: u64,
+    ) -> VpnResult<()> {
+        self.tunnel_manager.set_encryption(id, encryption)?;
+        self.set_state(TunnelState::KeyExchange)?;
+        let key_exchange = self.negotiate_keys(server_id, self.provider_tunnel)?;
+        self.key_exchange = Some(key_exchange);
+        self.await_handshake(started_millis, timeout_secs)?;
+        self.set_state(TunnelState::Connected)
+    }
+
+    /// Establish the shared secret with `server_id`.
+    ///
+    /// A session ticket from an earlier connection to the same server is
+    /// resumed when the server accepts it, skipping the key exchange; an
+    /// expired or declined ticket falls back to a full exchange. A fresh
+    /// ticket is kept for the next reconnect.
+    fn negotiate_keys(
+        &mut self,
+        server_id: &str,
+        provider_tunnel: Option<u64>,
+    ) -> VpnResult<PqcKeyExchange> {
+        let now = self.clock.now_secs();
+        let resumed = match self.session_ticket.take() {
+            Some(ticket) if ticket.server_id() == server_id && !ticket.is_expired(now) => {
+                self.resume_keys(ticket, provider_tunnel)?
+            },
+            _ => None,
+        };
+        let key_exchange = match resumed {
+            Some(key_exchange) => key_exchange,
+            None => self.exchange_keys(server_id, provider_tunnel)?,
+        };
+        self.session_ticket = key_exchange.export_session_at(now);
+        Ok(key_exchange)
+    }
+
+    /// Resume the session of `ticket`, or `None` if the server declines.
+    fn resume_keys(
+        &self,
+        ticket: SessionTicket,
+        provider_tunnel: Option<u64>,
+    ) -> VpnResult<Option<PqcKeyExchange>> {
+        let mut nonce = [0; RESUMPTION_NONCE_LEN];
+        rng::fill_bytes(&mut nonce)
+            .map_err(|e| VpnError::KeyExchange(format!("Resumption nonce failed: {e}")))?;
+        if let (Some(provider), Some(id)) = (&self.provider, provider_tunnel)
+            && !provider.resume_session(id, &nonce)?
+        {
+            return Ok(None);
+        }
+        PqcKeyExchange::resume_session_at(ticket, &nonce, self.clock.now_secs()).map(Some)
+    }
+
+    /// Run a full key exchange as initiator: encapsulate a shared secret
+    /// against the server's public key and send the ciphertext.
+    ///
+    /// Without a provider, or when it has no key for the tunnel, a local
+    /// responder supplies the public key so the tunnel still holds a
+    /// shared secret.
+    fn exchange_keys(
+        &self,
+        server_id: &str,
+        provider_tunnel: Option<u64>,
+    ) -> VpnResult<PqcKeyExchange> {
+        let provider = self.provider.as_ref().zip(provider_tunnel);
+        let server_key = match provider {
+            Some((provider, id)) => provider.server_public_key(id)?,
+            None => None,
+        };
+        let mut key_exchange = self.new_key_exchange().with_peer(server_id);
+        let server_key = match server_key {
+            Some(key) => key,
+            None => PqcKeyExchange::new(self.config.key_exchange, Role::Responder)
+                .with_variant(key_exchange.variant())
+                .generate_keypair()?,
+        };
+        let (ciphertext, _shared_secret) = key_exchange.encapsulate(&server_key)?;
+        if let Some((provider, id)) = provider {
+            provider.exchange_keys(id, &ciphertext)?;
+        }
+        Ok(key_exchange)
+    }
+
+    /// Poll the handshake step until it completes or `timeout_secs` have
+    /// passed since `started_millis`.
+    fn await_handshake(&self, started_millis: u64, timeout_secs: u64) -> VpnResult<()> {
+        let (Some(step), Some(tunnel)) = (self.handshake.as_ref(), self.current_tunnel()) else {
+            return Ok(());
+        };
+        let deadline = started_millis.saturating_add(timeout_secs.saturating_mul(1000));
+        loop {
+            if step.poll(tunnel)? == HandshakeProgress::Complete {
+                return Ok(());
+            }
+            if self.clock.now_millis() >= deadline {
+                return Err(VpnError::Connection("timeout".to_string()));
+            }
+            self.clock.sleep(HANDSHAKE_POLL_INTERVAL);
+        }
+    }
+
+    /// Run the post-connect verifier if enabled, tearing down on failure.
+    fn verify_connection(&mut self) -> VpnResult<()> {
+        if !self.config.verify_after_connect {
+            return Ok(());
+        }
+        let (Some(verifier), Some(tunnel)) = (self.verifier.as_ref(), self.current_tunnel()) else {
+            return Ok(());
+        };
+
+        let outcome = verifier.verify(tunnel);
+        let verified = outcome.as_ref().is_ok_and(|r| r.routed);
+        self.last_verification = outcome.ok();
+
+        if !verified {
+            let error = VpnError::Connection("post-connect verification failed".to_string());
+            self.disconnect_with_reason(DisconnectReason::for_error(&error));
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// Update the tunnel state and record the transition.
+    ///
+    /// Fails with `VpnError::Tunnel`, recording nothing, if the state
+    /// machine forbids the move.
+    fn set_state(&mut self, state: TunnelState) -> VpnResult<()> {
+        let previous = self.state();
+        if let Some(id) = self.tunnel_id {
+            self.tunnel_manager.update_state(id, state)?;
+        }
+        self.record_transition(previous, state);
+        Ok(())
+    }
+
+    /// Record a tunnel state transition.
+    fn record_transition(&mut self, from: TunnelState, to: TunnelState) {
+        push_bounded(&mut self.transitions, StateTransition {
+            at_secs: self.clock.now_secs(),
+            from,
+            to,
+        });
+        let event = match to {
+            TunnelState::Disconnected => self.last_disconnect.map(VpnEvent::Disconnected),
+            _ => VpnEvent::for_state(to),
+        };
+        if let Some(event) = event {
+            self.emit(event);
+        }
+    }
+
+    /// Deliver an event to the listener, if one is set.
+    fn emit(&mut self, event: VpnEvent) {
+        if let Some(listener) = &mut self.event_listener {
+            listener(event);
+        }
+    }
+
+    /// Record the outcome of a connection attempt.
+    fn record_attempt(&mut self, server_id: String, error: Option<&VpnError>) {
+        match error {
+            Some(error) => self.report_error(error),
+            None => self.end_error_burst(),
+        }
+
+        self.router.record_outcome(&server_id, error.is_none());
+        let error = error.map(|e| e.to_string());
+        if error.is_some() {
+            self.last_error.clone_from(&error);
+        }
+        push_bounded(&mut self.attempts, ConnectionAttempt {
+            at_secs: self.clock.now_secs(),
+            server_id,
+            error,
+        });
+    }
+
+    /// Report an error to observers, coalescing identical repeats.
+    fn report_error(&mut self, error: &VpnError) {
+        let report = self.error_coalescer.report(error, self.clock.now_secs());
+        if let Some((ended, count)) = report.ended {
+            self.notify_observers(|o| o.on_error_repeated(&ended, count));
+        }
+        if report.forward {
+            self.notify_observers(|o| o.on_error(error));
+            self.emit(VpnEvent::Error(error.to_string()));
+        }
+    }
+
+    /// Close the current error burst, summarizing it to observers.
+    fn end_error_burst(&mut self) {
+        if let Some((error, count)) = self.error_coalescer.finish() {
+            self.notify_observers(|o| o.on_error_repeated(&error, count));
+        }
+    }
+
+    /// Explain why the router produced no server.
+    ///
+    /// Distinguishes an empty pool from one where every server was filtered
+    /// out, listing how many were dropped for each reason.
+    fn no_server_error(&self) -> VpnError {
+        let servers = self.router.servers();
+        if servers.is_empty() {
+            return VpnError::Connection("No servers loaded".into());
+        }
+
+        let mut counts: HashMap<ExclusionReason, usize> = HashMap::new();
+        for server in servers {
+            if let Some(reason) = self.selection_exclusion(&server.borrow()) {
+                *counts.entry(reason).or_default() += 1;
+            }
+        }
+        let breakdown: Vec<String> = [
+            (ExclusionReason::NonPqc, "non-PQC"),
+            (ExclusionReason::Blocklisted, "blocklisted"),
+            (ExclusionReason::Maintenance, "in maintenance"),
+            (ExclusionReason::Unreachable, "unreachable"),
+            (ExclusionReason::CountryRestricted, "excluded country"),
+            (ExclusionReason::Cooldown, "in cooldown"),
+            (ExclusionReason::Overloaded, "full"),
+        ]
+        .into_iter()
+        .filter_map(|(reason, label)| counts.get(&reason).map(|n| format!("{n} {label}")))
+        .collect();
+        VpnError::Connection(format!(
+            "No eligible servers: {} filtered ({})",
+            counts.values().sum::<usize>(),
+            breakdown.join(", ")
+        ))
+    }
+
+    /// Get why [`connect_optimal`](Self::connect_optimal) would skip
+    /// `server`: the router's selection checks, then the connection cap.
+    fn selection_exclusion(&self, server: &VpnServer) -> Option<ExclusionReason> {
+        self.router
+            .exclusion_reason(server)
+            .or_else(|| server.is_full().then_some(ExclusionReason::Overloaded))
+    }
+
+    /// Connect to a server from the router pool.
+    fn connect_pooled(&mut self, server: &Rc<RefCell<VpnServer>>) -> VpnResult<()> {
+        let server = Rc::new(server.borrow().clone());
+        self.connect(server)
+    }
+
+    /// Notify observers of throughput crossing an alert threshold.
+    fn check_bandwidth_alerts(&mut self, sample: &TrafficSample) {
+        let thresholds = [
+            (Direction::Up, self.config.upload_alert_bps),
+            (Direction::Down, self.config.download_alert_bps),
+        ];
+        for (direction, threshold) in thresholds {
+            let (Some(threshold), Some(bps)) = (threshold, sample.rate_bps(direction)) else {
+                continue;
+            };
+            let above = bps > threshold;
+            let was_above = self.bandwidth_alerts.insert(direction, above).unwrap_or(false);
+            if above != was_above {
+                self.notify_observers(|o| o.on_bandwidth_alert(direction, bps, threshold));
+            }
+        }
+    }
+
+    /// Deliver a notification to every observer.
+    fn notify_observers(&mut self, mut notify: impl FnMut(&mut dyn ConnectionObserver)) {
+        for observer in &mut self.observers {
+            notify(observer.as_mut());
+        }
+    }
+
+    /// Close the active tunnel and clear its keys, leaving the kill switch
+    /// as is. `reason` is recorded if a tunnel was actually up.
+    fn close_active_tunnel(&mut self, reason: DisconnectReason) {
+        let previous = self.state();
+        if self.current_tunnel().is_some() {
+            self.stats_history.finish(self.clock.now_millis());
+            self.last_session = Some(self.session_summary());
+        }
+        if let Some(id) = self.tunnel_id.take() {
+            self.tunnel_manager.close_tunnel(id);
+        }
+        self.connected_at = None;
+        self.last_heartbeat = None;
+        let provider_tunnel = self.provider_tunnel.take();
+        self.destroy_provider_tunnel(provider_tunnel);
+        self.drop_reason = None;
+        if previous != TunnelState::Disconnected {
+            self.last_disconnect = Some(reason);
+            self.record_transition(previous, TunnelState::Disconnected);
+        }
+
+        // Clear key exchange
+        if let Some(ref mut ke) = self.key_exchange {
+            ke.clear();
+        }
+        self.key_exchange = None;
+        self.close_hops();
+    }
+
+    /// Destroy a provider tunnel, if any, warning observers on failure.
+    fn destroy_provider_tunnel(&mut self, provider_tunnel: Option<u64>) {
+        if let (Some(provider), Some(id)) = (&self.provider, provider_tunnel)
+            && let Err(e) = provider.destroy_tunnel(id)
+        {
+            let message = format!("Failed to destroy provider tunnel {id}: {e}");
+            self.notify_observers(|o| o.on_warning(&message));
+        }
+    }
+
+    /// Tear down the hop chain, last hop first.
+    fn close_hops(&mut self) {
+        while let Some(mut hop) = self.hop_chain.pop() {
+            self.tunnel_manager.close_tunnel(hop.tunnel_id);
+            self.destroy_provider_tunnel(hop.provider_tunnel);
+            if let Some(ke) = &mut hop.key_exchange {
+                ke.clear();
+            }
+        }
+    }
+
+    /// Check that the active tunnel is still up, asking the provider if set.
+    fn tunnel_alive(&self) -> bool {
+        match (&self.provider, self.provider_tunnel) {
+            (Some(provider), Some(id)) => {
+                provider.tunnel_state(id).is_ok_and(|s| s == TunnelState::Connected)
+            },
+            _ => self.is_connected(),
+        }
+    }
+
+    /// Create an initiator key exchange, traced when `debug_handshake` is set.
+    fn new_key_exchange(&self) -> PqcKeyExchange {
+        let mut key_exchange = PqcKeyExchange::new(self.config.key_exchange, Role::Initiator);
+        if self.config.debug_handshake {
+            key_exchange.enable_trace();
+        }
+        key_exchange
+    }
+
+    /// Fail if the plugin has been shut down.
+    fn ensure_running(&self) -> VpnResult<()> {
+        if self.shut_down {
+            return Err(VpnError::Configuration("plugin shut down".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Build the exemptions to engage the kill switch with: the configured
+    /// exemptions plus the exempt server endpoint, if any.
+    fn kill_switch_exemptions(&self) -> KillSwitchExemptions {
+        let mut exemptions = self.config.kill_switch_exemptions.clone();
+        if let Some(endpoint) = self.exempt_endpoint
+            && !exemptions.cidrs.contains(&endpoint)
+        {
+            exemptions.cidrs.push(endpoint);
+        }
+        exemptions
+    }
+
+    /// Exempt `server`'s endpoint from the kill switch when its hostname is
+    /// an IP address, so the tunnel itself stays reachable, and build the
+    /// resulting exemptions.
+    fn exempt_server(&mut self, server: Option<&VpnServer>) -> KillSwitchExemptions {
+        self.exempt_endpoint = server.and_then(|s| s.hostname.parse::<Cidr>().ok());
+        self.kill_switch_exemptions()
+    }
+
+    /// Activate kill switch, allowlisting `server`'s endpoint.
+    ///
+    /// If the backend fails to engage, this errors when
+    /// `kill_switch_required` is set and otherwise warns observers and leaves
+    /// the kill switch inactive.
+    fn activate_kill_switch(&mut self, server: Option<&VpnServer>) -> VpnResult<()> {
+        let exemptions = self.exempt_server(server);
+        if let Some(backend) = &self.kill_switch
+            && let Err(e) = backend.engage(&exemptions)
+        {
+            if self.config.kill_switch_required {
+                return Err(VpnError::Configuration(format!(
+                    "kill switch engage failed: {e}"
+                )));
+            }
+            let message = format!("Kill switch unavailable, continuing unprotected: {e}");
+            self.notify_observers(|o| o.on_warning(&message));
+            return Ok(());
+        }
+        self.kill_switch_active = true;
+        Ok(())
+    }
+
+    /// Deactivate kill switch.
+    fn deactivate_kill_switch(&mut self) {
+        if self.kill_switch_active
+            && let Some(backend) = &self.kill_switch
+            && let Err(e) = backend.release()
+        {
+            let message = format!("Kill switch release failed: {e}");
+            self.notify_observers(|o| o.on_warning(&message));
+        }
+        self.kill_switch_active = false;
+        self.exempt_endpoint = None;
+    }
+
+    /// Check if kill switch is active.
+    #[must_use]
+    pub fn is_kill_switch_active(&self) -> bool {
+        self.kill_switch_active
+    }
+}
+
+/// Append to a history buffer, dropping the oldest entry when full.
+fn push_bounded<T>(history: &mut VecDeque<T>, item: T) {
+    if history.len() == HISTORY_LIMIT {
+        history.pop_front();
+    }
+    history.push_back(item);
+}
+
+impl Default for VpnPlugin {
+    fn default() -> Self {
+        Self::new(VpnConfig::default())
+    }
+}
+
+impl Drop for VpnPlugin {
+    fn drop(&mut self) {
+        if !self.shut_down {
+            let _ = self.shutdown();
+        }
+    }
+}
+
+/// Get the minimum security level across `hops`, or `None` if empty.
+fn weakest_security_level<'a>(
+    hops: impl IntoIterator<Item = &'a VpnTunnel>,
+) -> Option<SecurityLevel> {
+    hops.into_iter().map(VpnTunnel::security_level).min()
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use std::{
+        net::IpAddr,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::types::{
+        ConnectionStats, EncryptionAlgorithm, KeyExchangeProtocol, KillSwitchExemptions,
+        ServerHealth, TransportMode, TunnelRole,
+    };
+
+    #[test]
+    fn test_plugin_creation() {
+        let plugin = VpnPlugin::default();
+        assert!(plugin.config().kill_switch);
+    }
+
+    #[test]
+    fn test_not_connected_initially() {
+        let plugin = VpnPlugin::default();
+        assert!(!plugin.is_connected());
+    }
+
+    #[test]
+    fn test_initial_state() {
+        let plugin = VpnPlugin::default();
+        assert_eq!(plugin.state(), TunnelState::Disconnected);
+    }
+
+    #[test]
+    fn test_connect_no_servers() {
+        let mut plugin = VpnPlugin::default();
+        let error = plugin.connect_optimal().expect_err("Empty pool should fail");
+        assert_eq!(
+            error.to_string(),
+            VpnError::Connection("No servers loaded".into()).to_string()
+        );
+
+        plugin.router_mut().add_server(Rc::new(RefCell::new(VpnServer {
+            pqc_enabled: false,
+            ..test_server("legacy")
+        })));
+        let error = plugin.connect_optimal().expect_err("Non-PQC pool should fail");
+        assert!(error.to_string().contains("No eligible servers: 1 filtered (1 non-PQC)"));
+
+        let full = VpnServer { current_users: 10, max_users: 10, ..test_server("full") };
+        let abroad = VpnServer { country: String::from("DE"), ..test_server("abroad") };
+        for server in [full, abroad, test_server("down")] {
+            plugin.router_mut().add_server(Rc::new(RefCell::new(server)));
+        }
+        plugin.router_mut().exclude_country("de");
+        plugin.router_mut().set_server_health("down", ServerHealth::Unreachable);
+        let error = plugin.connect_optimal().expect_err("Filtered pool should fail");
+        assert_eq!(
+            error,
+            VpnError::Connection(String::from(
+                "No eligible servers: 4 filtered (1 non-PQC, 1 unreachable, 1 excluded country, \
+                 1 full)"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_shutdown_is_terminal() {
+        let mut plugin = VpnPlugin::default();
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.is_kill_switch_active());
+
+        let summary = plugin.shutdown().expect("Should shut down");
+        assert_eq!(summary.server_id.as_deref(), Some("srv-1"));
+        assert_eq!(summary.final_state, TunnelState::Connected);
+        assert!(!plugin.is_connected());
+        assert!(!plugin.is_kill_switch_active());
+
+        assert!(matches!(
+            plugin.connect(Rc::new(test_server("srv-2"))),
+            Err(VpnError::Configuration(_))
+        ));
+        assert!(plugin.shutdown().is_err());
+    }
+
+    #[test]
+    fn test_connect_for_use_case() {
+        let mut plugin = VpnPlugin::default();
+        let events = record_events(&mut plugin);
+
+        let mut streaming = test_server("srv-stream");
+        streaming.load = 0.8;
+        streaming.tags = vec![String::from("streaming")];
+        plugin.router_mut().add_server(Rc::new(RefCell::new(streaming)));
+        plugin
+            .router_mut()
+            .add_server(Rc::new(RefCell::new(test_server("srv-general"))));
+
+        plugin.connect_for_use_case(UseCase::Streaming).expect("Should connect");
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-stream"));
+        assert!(events.lock().expect("Should lock events").is_empty());
+
+        plugin.disconnect();
+        plugin.connect_for_use_case(UseCase::P2P).expect("Should fall back");
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-general"));
+        assert_eq!(events.lock().expect("Should lock events").len(), 1);
+        assert!(events.lock().expect("Should lock events")[0].starts_with("warning:"));
+    }
+
+    struct RecordingObserver {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RecordingObserver {
+        fn push(&self, event: String) {
+            self.events.lock().expect("Should lock events").push(event);
+        }
+    }
+
+    impl ConnectionObserver for RecordingObserver {
+        fn on_warning(&mut self, message: &str) {
+            self.push(format!("warning: {message}"));
+        }
+
+        fn on_error(&mut self, error: &VpnError) {
+            self.push(format!("error: {error}"));
+        }
+
+        fn on_error_repeated(&mut self, error: &VpnError, count: u32) {
+            self.push(format!("repeated x{count}: {error}"));
+        }
+
+        fn on_reconnect_exhausted(&mut self, message: &str) {
+            self.push(format!("exhausted: {message}"));
+        }
+
+        fn on_bandwidth_alert(&mut self, direction: Direction, bps: f64, _threshold: f64) {
+            self.push(format!("bandwidth {direction:?}: {bps}"));
+        }
+    }
+
+    fn record_events(plugin: &mut VpnPlugin) -> Arc<Mutex<Vec<String>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        plugin.add_observer(Box::new(RecordingObserver { events: Arc::clone(&events) }));
+        events
+    }
+
+    struct TokenAuthenticator {
+        valid_token: &'static str,
+    }
+
+    impl Authenticator for TokenAuthenticator {
+        fn reauthenticate(&self, _tunnel: &VpnTunnel, credentials: &Credentials) -> VpnResult<()> {
+            if credentials.token == self.valid_token {
+                Ok(())
+            } else {
+                Err(VpnError::Authentication("token rejected".into()))
+            }
+        }
+    }
+
+    struct FailingKillSwitch;
+
+    impl KillSwitchBackend for FailingKillSwitch {
+        fn engage(&self, _exemptions: &KillSwitchExemptions) -> VpnResult<()> {
+            Err(VpnError::Configuration("firewall unavailable".into()))
+        }
+
+        fn release(&self) -> VpnResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Kill switch that logs the ranges of every engage.
+    #[derive(Default)]
+    struct RecordingKillSwitch {
+        engaged: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl KillSwitchBackend for RecordingKillSwitch {
+        fn engage(&self, exemptions: &KillSwitchExemptions) -> VpnResult<()> {
+            let cidrs = exemptions.cidrs.iter().map(ToString::to_string).collect();
+            self.engaged.lock().expect("Should lock log").push(cidrs);
+            Ok(())
+        }
+
+        fn release(&self) -> VpnResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Provider that refuses tunnels while `refusals` is non-zero.
+    #[derive(Default)]
+    struct FlakyProvider {
+        refusals: Arc<Mutex<u32>>,
+    }
+
+    impl TunnelProvider for FlakyProvider {
+        fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel> {
+            let mut refusals = self.refusals.lock().expect("Should lock refusals");
+            if *refusals > 0 {
+                *refusals -= 1;
+                return Err(VpnError::Tunnel(format!("{} unreachable", server.id)));
+            }
+            Ok(VpnTunnel {
+                id:           1,
+                server:       server.clone(),
+                state:        TunnelState::Connected,
+                encryption:   EncryptionAlgorithm::default(),
+                key_exchange: KeyExchangeProtocol::default(),
+                stats:        ConnectionStats::default(),
+                role:         TunnelRole::Primary,
+            })
+        }
+
+        fn tunnel_state(&self, _tunnel_id: u64) -> VpnResult<TunnelState> {
+            Ok(TunnelState::Connected)
+        }
+
+        fn tunnel_stats(&self, _tunnel_id: u64) -> VpnResult<ConnectionStats> {
+            Ok(ConnectionStats::default())
+        }
+
+        fn destroy_tunnel(&self, _tunnel_id: u64) -> VpnResult<()> {
+            Ok(())
+        }
+    }
+
+    /// Connect through a [`FlakyProvider`], then drop the tunnel.
+    fn dropped_connection(max_reconnect_attempts: u32) -> (VpnPlugin, Arc<Mutex<u32>>) {
+        let config = VpnConfig { max_reconnect_attempts, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        let provider = FlakyProvider::default();
+        let refusals = Arc::clone(&provider.refusals);
+        plugin.set_tunnel_provider(Box::new(provider));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        let id = plugin.tunnel_id.expect("Should have a tunnel");
+        plugin
+            .tunnel_manager
+            .update_state(id, TunnelState::Error)
+            .expect("Should update state");
+        (plugin, refusals)
+    }
+
+    struct LeakingResolver;
+
+    impl DnsLeakChecker for LeakingResolver {
+        fn leaked_resolvers(&self, _tunnel: &VpnTunnel) -> VpnResult<Vec<IpAddr>> {
+            Ok(vec![IpAddr::from([192, 168, 1, 1])])
+        }
+    }
+
+    fn credentials(token: &str) -> Credentials {
+        Credentials { username: "user".into(), token: token.into() }
+    }
+
+    fn current_server(plugin: &VpnPlugin) -> Option<String> {
+        plugin.tunnel_manager.active_tunnel().map(|t| t.server.id.clone())
+    }
+
+    fn test_server(id: &str) -> VpnServer {
+        VpnServer {
+            id: id.to_string(),
+            hostname: format!("{id}.example.com"),
+            port: 443,
+            country: String::from("US"),
+            city: String::from("New York"),
+            load: 0.5,
+            pqc_enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_circuit_opens_on_reconnect_storm() {
+        let config = VpnConfig { circuit_max_reconnects: 2, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+
+        assert!(plugin.begin_reconnect_attempt().is_ok());
+        assert!(plugin.begin_reconnect_attempt().is_ok());
+        assert!(plugin.begin_reconnect_attempt().is_err());
+        assert_eq!(plugin.circuit_state(), CircuitState::Open);
+        assert!(plugin.begin_reconnect_attempt().is_err());
+    }
+
+    #[test]
+    fn test_export_diagnostics() {
+        let mut plugin = VpnPlugin::default();
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.connect(Rc::new(test_server("srv-2"))).is_err());
+
+        let diagnostics = plugin.export_diagnostics();
+        let lines: Vec<&str> = diagnostics.lines().collect();
+        assert!(lines[0].starts_with("{\"type\":\"config\""));
+        let config = json::parse(lines[0]).expect("Should parse config record");
+        for (key, _) in plugin.config().fields() {
+            assert!(config.get(key).is_some(), "Missing config key {key}");
+        }
+        assert_eq!(config.get("mtu"), Some(&json::Value::Number(1420.0)));
+        assert_eq!(config.get("label"), Some(&json::Value::Null));
+        assert!(lines[1].contains("\"state\":\"connected\""));
+        assert_eq!(
+            lines.iter().filter(|l| l.contains("\"type\":\"attempt\"")).count(),
+            1
+        );
+        assert_eq!(
+            lines.iter().filter(|l| l.contains("\"type\":\"transition\"")).count(),
+            3
+        );
+        assert!(lines.iter().all(|l| l.starts_with('{') && l.ends_with('}')));
+    }
+
+    #[test]
+    fn test_post_connect_verification() {
+        let config = VpnConfig { verify_after_connect: true, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        assert!(matches!(
+            plugin.connect(Rc::new(test_server("srv-1"))),
+            Err(VpnError::Configuration(_))
+        ));
+
+        plugin.set_post_connect_verifier(Box::new(FixedVerifier { routed: false }));
+        assert!(matches!(
+            plugin.connect(Rc::new(test_server("srv-1"))),
+            Err(VpnError::Connection(_))
+        ));
+        assert_eq!(plugin.state(), TunnelState::Disconnected);
+
+        plugin.set_post_connect_verifier(Box::new(FixedVerifier { routed: true }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.last_verification().is_some_and(|r| r.egress_ip.is_some()));
+    }
+
+    struct FixedVerifier {
+        routed: bool,
+    }
+
+    impl PostConnectVerifier for FixedVerifier {
+        fn verify(&self, _tunnel: &crate::types::VpnTunnel) -> VpnResult<VerificationResult> {
+            Ok(VerificationResult {
+                egress_ip: Some(std::net::IpAddr::from([203, 0, 113, 7])),
+                routed:    self.routed,
+            })
+        }
+    }
+
+    #[test]
+    fn test_repeated_errors_are_coalesced() {
+        let config = VpnConfig { verify_after_connect: true, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        let events = record_events(&mut plugin);
+
+        plugin.set_post_connect_verifier(Box::new(FixedVerifier { routed: false }));
+        for _ in 0..3 {
+            assert!(plugin.connect(Rc::new(test_server("srv-1"))).is_err());
+        }
+        assert_eq!(events.lock().expect("Should lock events").len(), 1);
+
+        plugin.set_post_connect_verifier(Box::new(FixedVerifier { routed: true }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        let events = events.lock().expect("Should lock events");
+        assert_eq!(events.len(), 2);
+        assert!(events[0].starts_with("error:"));
+        assert!(events[1].starts_with("repeated x3:"));
+    }
+
+    #[test]
+    fn test_disconnect_with_drain() {
+        use crate::{implementation::MockClock, traits::Clock};
+
+        let clock = Arc::new(MockClock::new(0));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+
+        // Drains after a few polls
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        let mut polls = 0;
+        plugin
+            .disconnect_with_drain(Duration::from_secs(5), || {
+                polls += 1;
+                polls == 3
+            })
+            .expect("Should disconnect");
+        assert_eq!(plugin.state(), TunnelState::Disconnected);
+        assert!(clock.now_millis() < 5_000);
+
+        // Never drains: forced close once the grace period expires
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        let start = clock.now_millis();
+        plugin
+            .disconnect_with_drain(Duration::from_secs(2), || false)
+            .expect("Should disconnect");
+        assert_eq!(plugin.state(), TunnelState::Disconnected);
+        assert_eq!(clock.now_millis() - start, 2_000);
+        assert!(plugin.state_history().any(|t| t.to == TunnelState::Disconnecting));
+    }
+
+    #[test]
+    fn test_warm_standby_promotion() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+        let provider = RecordingProvider::default();
+        let log = Arc::clone(&provider.log);
+        plugin.set_tunnel_provider(Box::new(provider));
+        plugin.connect(Rc::new(test_server("primary"))).expect("Should connect");
+
+        let handle = plugin.warm_standby(Rc::new(test_server("backup"))).expect("Should warm");
+        assert_eq!(current_server(&plugin).as_deref(), Some("primary"));
+        assert!(plugin.standby_tunnels[&handle.id()].key_exchange.shared_secret().is_some());
+
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(plugin.uptime(), 30);
+        plugin.promote_standby(handle).expect("Should promote");
+        assert!(plugin.is_connected());
+        assert_eq!(current_server(&plugin).as_deref(), Some("backup"));
+        assert_eq!(plugin.uptime(), 0);
+        assert_eq!(plugin.provider_tunnel, Some(2));
+        assert!(plugin.last_session().is_some());
+        assert_eq!(log.lock().expect("Should lock log")[..], [
+            "create primary",
+            "exchange 1",
+            "create backup",
+            "exchange 2",
+            "destroy 1",
+        ]);
+
+        // A handle can only be promoted once
+        assert!(plugin.promote_standby(handle).is_err());
+    }
+
+    #[test]
+    fn test_promote_unknown_standby_leaves_kill_switch_alone() {
+        let mut plugin = VpnPlugin::new(VpnConfig { kill_switch: true, ..VpnConfig::default() });
+        let engaged = Arc::new(Mutex::new(Vec::new()));
+        plugin.set_kill_switch_backend(Box::new(RecordingKillSwitch {
+            engaged: Arc::clone(&engaged),
+        }));
+
+        assert_eq!(
+            plugin.promote_standby(TunnelHandle::new(7)),
+            Err(VpnError::Tunnel(String::from("Unknown standby tunnel 7")))
+        );
+        assert!(engaged.lock().expect("Should lock kill switch").is_empty());
+        assert!(!plugin.is_kill_switch_active());
+    }
+
+    #[test]
+    fn test_refresh_credentials() {
+        let mut plugin = VpnPlugin::default();
+        plugin.set_authenticator(Box::new(TokenAuthenticator { valid_token: "fresh" }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        plugin
+            .refresh_credentials(credentials("fresh"))
+            .expect("Should re-authenticate");
+        assert!(plugin.is_connected());
+        assert!(plugin.key_exchange.is_some());
+
+        let result = plugin.refresh_credentials(credentials("stale"));
+        assert!(matches!(result, Err(VpnError::Authentication(_))));
+        assert_eq!(plugin.state(), TunnelState::Reconnecting);
+        assert_eq!(
+            plugin.credentials().map(|c| c.token.as_str()),
+            Some("stale")
+        );
+    }
+
+    #[test]
+    fn test_status_includes_label() {
+        let config = VpnConfig { label: Some("Work VPN".into()), ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        assert_eq!(plugin.status_line(), "Work VPN: connected (srv-1)");
+        assert_eq!(
+            plugin.status_json(),
+            r#"{"label":"Work VPN","state":"connected","server_id":"srv-1"}"#
+        );
+
+        plugin.disconnect();
+        plugin.config.label = None;
+        assert_eq!(plugin.status_line(), "disconnected");
+    }
+
+    #[test]
+    fn test_record_traffic_sampling() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+        plugin.set_sample_interval(Duration::from_millis(500));
+
+        plugin.record_traffic(100, 100);
+        assert_eq!(
+            plugin.stats_history().len(),
+            0,
+            "Ignored while disconnected"
+        );
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        for _ in 0..10 {
+            plugin.record_traffic(100, 200);
+            clock.advance(Duration::from_millis(100));
+        }
+        assert_eq!(plugin.stats_history().len(), 1);
+        assert_eq!(
+            plugin.stats_history().latest().map(|s| s.bytes_sent),
+            Some(600)
+        );
+
+        let summary = plugin.shutdown().expect("Should shut down");
+        assert_eq!((summary.bytes_sent, summary.bytes_received), (1000, 2000));
+    }
+
+    #[test]
+    fn test_preflight_reachability() {
+        use std::net::TcpListener;
+
+        let plugin = VpnPlugin::default();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Should bind");
+        let mut server = test_server("local");
+        server.hostname = "127.0.0.1".into();
+        server.port = listener.local_addr().expect("Should have addr").port();
+
+        let report = plugin.preflight(&server);
+        assert!(report.reachable);
+        assert!(report.rtt_ms.is_some());
+        assert_eq!(report.resolved_addrs.len(), 1);
+
+        drop(listener);
+        let report = plugin.preflight(&server);
+        assert!(!report.reachable);
+        assert_eq!(report.rtt_ms, None);
+        assert!(report.error.is_some());
+    }
+
+    #[test]
+    fn test_bandwidth_alert_edges() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let config = VpnConfig { download_alert_bps: Some(1000.0), ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+        let events = record_events(&mut plugin);
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        // One second per sample: 500, 2000, 3000, 100 bytes received
+        plugin.record_traffic(0, 0);
+        for received in [500, 2000, 3000, 100] {
+            clock.advance(Duration::from_secs(1));
+            plugin.record_traffic(0, received);
+        }
+
+        let alerts: Vec<String> = events
+            .lock()
+            .expect("Should lock")
+            .iter()
+            .filter(|e| e.starts_with("bandwidth"))
+            .cloned()
+            .collect();
+        assert_eq!(alerts, vec!["bandwidth Down: 2000", "bandwidth Down: 100"]);
+    }
+
+    #[test]
+    fn test_connect_metrics_counts_successes() {
+        let mut plugin = VpnPlugin::default();
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.connect(Rc::new(test_server("srv-2"))).is_err());
+        plugin.disconnect();
+        plugin.connect(Rc::new(test_server("srv-2"))).expect("Should connect");
+
+        let metrics = plugin.connect_metrics();
+        assert_eq!(metrics.total.count, 2);
+        assert_eq!(metrics.connecting.count, 2);
+        assert_eq!(metrics.handshake.count, 2);
+    }
+
+    #[test]
+    fn test_disconnect_flushes_pending_traffic() {
+        let mut plugin = VpnPlugin::default();
+        plugin.set_sample_interval(Duration::from_secs(3600));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        plugin.record_traffic(1500, 4000);
+        plugin.record_traffic(500, 1000);
+        assert!(plugin.stats_history().is_empty());
+
+        plugin.disconnect();
+        let sample = plugin.stats_history().latest().expect("Should flush final sample");
+        assert_eq!((sample.bytes_sent, sample.bytes_received), (2000, 5000));
+
+        let summary = plugin.last_session().expect("Should keep final totals");
+        assert_eq!(summary.server_id.as_deref(), Some("srv-1"));
+        assert_eq!((summary.bytes_sent, summary.bytes_received), (2000, 5000));
+    }
+
+    #[test]
+    fn test_connect_uses_transport_port() {
+        let config = VpnConfig { transport: TransportMode::Websocket, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        let mut server = test_server("srv-ws");
+        server.hostname = "127.0.0.1".to_string();
+        server.port_map.insert(TransportMode::Websocket, 8080);
+
+        assert!(server_addrs_ports(&plugin, &server).iter().all(|&p| p == 8080));
+        plugin.connect(Rc::new(server.clone())).expect("Should connect");
+        assert_eq!(
+            plugin
+                .tunnel_manager
+                .active_tunnel()
+                .expect("Should have an active tunnel")
+                .server
+                .port,
+            8080
+        );
+
+        plugin.disconnect();
+        plugin.config.transport = TransportMode::ObfuscatedTls;
+        server.supports_obfuscation = true;
+        plugin.connect(Rc::new(server)).expect("Should connect");
+        assert_eq!(
+            plugin
+                .tunnel_manager
+                .active_tunnel()
+                .expect("Should have an active tunnel")
+                .server
+                .port,
+            443
+        );
+    }
+
+    fn server_addrs_ports(plugin: &VpnPlugin, server: &VpnServer) -> Vec<u16> {
+        plugin
+            .server_addrs(server)
+            .expect("Should resolve")
+            .iter()
+            .map(SocketAddr::port)
+            .collect()
+    }
+
+    #[test]
+    fn test_kill_switch_released_when_provider_fails() {
+        let mut plugin = VpnPlugin::default();
+        let provider = FlakyProvider::default();
+        *provider.refusals.lock().expect("Should lock refusals") = 1;
+        plugin.set_tunnel_provider(Box::new(provider));
+        plugin.set_kill_switch_backend(Box::new(RecordingKillSwitch::default()));
+
+        assert_eq!(
+            plugin.connect(Rc::new(test_server("srv-1"))),
+            Err(VpnError::Tunnel("srv-1 unreachable".to_string()))
+        );
+        assert!(!plugin.is_connected());
+        assert!(
+            !plugin.is_kill_switch_active(),
+            "Released with the failed tunnel"
+        );
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.is_kill_switch_active());
+    }
+
+    #[test]
+    fn test_connect_aborts_when_kill_switch_fails() {
+        let mut plugin = VpnPlugin::default();
+        plugin.set_kill_switch_backend(Box::new(FailingKillSwitch));
+
+        let err = plugin.connect(Rc::new(test_server("srv-1"))).unwrap_err();
+        assert!(err.to_string().contains("kill switch engage failed: "));
+        assert!(err.to_string().contains("firewall unavailable"));
+        assert!(!plugin.is_connected());
+        assert!(!plugin.is_kill_switch_active());
+
+        let config = VpnConfig { kill_switch_required: false, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        let events = record_events(&mut plugin);
+        plugin.set_kill_switch_backend(Box::new(FailingKillSwitch));
+
+        plugin
+            .connect(Rc::new(test_server("srv-1")))
+            .expect("Should connect best-effort");
+        assert!(!plugin.is_kill_switch_active());
+        assert!(
+            events
+                .lock()
+                .expect("Should lock events")
+                .iter()
+                .any(|e| e.contains("Kill switch unavailable"))
+        );
+    }
+
+    #[test]
+    fn test_chain_security_level_is_weakest_hop() {
+        let hop = |encryption, key_exchange| VpnTunnel {
+            id: 1,
+            server: test_server("hop"),
+            state: TunnelState::Connected,
+            encryption,
+            key_exchange,
+            stats: Default::default(),
+            role: TunnelRole::Hop(0),
+        };
+        let hybrid = hop(
+            EncryptionAlgorithm::Aes256GcmPqc,
+            KeyExchangeProtocol::HybridMlKem,
+        );
+        let pq = hop(
+            EncryptionAlgorithm::Aes256GcmPqc,
+            KeyExchangeProtocol::MlKem,
+        );
+        let classical = hop(
+            EncryptionAlgorithm::Aes256GcmPqc,
+            KeyExchangeProtocol::X25519,
+        );
+        let weak_cipher = hop(
+            EncryptionAlgorithm::ChaCha20Poly1305,
+            KeyExchangeProtocol::MlKem,
+        );
+
+        assert_eq!(
+            weakest_security_level([&hybrid, &pq]),
+            Some(SecurityLevel::PostQuantum)
+        );
+        assert_eq!(
+            weakest_security_level([&hybrid, &classical, &pq]),
+            Some(SecurityLevel::Classical)
+        );
+        assert_eq!(
+            weakest_security_level([&weak_cipher]),
+            Some(SecurityLevel::Classical)
+        );
+        assert_eq!(weakest_security_level([]), None);
+
+        let mut plugin = VpnPlugin::default();
+        assert_eq!(plugin.chain_security_level(), None);
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert_eq!(plugin.chain_security_level(), Some(SecurityLevel::Hybrid));
+    }
+
+    #[test]
+    fn test_explicit_connect_cancels_scheduled_reconnect() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+        plugin.schedule_reconnect(Rc::new(test_server("srv-old")));
+        assert!(plugin.is_reconnecting());
+
+        clock.advance(Duration::from_secs(2));
+        assert!(!plugin.poll_reconnect().expect("Should poll"));
+
+        plugin.connect(Rc::new(test_server("srv-new"))).expect("Should connect");
+        assert!(!plugin.is_reconnecting());
+
+        clock.advance(Duration::from_secs(10));
+        assert!(!plugin.poll_reconnect().expect("Should poll"));
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-new"));
+        assert_eq!(plugin.attempt_history().count(), 1);
+
+        plugin.disconnect();
+        plugin.schedule_reconnect(Rc::new(test_server("srv-old")));
+        clock.advance(Duration::from_secs(5));
+        assert!(plugin.poll_reconnect().expect("Should poll"));
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-old"));
+    }
+
+    #[test]
+    fn test_diagnose_connectivity() {
+        let mut plugin = VpnPlugin::new(VpnConfig::default());
+        let servers = [
+            test_server("ok"),
+            VpnServer { pqc_enabled: false, ..test_server("classic") },
+            VpnServer { max_users: 10, current_users: 10, ..test_server("busy") },
+            VpnServer { country: "FR".into(), ..test_server("abroad") },
+            test_server("banned"),
+            test_server("patching"),
+            test_server("resting"),
+        ];
+        for server in servers {
+            plugin.router_mut().add_server(Rc::new(RefCell::new(server)));
+        }
+        let router = plugin.router_mut();
+        router.exclude_country("fr");
+        router.block_server("banned");
+        router.set_server_health("patching", ServerHealth::Maintenance);
+        router.cool_down_server("resting", Duration::from_secs(60));
+
+        let diagnosis = plugin.diagnose_connectivity();
+        assert_eq!((diagnosis.server_count, diagnosis.eligible_count), (7, 1));
+        assert_eq!(diagnosis.excluded, [
+            ("classic".into(), ExclusionReason::NonPqc),
+            ("busy".into(), ExclusionReason::Overloaded),
+            ("abroad".into(), ExclusionReason::CountryRestricted),
+            ("banned".into(), ExclusionReason::Blocklisted),
+            ("patching".into(), ExclusionReason::Maintenance),
+            ("resting".into(), ExclusionReason::Cooldown),
+        ]);
+        assert_eq!(diagnosis.circuit, CircuitState::Closed);
+        assert!(!diagnosis.shut_down);
+        assert!(diagnosis.network_up && !diagnosis.captive_portal);
+
+        plugin.set_network_available(false);
+        plugin.set_captive_portal(true);
+        let diagnosis = plugin.diagnose_connectivity();
+        assert!(!diagnosis.network_up && diagnosis.captive_portal);
+    }
+
+    #[test]
+    fn test_dns_leak_triggers_protective_action() {
+        let config = VpnConfig { kill_on_dns_leak: true, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config.clone());
+        let events = record_events(&mut plugin);
+        plugin.set_dns_leak_checker(Box::new(LeakingResolver));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        assert!(plugin.check_dns_leak().expect("Should check for leaks"));
+        assert!(plugin.is_connected());
+        assert!(plugin.is_kill_switch_active());
+        assert!(
+            events
+                .lock()
+                .expect("Should lock events")
+                .iter()
+                .any(|e| e.contains("DNS leak detected via 192.168.1.1"))
+        );
+
+        let mut plugin = VpnPlugin::new(VpnConfig { kill_switch_required: false, ..config });
+        plugin.set_dns_leak_checker(Box::new(LeakingResolver));
+        plugin.set_kill_switch_backend(Box::new(FailingKillSwitch));
+        plugin
+            .connect(Rc::new(test_server("srv-1")))
+            .expect("Should connect best-effort");
+
+        assert!(plugin.check_dns_leak().expect("Should check for leaks"));
+        assert!(!plugin.is_connected());
+    }
+
+    #[test]
+    fn test_tunnel_roles() {
+        let mut plugin = VpnPlugin::default();
+        plugin.connect(Rc::new(test_server("primary"))).expect("Should connect");
+        let handle = plugin
+            .warm_standby(Rc::new(test_server("backup")))
+            .expect("Should warm standby");
+
+        let manager = &plugin.tunnel_manager;
+        let primary = manager.tunnels_by_role(TunnelRole::Primary);
+        assert_eq!(primary.len(), 1);
+        assert_eq!(primary[0].server.id, "primary");
+        assert_eq!(
+            manager.tunnels_by_role(TunnelRole::Backup)[0].server.id,
+            "backup"
+        );
+
+        plugin.promote_standby(handle).expect("Should promote");
+        assert_eq!(current_server(&plugin).as_deref(), Some("backup"));
+        assert_eq!(
+            plugin
+                .tunnel_manager
+                .active_tunnel()
+                .expect("Should have an active tunnel")
+                .role,
+            TunnelRole::Primary
+        );
+        assert!(plugin.tunnel_manager.tunnels_by_role(TunnelRole::Backup).is_empty());
+    }
+
+    #[test]
+    fn test_debug_handshake_exposes_trace() {
+        let mut plugin = VpnPlugin::default();
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.handshake_trace().is_none());
+        plugin.disconnect();
+
+        plugin.config.debug_handshake = true;
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        let trace = plugin.handshake_trace().expect("Should trace");
+        assert_eq!(
+            trace.steps()[0].state,
+            crate::implementation::KexState::Encapsulated
+        );
+    }
+
+    #[test]
+    fn test_scheduled_reconnects_back_off() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let config = VpnConfig { reconnect_cap_secs: 12, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+        let mut delays = Vec::new();
+        for _ in 0..4 {
+            plugin.schedule_reconnect(Rc::new(test_server("srv-1")));
+            delays.push(
+                plugin
+                    .pending_reconnect
+                    .as_ref()
+                    .expect("Should schedule a reconnect")
+                    .due_millis
+                    - clock.now_millis(),
+            );
+        }
+        assert_eq!(delays, [5000, 10_000, 12_000, 12_000]);
+
+        clock.advance(Duration::from_secs(12));
+        assert!(plugin.poll_reconnect().expect("Should poll"));
+        plugin.disconnect();
+        plugin.schedule_reconnect(Rc::new(test_server("srv-1")));
+        assert_eq!(
+            plugin
+                .pending_reconnect
+                .as_ref()
+                .expect("Should schedule a reconnect")
+                .due_millis
+                - clock.now_millis(),
+            5000
+        );
+    }
+
+    #[test]
+    fn test_background_holds_tunnel_and_foreground_recovers() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(0));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        plugin.background();
+        plugin.schedule_reconnect(Rc::new(test_server("srv-1")));
+        clock.advance(Duration::from_secs(60));
+        assert!(!plugin.poll_reconnect().expect("Should poll"));
+        assert!(!plugin.foreground());
+        assert!(plugin.is_connected());
+        plugin.cancel_reconnect();
+
+        plugin.background();
+        let id = plugin.tunnel_id.expect("Should have a tunnel");
+        plugin
+            .tunnel_manager
+            .update_state(id, TunnelState::Error)
+            .expect("Should update state");
+        assert!(plugin.foreground());
+        assert!(plugin.is_kill_switch_active());
+        assert!(!plugin.is_connected());
+
+        clock.advance(Duration::from_secs(60));
+        assert!(plugin.poll_reconnect().expect("Should poll"));
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-1"));
+    }
+
+    #[test]
+    fn test_reconnect_exhausts_attempts() {
+        let (mut plugin, refusals) = dropped_connection(3);
+        *refusals.lock().expect("Should lock refusals") = u32::MAX;
+        let events = record_events(&mut plugin);
+
+        let mut delays = Vec::new();
+        let result = plugin.reconnect(|d| delays.push(d.as_secs()));
+        assert!(matches!(result, Err(VpnError::Tunnel(_))));
+        assert_eq!(delays, [5, 10, 20]);
+        assert_eq!(plugin.state(), TunnelState::Disconnected);
+        assert!(plugin.is_kill_switch_active());
+        assert!(
+            events
+                .lock()
+                .expect("Should lock events")
+                .last()
+                .expect("Should record an event")
+                .starts_with("exhausted:")
+        );
+    }
+
+    #[test]
+    fn test_reconnect_succeeds_on_second_try() {
+        let (mut plugin, refusals) = dropped_connection(5);
+        *refusals.lock().expect("Should lock refusals") = 1;
+
+        let mut delays = 0;
+        plugin.reconnect(|_| delays += 1).expect("Should reconnect");
+        assert_eq!(delays, 2);
+        assert!(plugin.is_connected());
+        assert!(plugin.state_history().any(|t| t.to == TunnelState::Reconnecting));
+    }
+
+    #[test]
+    fn test_kill_switch_allowlist() {
+        let mut plugin = VpnPlugin::default();
+        plugin.add_allowlist_entry("192.168.0.0/16").expect("Should accept LAN");
+        assert!(matches!(
+            plugin.add_allowlist_entry("192.168.0.0/33"),
+            Err(VpnError::Configuration(_))
+        ));
+        assert!(plugin.add_allowlist_entry("lan").is_err());
+        assert!(plugin.add_allowlist_entry("0.0.0.0/0").is_err());
+        assert_eq!(plugin.allowlist().len(), 1);
+
+        let server = VpnServer {
+            hostname: String::from("203.0.113.7"),
+            ..test_server("srv-1")
+        };
+        plugin.connect(Rc::new(server)).expect("Should connect");
+        let allowlist: Vec<String> = plugin.allowlist().iter().map(ToString::to_string).collect();
+        assert_eq!(allowlist, ["192.168.0.0/16", "203.0.113.7/32"]);
+        assert_eq!(plugin.config().kill_switch_exemptions.cidrs.len(), 1);
+
+        plugin.disconnect();
+        let allowlist: Vec<String> = plugin.allowlist().iter().map(ToString::to_string).collect();
+        assert_eq!(allowlist, ["192.168.0.0/16"]);
+    }
+
+    #[test]
+    fn test_allowlist_entry_reapplied_to_engaged_kill_switch() {
+        let mut plugin = VpnPlugin::default();
+        let backend = RecordingKillSwitch::default();
+        let engaged = Arc::clone(&backend.engaged);
+        plugin.set_kill_switch_backend(Box::new(backend));
+
+        let server = VpnServer {
+            hostname: String::from("203.0.113.7"),
+            ..test_server("srv-1")
+        };
+        plugin.connect(Rc::new(server)).expect("Should connect");
+        plugin.add_allowlist_entry("10.0.0.0/8").expect("Should accept entry");
+        assert_eq!(
+            engaged.lock().expect("Should lock log").last().map(Vec::as_slice),
+            Some(["10.0.0.0/8".to_string(), "203.0.113.7/32".to_string()].as_slice())
+        );
+
+        plugin.disconnect();
+        let engages = engaged.lock().expect("Should lock log").len();
+        plugin.add_allowlist_entry("172.16.0.0/12").expect("Should accept entry");
+        assert_eq!(engaged.lock().expect("Should lock log").len(), engages);
+        let allowlist: Vec<String> = plugin.allowlist().iter().map(ToString::to_string).collect();
+        assert_eq!(allowlist, ["10.0.0.0/8", "172.16.0.0/12"]);
+    }
+
+    #[test]
+    fn test_split_rules() {
+        let mut plugin = VpnPlugin::default();
+        let mail = SplitTunnelRule::App(String::from("com.example.mail"));
+        assert!(matches!(
+            plugin.add_split_rule(SplitTunnelMode::Exclude, mail.clone()),
+            Err(VpnError::Configuration(_))
+        ));
+
+        let mut plugin =
+            VpnPlugin::new(VpnConfig { split_tunneling: true, ..VpnConfig::default() });
+        let lan: SplitTunnelRule = "10.0.0.0/8".parse().expect("Should parse");
+        plugin
+            .add_split_rule(SplitTunnelMode::Include, lan.clone())
+            .expect("Should add");
+        plugin
+            .add_split_rule(SplitTunnelMode::Exclude, mail.clone())
+            .expect("Should add");
+        plugin
+            .add_split_rule(SplitTunnelMode::Exclude, mail.clone())
+            .expect("Should dedupe");
+        assert_eq!(plugin.split_rules().include, std::slice::from_ref(&lan));
+        assert_eq!(plugin.split_rules().exclude, std::slice::from_ref(&mail));
+
+        let host: SplitTunnelRule = "10.1.2.3".parse().expect("Should parse");
+        assert!(plugin.add_split_rule(SplitTunnelMode::Exclude, host).is_err());
+        assert_eq!(plugin.split_rules().exclude.len(), 1);
+
+        assert!(plugin.remove_split_rule(SplitTunnelMode::Exclude, &mail));
+        assert!(!plugin.remove_split_rule(SplitTunnelMode::Include, &mail));
+        assert!(plugin.split_rules().exclude.is_empty());
+    }
+
+    #[test]
+    fn test_uptime_follows_clock() {
+        use crate::implementation::MockClock;
+
+        let clock = Arc::new(MockClock::new(5_000));
+        let mut plugin = VpnPlugin::with_clock(VpnConfig::default(), clock.clone());
+        assert_eq!(plugin.uptime(), 0);
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert_eq!(plugin.uptime(), 0);
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(plugin.uptime(), 90);
+
+        plugin.disconnect();
+        assert_eq!(plugin.uptime(), 0);
+        assert_eq!(plugin.last_session().map(|s| s.uptime_secs), Some(90));
+    }
+
+    #[test]
+    fn test_event_listener() {
+        let mut plugin = VpnPlugin::default();
+        plugin
+            .connect(Rc::new(test_server("srv-1")))
+            .expect("Should connect without listener");
+        plugin.disconnect();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        plugin.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        plugin.disconnect();
+        assert_eq!(*events.borrow(), [
+            VpnEvent::Connecting,
+            VpnEvent::Connected,
+            VpnEvent::Disconnected(DisconnectReason::UserRequested),
+        ]);
+
+        drop(plugin);
+        assert_eq!(events.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_event_listener_on_reconnect() {
+        let (mut plugin, refusals) = dropped_connection(2);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        plugin.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+
+        *refusals.lock().expect("Should lock refusals") = 1;
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert_eq!(*events.borrow(), [
+            VpnEvent::Reconnecting,
+            VpnEvent::Disconnected(DisconnectReason::NetworkLost),
+            VpnEvent::Error(String::from("Tunnel error: srv-1 unreachable")),
+            VpnEvent::Connecting,
+            VpnEvent::Connected,
+        ]);
+    }
+
+    #[test]
+    fn test_connect_checks_protocol_support() {
+        let config = VpnConfig {
+            encryption: EncryptionAlgorithm::Aes256GcmPqc,
+            key_exchange: KeyExchangeProtocol::HybridMlKem,
+            ..VpnConfig::default()
+        };
+        let mut plugin = VpnPlugin::new(config);
+        let mut server = test_server("srv-chacha");
+        server.supported_encryption = vec![EncryptionAlgorithm::ChaCha20Poly1305];
+
+        assert_eq!(
+            plugin.connect(Rc::new(server.clone())),
+            Err(VpnError::Configuration(
+                "Server srv-chacha rejected: encryption aes256_gcm_pqc not supported".to_string()
+            ))
+        );
+        assert!(!plugin.is_connected());
+        assert_eq!(plugin.attempt_history().count(), 0);
+
+        server.supported_encryption.push(EncryptionAlgorithm::Aes256GcmPqc);
+        server.supported_key_exchange = vec![KeyExchangeProtocol::HybridMlKem];
+        plugin.connect(Rc::new(server)).expect("Should connect");
+        assert!(plugin.is_connected());
+    }
+
+    #[test]
+    fn test_connect_requires_obfuscation_support() {
+        let config = VpnConfig {
+            transport: TransportMode::ObfuscatedTls,
+            ..VpnConfig::default()
+        };
+        let mut plugin = VpnPlugin::new(config);
+        let mut server = test_server("srv-plain");
+
+        assert_eq!(
+            plugin.connect(Rc::new(server.clone())),
+            Err(VpnError::Configuration(
+                "Server srv-plain rejected: transport obfuscated_tls not supported".to_string()
+            ))
+        );
+        assert!(!plugin.is_connected());
+
+        server.supports_obfuscation = true;
+        plugin.connect(Rc::new(server)).expect("Should connect");
+        assert!(plugin.is_connected());
+    }
+
+    /// Handshake that completes on its `polls_needed`th poll.
+    struct SlowHandshake {
+        polls_needed: u32,
+        polls:        Mutex<u32>,
+    }
+
+    impl HandshakeStep for SlowHandshake {
+        fn poll(&self, tunnel: &VpnTunnel) -> VpnResult<HandshakeProgress> {
+            assert_eq!(tunnel.state, TunnelState::KeyExchange);
+            let mut polls = self.polls.lock().expect("Should lock polls");
+            *polls += 1;
+            Ok(if *polls >= self.polls_needed {
+                HandshakeProgress::Complete
+            } else {
+                HandshakeProgress::Pending
+            })
+        }
+    }
+
+    fn slow_handshake_plugin(
+        polls_needed: u32,
+    ) -> (VpnPlugin, Arc<crate::implementation::MockClock>) {
+        let clock = Arc::new(crate::implementation::MockClock::new(1_000));
+        let config = VpnConfig { connect_timeout_secs: 2, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+        plugin.set_handshake_step(Box::new(SlowHandshake {
+            polls_needed,
+            polls: Mutex::new(0),
+        }));
+        (plugin, clock)
+    }
+
+    #[test]
+    fn test_connect_completes_within_timeout() {
+        let (mut plugin, clock) = slow_handshake_plugin(5);
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert_eq!(plugin.state(), TunnelState::Connected);
+        assert_eq!(clock.now_millis(), 1_400);
+    }
+
+    #[test]
+    fn test_connect_times_out() {
+        let (mut plugin, clock) = slow_handshake_plugin(u32::MAX);
+        assert_eq!(
+            plugin.connect(Rc::new(test_server("srv-1"))),
+            Err(VpnError::Connection("timeout".to_string()))
+        );
+        assert_eq!(clock.now_millis(), 3_000);
+        assert!(!plugin.is_connected());
+        assert!(plugin.current_tunnel().is_none());
+
+        // A longer window lets the same handshake finish
+        let (mut plugin, _) = slow_handshake_plugin(25);
+        assert!(plugin.connect(Rc::new(test_server("srv-1"))).is_err());
+        let (mut plugin, _) = slow_handshake_plugin(25);
+        plugin
+            .connect_with_timeout(Rc::new(test_server("srv-1")), 5)
+            .expect("Should connect within the longer window");
+    }
+
+    #[test]
+    fn test_last_disconnect_reason() {
+        let mut plugin = VpnPlugin::default();
+        assert_eq!(plugin.last_disconnect_reason(), None);
+        plugin.disconnect();
+        assert_eq!(plugin.last_disconnect_reason(), None, "No tunnel ended");
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        plugin.disconnect();
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::UserRequested)
+        );
+
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        plugin.disconnect_with_reason(DisconnectReason::ServerClosed);
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::ServerClosed)
+        );
+
+        let (mut plugin, _) = dropped_connection(1);
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::NetworkLost)
+        );
+
+        let (mut plugin, _) = slow_handshake_plugin(u32::MAX);
+        assert!(plugin.connect(Rc::new(test_server("srv-1"))).is_err());
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::NetworkLost)
+        );
+    }
+
+    #[test]
+    fn test_failed_reauthentication_records_auth_failed() {
+        let mut plugin = VpnPlugin::default();
+        plugin.set_authenticator(Box::new(TokenAuthenticator { valid_token: "fresh" }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.refresh_credentials(credentials("stale")).is_err());
+        assert_eq!(plugin.last_disconnect_reason(), None, "Tunnel still up");
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        plugin.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::AuthFailed)
+        );
+        assert_eq!(events.borrow()[..2], [
+            VpnEvent::Reconnecting,
+            VpnEvent::Disconnected(DisconnectReason::AuthFailed),
+        ]);
+
+        plugin.disconnect();
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::UserRequested)
+        );
+    }
+
+    #[test]
+    fn test_current_reconnect_attempt() {
+        let (mut plugin, refusals) = dropped_connection(3);
+        assert_eq!(plugin.current_reconnect_attempt(), 0);
+
+        *refusals.lock().expect("Should lock refusals") = u32::MAX;
+        let mut seen = Vec::new();
+        assert!(plugin.reconnect(|delay| seen.push(delay)).is_err());
+        assert_eq!(seen.len(), 3);
+        assert_eq!(plugin.current_reconnect_attempt(), 3);
+
+        // Further scheduled retries never push the count past the maximum
+        let server = Rc::new(test_server("srv-1"));
+        plugin.schedule_reconnect(Rc::clone(&server));
+        assert_eq!(plugin.current_reconnect_attempt(), 3);
+
+        *refusals.lock().expect("Should lock refusals") = 0;
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert_eq!(plugin.current_reconnect_attempt(), 0);
+
+        for expected in 1..=2 {
+            plugin.schedule_reconnect(Rc::clone(&server));
+            assert_eq!(plugin.current_reconnect_attempt(), expected);
+        }
+        plugin.disconnect();
+        assert_eq!(plugin.current_reconnect_attempt(), 0);
+    }
+
+    fn keepalive_plugin() -> (VpnPlugin, Arc<crate::implementation::MockClock>) {
+        let clock = Arc::new(crate::implementation::MockClock::new(1_000_000));
+        let config = VpnConfig { keepalive_interval_secs: 10, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        (plugin, clock)
+    }
+
+    #[test]
+    fn test_check_liveness_healthy_tunnel() {
+        let (mut plugin, clock) = keepalive_plugin();
+        assert_eq!(plugin.last_heartbeat(), Some(1_000));
+
+        for _ in 0..5 {
+            clock.advance(Duration::from_secs(15));
+            plugin.record_heartbeat();
+            assert!(!plugin.check_liveness(clock.now_secs()));
+        }
+        assert_eq!(plugin.last_heartbeat(), Some(1_075));
+        assert!(
+            !plugin.check_liveness(1_095),
+            "Exactly two intervals is not stale"
+        );
+        assert!(plugin.is_connected());
+    }
+
+    #[test]
+    fn test_check_liveness_stale_tunnel_reconnects() {
+        let (mut plugin, clock) = keepalive_plugin();
+        let observed = record_events(&mut plugin);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        plugin.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+
+        clock.advance(Duration::from_secs(21));
+        assert!(plugin.check_liveness(clock.now_secs()));
+        assert_eq!(plugin.state(), TunnelState::Reconnecting);
+        assert!(!plugin.is_connected());
+        assert_eq!(events.borrow()[..], [VpnEvent::Reconnecting]);
+        assert_eq!(observed.lock().expect("Should lock observed")[..], [
+            String::from("warning: No heartbeat from srv-1 since 1000")
+        ]);
+        assert!(
+            !plugin.check_liveness(clock.now_secs()),
+            "Already reconnecting"
+        );
+
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert!(plugin.is_connected());
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::NetworkLost)
+        );
+        assert_eq!(plugin.last_heartbeat(), Some(1_021));
+        assert_eq!(events.borrow()[1..3], [
+            VpnEvent::Reconnecting,
+            VpnEvent::Disconnected(DisconnectReason::NetworkLost),
+        ]);
+    }
+
+    #[test]
+    fn test_connect_to_id() {
+        let mut plugin = VpnPlugin::default();
+        for id in ["srv-1", "srv-2"] {
+            plugin.router_mut().add_server(Rc::new(RefCell::new(test_server(id))));
+        }
+
+        assert_eq!(
+            plugin.connect_to_id("srv-9"),
+            Err(VpnError::Connection("Unknown server srv-9".to_string()))
+        );
+        assert!(!plugin.is_connected());
+
+        plugin.connect_to_id("srv-2").expect("Should connect");
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-2"));
+    }
+
+    /// Provider that logs tunnel creation, key exchanges and teardown, and
+    /// accepts every session resumption.
+    #[derive(Default)]
+    struct RecordingProvider {
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TunnelProvider for RecordingProvider {
+        fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel> {
+            let mut log = self.log.lock().expect("Should lock log");
+            log.push(format!("create {}", server.id));
+            Ok(VpnTunnel {
+                id:           log.iter().filter(|e| e.starts_with("create")).count() as u64,
+                server:       server.clone(),
+                state:        TunnelState::Connecting,
+                encryption:   EncryptionAlgorithm::default(),
+                key_exchange: KeyExchangeProtocol::default(),
+                stats:        ConnectionStats::default(),
+                role:         TunnelRole::Primary,
+            })
+        }
+
+        fn create_tunnel_via(
+            &self,
+            server: &VpnServer,
+            via_tunnel_id: u64,
+        ) -> VpnResult<VpnTunnel> {
+            let tunnel = self.create_tunnel(server)?;
+            let mut log = self.log.lock().expect("Should lock log");
+            if let Some(entry) = log.last_mut() {
+                entry.push_str(&format!(" via {via_tunnel_id}"));
+            }
+            Ok(tunnel)
+        }
+
+        fn exchange_keys(&self, tunnel_id: u64, _ciphertext: &[u8]) -> VpnResult<()> {
+            self.log.lock().expect("Should lock log").push(format!("exchange {tunnel_id}"));
+            Ok(())
+        }
+
+        fn resume_session(&self, tunnel_id: u64, _nonce: &[u8]) -> VpnResult<bool> {
+            self.log.lock().expect("Should lock log").push(format!("resume {tunnel_id}"));
+            Ok(true)
+        }
+
+        fn tunnel_state(&self, _tunnel_id: u64) -> VpnResult<TunnelState> {
+            Ok(TunnelState::Connected)
+        }
+
+        fn tunnel_stats(&self, _tunnel_id: u64) -> VpnResult<ConnectionStats> {
+            Ok(ConnectionStats::default())
+        }
+
+        fn destroy_tunnel(&self, tunnel_id: u64) -> VpnResult<()> {
+            self.log.lock().expect("Should lock log").push(format!("destroy {tunnel_id}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_connect_multihop() {
+        let mut plugin = VpnPlugin::default();
+        let provider = RecordingProvider::default();
+        let log = Arc::clone(&provider.log);
+        plugin.set_tunnel_provider(Box::new(provider));
+        assert_eq!(plugin.hop_count(), 0);
+
+        let entry = Rc::new(test_server("srv-entry"));
+        assert!(matches!(
+            plugin.connect_multihop(Rc::clone(&entry), Rc::clone(&entry)),
+            Err(VpnError::Configuration(_))
+        ));
+
+        plugin
+            .connect_multihop(entry, Rc::new(test_server("srv-exit")))
+            .expect("Should connect both hops");
+        assert!(plugin.is_connected());
+        assert_eq!(plugin.hop_count(), 2);
+        let ids: Vec<&str> = plugin.hops().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["srv-entry", "srv-exit"]);
+        assert_eq!(current_server(&plugin).as_deref(), Some("srv-exit"));
+        let roles: Vec<TunnelRole> = plugin.chain_tunnels().iter().map(|t| t.role).collect();
+        assert_eq!(roles, [TunnelRole::Hop(0), TunnelRole::Primary]);
+        let primary = plugin.tunnel_manager.tunnels_by_role(TunnelRole::Primary);
+        assert_eq!(primary.len(), 1);
+        assert_eq!(primary[0].server.id, "srv-exit");
+        assert_eq!(plugin.chain_security_level(), Some(SecurityLevel::Hybrid));
+        assert!(matches!(
+            plugin.connect(Rc::new(test_server("srv-other"))),
+            Err(VpnError::Connection(_))
+        ));
+
+        plugin.disconnect();
+        assert_eq!(plugin.hop_count(), 0);
+        assert_eq!(plugin.tunnel_manager.tunnels().count(), 0);
+        assert_eq!(log.lock().expect("Should lock log")[..], [
+            "create srv-entry",
+            "exchange 1",
+            "create srv-exit via 1",
+            "exchange 2",
+            "destroy 2",
+            "destroy 1",
+        ]);
+    }
+
+    /// Handshake that refuses one server.
+    struct RefusingHandshake {
+        server_id: &'static str,
+    }
+
+    impl HandshakeStep for RefusingHandshake {
+        fn poll(&self, tunnel: &VpnTunnel) -> VpnResult<HandshakeProgress> {
+            if tunnel.server.id == self.server_id {
+                return Err(VpnError::Connection("handshake refused".to_string()));
+            }
+            Ok(HandshakeProgress::Complete)
+        }
+    }
+
+    #[test]
+    fn test_connect_multihop_tears_down_entry_when_exit_fails() {
+        let mut plugin = VpnPlugin::default();
+        let mut exit = test_server("srv-exit");
+        exit.supported_encryption = vec![EncryptionAlgorithm::ChaCha20Poly1305];
+        assert!(matches!(
+            plugin.connect_multihop(Rc::new(test_server("srv-entry")), Rc::new(exit)),
+            Err(VpnError::Configuration(_))
+        ));
+        assert_eq!(plugin.attempt_history().count(), 0, "Exit checked up front");
+
+        plugin.set_handshake_step(Box::new(RefusingHandshake { server_id: "srv-exit" }));
+        assert_eq!(
+            plugin.connect_multihop(
+                Rc::new(test_server("srv-entry")),
+                Rc::new(test_server("srv-exit"))
+            ),
+            Err(VpnError::Connection("handshake refused".to_string()))
+        );
+        assert!(!plugin.is_connected());
+        assert_eq!(plugin.hop_count(), 0);
+        assert_eq!(plugin.tunnel_manager.tunnels().count(), 0);
+    }
+
+    #[test]
+    fn test_connect_multihop_needs_a_chaining_provider() {
+        let mut plugin = VpnPlugin::default();
+        plugin.set_tunnel_provider(Box::new(FlakyProvider::default()));
+        assert_eq!(
+            plugin.connect_multihop(
+                Rc::new(test_server("srv-entry")),
+                Rc::new(test_server("srv-exit"))
+            ),
+            Err(VpnError::Tunnel(
+                "Provider cannot chain srv-exit through tunnel 1".to_string()
+            ))
+        );
+        assert!(!plugin.is_connected());
+        assert_eq!(plugin.hop_count(), 0);
+        assert_eq!(plugin.tunnel_manager.tunnels().count(), 0);
+    }
+
+    fn set_packet_loss(plugin: &mut VpnPlugin, packet_loss: f32) {
+        let id = plugin.tunnel_id.expect("Should have a tunnel");
+        let stats = ConnectionStats { packet_loss, ..ConnectionStats::default() };
+        plugin.tunnel_manager.update_stats(id, stats).expect("Should update stats");
+    }
+
+    #[test]
+    fn test_evaluate_quality_alerts_on_packet_loss() {
+        let mut plugin = VpnPlugin::default();
+        assert!(!plugin.evaluate_quality(), "Not connected");
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&events);
+        plugin.set_event_listener(Box::new(move |event| sink.borrow_mut().push(event)));
+
+        set_packet_loss(&mut plugin, 4.0);
+        assert!(!plugin.evaluate_quality());
+        assert!(events.borrow().is_empty());
+
+        set_packet_loss(&mut plugin, 12.0);
+        assert!(plugin.evaluate_quality());
+        assert!(plugin.evaluate_quality());
+        assert_eq!(
+            events.borrow()[..],
+            [VpnEvent::PacketLossHigh],
+            "Alerts once"
+        );
+        assert!(plugin.is_connected());
+
+        set_packet_loss(&mut plugin, 1.0);
+        assert!(!plugin.evaluate_quality());
+        set_packet_loss(&mut plugin, 8.0);
+        assert!(plugin.evaluate_quality());
+        assert_eq!(events.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_evaluate_quality_reconnects_when_configured() {
+        let config = VpnConfig { reconnect_on_packet_loss: true, ..VpnConfig::default() };
+        let mut plugin = VpnPlugin::new(config);
+        let warnings = record_events(&mut plugin);
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        set_packet_loss(&mut plugin, 30.0);
+        assert!(plugin.evaluate_quality());
+        assert!(!plugin.is_connected());
+        assert!(plugin.is_reconnecting());
+        assert_eq!(
+            plugin.last_disconnect_reason(),
+            Some(DisconnectReason::NetworkLost)
+        );
+        assert_eq!(warnings.lock().expect("Should lock warnings")[..], [
+            String::from("warning: Packet loss 30.0% on srv-1, reconnecting")
+        ]);
+    }
+
+    #[test]
+    fn test_establish_rejects_second_tunnel_before_side_effects() {
+        let mut plugin = VpnPlugin::default();
+        let provider = RecordingProvider::default();
+        let log = Arc::clone(&provider.log);
+        plugin.set_tunnel_provider(Box::new(provider));
+        plugin.set_authenticator(Box::new(TokenAuthenticator { valid_token: "fresh" }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.refresh_credentials(credentials("stale")).is_err());
+        assert_eq!(plugin.state(), TunnelState::Reconnecting);
+
+        assert_eq!(
+            plugin.connect(Rc::new(test_server("srv-2"))),
+            Err(VpnError::Tunnel("Tunnel already active".to_string()))
+        );
+        assert_eq!(log.lock().expect("Should lock log")[..], [
+            "create srv-1",
+            "exchange 1"
+        ]);
+        assert_eq!(plugin.provider_tunnel, Some(1));
+
+        plugin.disconnect();
+        assert_eq!(log.lock().expect("Should lock log")[..], [
+            "create srv-1",
+            "exchange 1",
+            "destroy 1"
+        ]);
+    }
+
+    #[test]
+    fn test_reconnect_resumes_session() {
+        let mut plugin = VpnPlugin::default();
+        let provider = RecordingProvider::default();
+        let log = Arc::clone(&provider.log);
+        plugin.set_tunnel_provider(Box::new(provider));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert!(plugin.key_exchange.as_ref().and_then(PqcKeyExchange::shared_secret).is_some());
+
+        let id = plugin.tunnel_id.expect("Should have a tunnel");
+        plugin
+            .tunnel_manager
+            .update_state(id, TunnelState::Error)
+            .expect("Should update state");
+        plugin.reconnect(|_| {}).expect("Should reconnect");
+        assert!(
+            plugin
+                .key_exchange
+                .as_ref()
+                .and_then(PqcKeyExchange::resumption_nonce)
+                .is_some()
+        );
+
+        // An explicit disconnect discards the ticket
+        plugin.disconnect();
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+        assert_eq!(log.lock().expect("Should lock log")[..], [
+            "create srv-1",
+            "exchange 1",
+            "destroy 1",
+            "create srv-1",
+            "resume 2",
+            "destroy 2",
+            "create srv-1",
+            "exchange 3",
+        ]);
+    }
+
+    #[test]
+    fn test_failed_reauthentication_keeps_auth_error_on_bad_transition() {
+        let mut plugin = VpnPlugin::default();
+        let warnings = record_events(&mut plugin);
+        plugin.set_authenticator(Box::new(TokenAuthenticator { valid_token: "fresh" }));
+        plugin.connect(Rc::new(test_server("srv-1"))).expect("Should connect");
+
+        // Another connected tunnel keeps the plugin connected while its own
+        // tunnel is draining, which cannot move to `Reconnecting`
+        let other = plugin.tunnel_manager.create_tunnel(Rc::new(test_server("srv-2")));
+        for state in [TunnelState::KeyExchange, TunnelState::Connected] {
+            plugin.tunnel_manager.update_state(other, state).expect("Should advance tunnel");
+        }
+        let id = plugin.tunnel_id.expect("Should have a tunnel");
+        plugin
+            .tunnel_manager
+            .update_state(id, TunnelState::Disconnecting)
+            .expect("Should start draining");
+
+        let result = plugin.refresh_credentials(credentials("stale"));
+        assert!(matches!(result, Err(VpnError::Authentication(_))));
+        assert_eq!(plugin.drop_reason, Some(DisconnectReason::AuthFailed));
+        let events = warnings.lock().expect("Should lock events");
+        assert!(events.iter().any(|e| e.contains("Could not mark tunnel for reconnect")));
+        assert!(events.contains(&String::from("error: Authentication error: token rejected")));
     }
 }
@@ -1,19 +1,191 @@
 //! Neural network-optimized routing implementation.
 
-use std::{cell::RefCell, rc::Rc};
+use core::str::FromStr;
+use std::{
+    cell::{Ref, RefCell},
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
 
-use crate::types::VpnServer;
+use crate::{
+    errors::{VpnError, VpnResult},
+    implementation::{
+        SystemClock, VpnConfig,
+        json::{self, Value},
+    },
+    traits::{Clock, Pinger},
+    types::{
+        EncryptionAlgorithm, ExclusionReason, KeyExchangeProtocol, ServerHealth, ServerRegion,
+        ServerSummary, SortKey, TransportMode, VpnServer,
+    },
+};
+
+/// Score bonus applied to recommended servers when preferred.
+const RECOMMENDED_BONUS: f32 = 0.2;
+
+/// Score penalty for a server that fails every connection attempt.
+const UNRELIABLE_PENALTY: f32 = 0.5;
+
+/// Largest user rating magnitude; ratings are clamped to `-5..=5`.
+const MAX_RATING: i8 = 5;
+
+/// Default score adjustment for a maximal user rating.
+const DEFAULT_RATING_WEIGHT: f32 = 0.2;
+
+/// Score penalty for a server whose health probe reports it degraded.
+const DEGRADED_PENALTY: f32 = 0.3;
+
+/// Default number of top servers rotated through by
+/// [`NeuralRouter::next_balanced_server`].
+const DEFAULT_BALANCE_POOL_SIZE: usize = 3;
+
+/// Outcomes needed before the success rate affects the score.
+const MIN_OUTCOMES_FOR_PENALTY: u32 = 5;
+
+/// Connection outcomes recorded for one server.
+#[derive(Debug, Clone, Copy, Default)]
+struct OutcomeCounts {
+    successes: u32,
+    failures:  u32,
+}
+
+impl OutcomeCounts {
+    fn total(self) -> u32 {
+        self.successes.saturating_add(self.failures)
+    }
+
+    fn success_rate(self) -> Option<f32> {
+        let total = self.total();
+        (total > 0).then(|| self.successes as f32 / total as f32)
+    }
+}
 
 /// Neural router for optimal server selection.
 pub struct NeuralRouter {
-    servers: Vec<Rc<RefCell<VpnServer>>>,
+    servers:            Vec<Rc<RefCell<VpnServer>>>,
+    deterministic:      bool,
+    prefer_recommended: bool,
+    outcomes:           HashMap<String, OutcomeCounts>,
+    ratings:            HashMap<String, i8>,
+    rating_weight:      f32,
+    health:             HashMap<String, ServerHealth>,
+    balance_pool_size:  usize,
+    balance_cursor:     usize,
+    excluded_countries: HashSet<String>,
+    favorites:          HashSet<String>,
+    blocklist:          HashSet<String>,
+    cooldowns:          HashMap<String, u64>,
+    clock:              Arc<dyn Clock>,
 }
 
 impl NeuralRouter {
     /// Create a new neural router.
     #[must_use]
     pub fn new() -> Self {
-        Self { servers: Vec::new() }
+        Self {
+            servers:            Vec::new(),
+            deterministic:      false,
+            prefer_recommended: false,
+            outcomes:           HashMap::new(),
+            ratings:            HashMap::new(),
+            rating_weight:      DEFAULT_RATING_WEIGHT,
+            health:             HashMap::new(),
+            balance_pool_size:  DEFAULT_BALANCE_POOL_SIZE,
+            balance_cursor:     0,
+            excluded_countries: HashSet::new(),
+            favorites:          HashSet::new(),
+            blocklist:          HashSet::new(),
+            cooldowns:          HashMap::new(),
+            clock:              Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the clock cooldowns are measured against.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Enable or disable deterministic selection.
+    ///
+    /// When enabled, servers that score equally are ordered by `id` so the
+    /// chosen server does not depend on the order servers were added.
+    pub fn set_deterministic(&mut self, on: bool) {
+        self.deterministic = on;
+    }
+
+    /// Check if deterministic selection is enabled.
+    #[must_use]
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Enable or disable preferring recommended servers.
+    ///
+    /// When enabled, recommended servers score as if their load were
+    /// `RECOMMENDED_BONUS` (0.2) lower, so they win unless a regular server is
+    /// clearly less loaded.
+    pub fn set_prefer_recommended(&mut self, on: bool) {
+        self.prefer_recommended = on;
+    }
+
+    /// Set how much user ratings move the score.
+    ///
+    /// A rating of `5` lowers the score by `weight` and `-5` raises it by
+    /// `weight`, scaling linearly in between. Defaults to
+    /// `DEFAULT_RATING_WEIGHT` (0.2); `0.0` ignores ratings.
+    pub fn set_rating_weight(&mut self, weight: f32) {
+        self.rating_weight = weight;
+    }
+
+    /// Never select servers in a country (case-insensitive country code).
+    ///
+    /// Exclusions apply to every selection method and override favorites.
+    pub fn exclude_country(&mut self, code: &str) {
+        self.excluded_countries.insert(code.to_ascii_uppercase());
+    }
+
+    /// Mark a server as a favorite.
+    ///
+    /// [`find_optimal_server`](Self::find_optimal_server) picks the best
+    /// favorite and only falls back to other servers when no favorite is
+    /// eligible.
+    pub fn add_favorite(&mut self, id: &str) {
+        self.favorites.insert(id.to_string());
+    }
+
+    /// Never select a server, whatever its health or score.
+    ///
+    /// The blocklist is keyed by server id, so it survives list refreshes.
+    pub fn block_server(&mut self, id: &str) {
+        self.blocklist.insert(id.to_string());
+    }
+
+    /// Allow a blocklisted server to be selected again.
+    pub fn unblock_server(&mut self, id: &str) {
+        self.blocklist.remove(id);
+    }
+
+    /// Check if a server is blocklisted.
+    #[must_use]
+    pub fn is_blocked(&self, id: &str) -> bool {
+        self.blocklist.contains(id)
+    }
+
+    /// Skip a server for `duration`, e.g. after it refused a connection.
+    ///
+    /// A later call replaces the deadline rather than extending it.
+    pub fn cool_down_server(&mut self, id: &str, duration: Duration) {
+        let until = self.clock.now_millis().saturating_add(duration.as_millis() as u64);
+        self.cooldowns.insert(id.to_string(), until);
+    }
+
+    /// Check if a server is still cooling down.
+    #[must_use]
+    pub fn is_cooling_down(&self, id: &str) -> bool {
+        self.cooldowns.get(id).is_some_and(|&until| self.clock.now_millis() < until)
     }
 
     /// Add a server to the routing pool.
@@ -21,43 +193,738 @@ impl NeuralRouter {
         self.servers.push(server);
     }
 
+    /// Add every server in a JSON array of server objects, returning how
+    /// many were added.
+    ///
+    /// Objects use the [`VpnServer`] field names. `id`, `hostname`, `port`
+    /// and `country` are required; other fields default when absent or
+    /// `null`, and unknown fields are ignored. Enum values use their string
+    /// forms, `port_map` maps transport names to ports, and `load` is clamped
+    /// to `0.0..=1.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if the payload is not valid JSON,
+    /// not an array, any entry is missing a required field or has one of the
+    /// wrong type, or an id repeats one in the payload or the pool. Nothing
+    /// is added on error.
+    pub fn load_servers_from_json(&mut self, json: &str) -> VpnResult<usize> {
+        let Value::Array(entries) = json::parse(json)? else {
+            return Err(VpnError::Configuration(
+                "Server list must be a JSON array".to_string(),
+            ));
+        };
+        let mut ids: HashSet<String> = self.servers.iter().map(|s| s.borrow().id.clone()).collect();
+        let mut servers = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let json = JsonServer { index, entry };
+            let server = json.parse()?;
+            if !ids.insert(server.id.clone()) {
+                return Err(json.error(format!("duplicate id `{}`", server.id)));
+            }
+            servers.push(server);
+        }
+
+        let count = servers.len();
+        self.servers.extend(servers.into_iter().map(|s| Rc::new(RefCell::new(s))));
+        Ok(count)
+    }
+
+    /// Add a server only if it conforms to the configuration's policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if [`VpnConfig::accepts_server`]
+    /// rejects the server or its latest probe found it unreachable; the pool
+    /// is left unchanged.
+    pub fn add_server_checked(
+        &mut self,
+        server: Rc<RefCell<VpnServer>>,
+        config: &VpnConfig,
+    ) -> VpnResult<()> {
+        {
+            let candidate = server.borrow();
+            config.accepts_server(&candidate)?;
+            if self.server_health(&candidate.id) == ServerHealth::Unreachable {
+                return Err(config.rejection(&candidate, ExclusionReason::Unreachable));
+            }
+        }
+        self.servers.push(server);
+        Ok(())
+    }
+
     /// Get all available servers.
     #[must_use]
     pub fn servers(&self) -> &[Rc<RefCell<VpnServer>>] {
         &self.servers
     }
 
+    /// Get the pooled server with the given id.
+    #[must_use]
+    pub fn find_by_id(&self, id: &str) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers.iter().find(|s| s.borrow().id == id)
+    }
+
+    /// Get every pooled server ordered for display.
+    ///
+    /// Eligibility is not considered. Servers that tie on `by` keep their
+    /// pool order.
+    #[must_use]
+    pub fn servers_sorted(&self, by: SortKey) -> Vec<Rc<RefCell<VpnServer>>> {
+        let mut servers = self.servers.clone();
+        servers.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
+            match by {
+                SortKey::Load => a.load.total_cmp(&b.load),
+                SortKey::Latency => {
+                    let latency = |s: &VpnServer| s.latency_ms.unwrap_or(u32::MAX);
+                    latency(&a).cmp(&latency(&b))
+                },
+                SortKey::Country => a.country.to_lowercase().cmp(&b.country.to_lowercase()),
+                SortKey::Capacity => free_slots(&b).cmp(&free_slots(&a)),
+            }
+        });
+        servers
+    }
+
+    /// Remove every server with the given id, returning whether any was
+    /// removed.
+    ///
+    /// Outcomes, ratings and favorites recorded for the id are kept, so
+    /// they apply again if the server is re-added.
+    pub fn remove_server(&mut self, id: &str) -> bool {
+        let before = self.servers.len();
+        self.servers.retain(|s| s.borrow().id != id);
+        self.servers.len() != before
+    }
+
+    /// Replace the server with the given id in place, returning whether it
+    /// was found.
+    ///
+    /// The existing `Rc` is kept and only its contents are swapped, so
+    /// holders of the old handle see the update.
+    pub fn replace_server(&mut self, id: &str, new: VpnServer) -> bool {
+        let Some(server) = self.find_by_id(id) else {
+            return false;
+        };
+        *server.borrow_mut() = new;
+        true
+    }
+
+    /// Remove every server from the pool.
+    pub fn clear(&mut self) {
+        self.servers.clear();
+        self.balance_cursor = 0;
+    }
+
+    /// Get servers marked as recommended, in pool order.
+    #[must_use]
+    pub fn recommended_servers(&self) -> Vec<Rc<RefCell<VpnServer>>> {
+        self.servers.iter().filter(|s| s.borrow().recommended).map(Rc::clone).collect()
+    }
+
+    /// Record the outcome of a connection attempt to a server.
+    ///
+    /// Outcomes are keyed by server id, so they survive the server being
+    /// removed and re-added when the list is refreshed.
+    pub fn record_outcome(&mut self, id: &str, success: bool) {
+        let counts = self.outcomes.entry(id.to_string()).or_default();
+        if success {
+            counts.successes = counts.successes.saturating_add(1);
+        } else {
+            counts.failures = counts.failures.saturating_add(1);
+        }
+    }
+
+    /// Set or clear the user's rating of a server, clamped to `-5..=5`.
+    ///
+    /// Ratings are keyed by server id, so they survive list refreshes.
+    pub fn rate_server(&mut self, id: &str, rating: Option<i8>) {
+        match rating {
+            Some(rating) => {
+                self.ratings.insert(id.to_string(), rating.clamp(-MAX_RATING, MAX_RATING));
+            },
+            None => {
+                self.ratings.remove(id);
+            },
+        }
+    }
+
+    /// Get the user's rating of a server.
+    #[must_use]
+    pub fn server_rating(&self, id: &str) -> Option<i8> {
+        self.ratings.get(id).copied()
+    }
+
+    /// Record the result of a health probe of a server.
+    ///
+    /// Unreachable servers and those in maintenance are never selected, and
+    /// degraded ones score `DEGRADED_PENALTY` (0.3) worse. Health is keyed by
+    /// server id, so it survives list refreshes; unprobed servers count as
+    /// healthy.
+    pub fn set_server_health(&mut self, id: &str, health: ServerHealth) {
+        self.health.insert(id.to_string(), health);
+    }
+
+    /// Get the latest probed health of a server.
+    #[must_use]
+    pub fn server_health(&self, id: &str) -> ServerHealth {
+        self.health.get(id).copied().unwrap_or_default()
+    }
+
+    /// Get servers whose latest probe found them healthy, in pool order.
+    #[must_use]
+    pub fn healthy_servers(&self) -> Vec<Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| self.server_health(&s.borrow().id) == ServerHealth::Healthy)
+            .map(Rc::clone)
+            .collect()
+    }
+
+    /// Fraction of recorded connection attempts to a server that succeeded.
+    ///
+    /// Returns `None` if no outcomes have been recorded for `id`.
+    #[must_use]
+    pub fn success_rate(&self, id: &str) -> Option<f32> {
+        self.outcomes.get(id).and_then(|c| c.success_rate())
+    }
+
+    /// Get up to `n` pooled servers with the highest success rate.
+    ///
+    /// Servers without recorded outcomes are skipped; equal rates are ordered
+    /// by selection preference.
+    #[must_use]
+    pub fn most_reliable_servers(&self, n: usize) -> Vec<Rc<RefCell<VpnServer>>> {
+        let mut rated: Vec<_> = self
+            .servers
+            .iter()
+            .filter_map(|s| self.success_rate(&s.borrow().id).map(|rate| (rate, s)))
+            .collect();
+        rated.sort_by(|(rate_a, a), (rate_b, b)| {
+            rate_b
+                .partial_cmp(rate_a)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| self.compare(&a.borrow(), &b.borrow()))
+        });
+        rated.into_iter().take(n).map(|(_, s)| Rc::clone(s)).collect()
+    }
+
     /// Find best server for a given country.
     #[must_use]
     pub fn find_best_server(&self, country: &str) -> Option<&Rc<RefCell<VpnServer>>> {
         self.servers
             .iter()
-            .filter(|s| s.borrow().country == country && s.borrow().pqc_enabled)
+            .filter(|s| s.borrow().country == country && self.is_eligible(&s.borrow()))
+            .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+    }
+
+    /// Find best server overall (lowest load, PQC enabled, reachable),
+    /// preferring favorites.
+    #[must_use]
+    pub fn find_optimal_server(&self) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.find_optimal_where(|_| true)
+    }
+
+    /// Like [`find_optimal_server`](Self::find_optimal_server), but skips
+    /// servers at their connection cap however low their load.
+    #[must_use]
+    pub fn find_optimal_server_with_capacity(&self) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.find_optimal_where(|s| !s.is_full())
+    }
+
+    /// Find the best eligible server passing `keep`, preferring favorites.
+    fn find_optimal_where(
+        &self,
+        keep: impl Fn(&VpnServer) -> bool,
+    ) -> Option<&Rc<RefCell<VpnServer>>> {
+        let best = |favorites_only: bool| {
+            self.servers
+                .iter()
+                .filter(|s| {
+                    let s = s.borrow();
+                    self.is_eligible(&s)
+                        && keep(&s)
+                        && (!favorites_only || self.favorites.contains(&s.id))
+                })
+                .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+        };
+        best(true).or_else(|| best(false))
+    }
+
+    /// Find best server carrying the given tag (lowest load, PQC enabled).
+    #[must_use]
+    pub fn find_best_with_tag(&self, tag: &str) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| self.is_eligible(&s.borrow()) && s.borrow().has_tag(tag))
+            .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+    }
+
+    /// Find best server supporting both an encryption algorithm and a key
+    /// exchange protocol (lowest load, PQC enabled).
+    ///
+    /// Servers with empty capability lists are treated as supporting
+    /// everything.
+    #[must_use]
+    pub fn find_server_supporting(
+        &self,
+        encryption: EncryptionAlgorithm,
+        key_exchange: KeyExchangeProtocol,
+    ) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| {
+                let s = s.borrow();
+                self.is_eligible(&s)
+                    && s.supports_encryption(encryption)
+                    && s.supports_key_exchange(key_exchange)
+            })
+            .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+    }
+
+    /// Find the server with the highest security rank.
+    ///
+    /// Unlike [`find_optimal_server`](Self::find_optimal_server) this favors
+    /// cryptographic strength over load; load only breaks ties. See
+    /// [`VpnServer::security_rank`] for the ranking weights.
+    #[must_use]
+    pub fn find_most_secure(&self) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| self.availability_exclusion(&s.borrow()).is_none())
             .min_by(|a, b| {
-                a.borrow()
-                    .load
-                    .partial_cmp(&b.borrow().load)
-                    .unwrap_or(std::cmp::Ordering::Equal)
+                let (a, b) = (a.borrow(), b.borrow());
+                b.security_rank().cmp(&a.security_rank()).then_with(|| self.compare(&a, &b))
             })
     }
 
-    /// Find best server overall (lowest load, PQC enabled).
+    /// Find the eligible server closest to a `(lat, lon)` point.
+    ///
+    /// Distance is the great-circle distance; servers without coordinates
+    /// are skipped and equally distant servers are ordered by load.
     #[must_use]
-    pub fn find_optimal_server(&self) -> Option<&Rc<RefCell<VpnServer>>> {
-        self.servers.iter().filter(|s| s.borrow().pqc_enabled).min_by(|a, b| {
-            a.borrow()
-                .load
-                .partial_cmp(&b.borrow().load)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        })
+    pub fn find_nearest_server(&self, lat: f64, lon: f64) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| self.is_eligible(&s.borrow()))
+            .filter_map(|s| {
+                let distance = haversine_km((lat, lon), s.borrow().coordinates()?);
+                Some((distance, s))
+            })
+            .min_by(|(da, a), (db, b)| {
+                da.total_cmp(db).then_with(|| a.borrow().load.total_cmp(&b.borrow().load))
+            })
+            .map(|(_, s)| s)
+    }
+
+    /// Set how many of the best servers
+    /// [`next_balanced_server`](Self::next_balanced_server) rotates through.
+    ///
+    /// Defaults to `DEFAULT_BALANCE_POOL_SIZE` (3); `0` is treated as `1`.
+    pub fn set_balance_pool_size(&mut self, size: usize) {
+        self.balance_pool_size = size.max(1);
+    }
+
+    /// Pick the next server in a round-robin over the best eligible
+    /// servers, spreading connections instead of always using the best.
+    ///
+    /// The pool is the top `balance_pool_size` servers in
+    /// [`find_optimal_server`](Self::find_optimal_server) order, rebuilt on
+    /// every call; if it shrinks, the rotation wraps around.
+    pub fn next_balanced_server(&mut self) -> Option<Rc<RefCell<VpnServer>>> {
+        let mut pool: Vec<_> =
+            self.servers.iter().filter(|s| self.is_eligible(&s.borrow())).collect();
+        pool.sort_by(|a, b| self.compare(&a.borrow(), &b.borrow()));
+        pool.truncate(self.balance_pool_size);
+        if pool.is_empty() {
+            return None;
+        }
+
+        let index = self.balance_cursor % pool.len();
+        let server = Rc::clone(pool[index]);
+        self.balance_cursor = index + 1;
+        Some(server)
+    }
+
+    /// Select a server for a region picked in the UI.
+    ///
+    /// Concrete regions select the best server deployed there. `Auto` picks
+    /// the nearest region with an eligible server when `client_geo`
+    /// (`latitude`, `longitude`) is known, and the best server in any known
+    /// region otherwise. Returns the server together with the concrete
+    /// region it was resolved to.
+    #[must_use]
+    pub fn connect_region_selection(
+        &self,
+        region: ServerRegion,
+        client_geo: Option<(f64, f64)>,
+    ) -> Option<(&Rc<RefCell<VpnServer>>, ServerRegion)> {
+        if region != ServerRegion::Auto {
+            return self.find_best_in_region(region).map(|s| (s, region));
+        }
+
+        match client_geo {
+            Some(client) => {
+                let mut regions = ServerRegion::CONCRETE.to_vec();
+                regions.sort_by(|a, b| {
+                    let da = a.centroid().map_or(f64::MAX, |c| haversine_km(client, c));
+                    let db = b.centroid().map_or(f64::MAX, |c| haversine_km(client, c));
+                    da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+                });
+                regions.into_iter().find_map(|r| self.find_best_in_region(r).map(|s| (s, r)))
+            },
+            None => self
+                .servers
+                .iter()
+                .filter(|s| self.is_eligible(&s.borrow()) && s.borrow().region.is_some())
+                .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+                .and_then(|s| s.borrow().region.map(|r| (s, r))),
+        }
+    }
+
+    /// Find best server deployed in a concrete region.
+    fn find_best_in_region(&self, region: ServerRegion) -> Option<&Rc<RefCell<VpnServer>>> {
+        self.servers
+            .iter()
+            .filter(|s| self.is_eligible(&s.borrow()) && s.borrow().region == Some(region))
+            .min_by(|a, b| self.compare(&a.borrow(), &b.borrow()))
+    }
+
+    /// Group eligible servers by country, best first within each country.
+    ///
+    /// Countries are ordered alphabetically. See
+    /// [`by_country_with_ineligible`](Self::by_country_with_ineligible) to
+    /// also list servers the router would not select.
+    #[must_use]
+    pub fn by_country(&self) -> BTreeMap<String, Vec<ServerSummary>> {
+        self.group_by_country(false)
+    }
+
+    /// Group all servers by country, marking ineligible ones.
+    ///
+    /// Eligible servers come first within each country, ordered by score.
+    #[must_use]
+    pub fn by_country_with_ineligible(&self) -> BTreeMap<String, Vec<ServerSummary>> {
+        self.group_by_country(true)
+    }
+
+    /// List every server with its current score and eligibility, best
+    /// first.
+    ///
+    /// Eligible servers come first, each group ordered as the router would
+    /// pick them, so the first entry is what
+    /// [`find_optimal_server`](Self::find_optimal_server) returns.
+    #[must_use]
+    pub fn scored_table(&self) -> Vec<(ServerSummary, f32, bool)> {
+        self.ranked(true)
+            .iter()
+            .map(|s| {
+                let summary = self.summary(s);
+                let (score, eligible) = (summary.score, summary.eligible);
+                (summary, score, eligible)
+            })
+            .collect()
+    }
+
+    /// Measure every server with `pinger` and store the result in its
+    /// `latency_ms`.
+    ///
+    /// A server whose measurement fails has its latency cleared rather than
+    /// keeping a stale reading.
+    pub fn refresh_latencies(&mut self, pinger: &dyn Pinger) {
+        for server in &self.servers {
+            let latency = pinger.measure(&server.borrow()).ok();
+            server.borrow_mut().latency_ms = latency;
+        }
     }
 
     /// Update server load information.
     pub fn update_server_load(&mut self, server_id: &str, load: f32) {
-        if let Some(server) = self.servers.iter().find(|s| s.borrow().id == server_id) {
+        if let Some(server) = self.find_by_id(server_id) {
             server.borrow_mut().load = load.clamp(0.0, 1.0);
         }
     }
+
+    /// Update the load of many servers in a single pass.
+    ///
+    /// Each load is clamped to 0.0–1.0; when an id repeats, its last entry
+    /// wins. Unknown ids are ignored. Returns `(matched, missed)`, counting
+    /// distinct ids.
+    pub fn update_loads(&mut self, loads: &[(String, f32)]) -> (usize, usize) {
+        let loads: HashMap<&str, f32> =
+            loads.iter().map(|(id, load)| (id.as_str(), *load)).collect();
+        let mut matched = HashSet::new();
+        for server in &self.servers {
+            let mut server = server.borrow_mut();
+            if let Some((&id, load)) = loads.get_key_value(server.id.as_str()) {
+                server.load = load.clamp(0.0, 1.0);
+                matched.insert(id);
+            }
+        }
+        (matched.len(), loads.len() - matched.len())
+    }
+
+    /// Group servers by country, optionally keeping ineligible ones.
+    fn group_by_country(&self, include_ineligible: bool) -> BTreeMap<String, Vec<ServerSummary>> {
+        let mut groups: BTreeMap<String, Vec<ServerSummary>> = BTreeMap::new();
+        for server in self.ranked(include_ineligible) {
+            groups.entry(server.country.clone()).or_default().push(self.summary(&server));
+        }
+        groups
+    }
+
+    /// Borrow servers in selection order, eligible ones first.
+    fn ranked(&self, include_ineligible: bool) -> Vec<Ref<'_, VpnServer>> {
+        let mut servers: Vec<_> = self
+            .servers
+            .iter()
+            .map(|s| s.borrow())
+            .filter(|s| include_ineligible || self.is_eligible(s))
+            .collect();
+        servers.sort_by(|a, b| {
+            self.is_eligible(b).cmp(&self.is_eligible(a)).then_with(|| self.compare(a, b))
+        });
+        servers
+    }
+
+    /// Summarize a server for listings.
+    fn summary(&self, server: &VpnServer) -> ServerSummary {
+        ServerSummary {
+            id:       server.id.clone(),
+            city:     server.city.clone(),
+            load:     server.load,
+            score:    self.score(server),
+            eligible: self.is_eligible(server),
+        }
+    }
+
+    /// Get the first selection check `server` fails: PQC enabled, not
+    /// blocklisted, not in maintenance, not unreachable, not in an excluded
+    /// country and not cooling down.
+    ///
+    /// `None` means every `find_*` method may pick the server. Capacity is
+    /// not checked here; see
+    /// [`find_optimal_server_with_capacity`](Self::find_optimal_server_with_capacity).
+    #[must_use]
+    pub fn exclusion_reason(&self, server: &VpnServer) -> Option<ExclusionReason> {
+        if !server.pqc_enabled {
+            return Some(ExclusionReason::NonPqc);
+        }
+        self.availability_exclusion(server)
+    }
+
+    /// Get the first selection check other than PQC that `server` fails.
+    fn availability_exclusion(&self, server: &VpnServer) -> Option<ExclusionReason> {
+        if self.is_blocked(&server.id) {
+            return Some(ExclusionReason::Blocklisted);
+        }
+        match self.server_health(&server.id) {
+            ServerHealth::Maintenance => return Some(ExclusionReason::Maintenance),
+            ServerHealth::Unreachable => return Some(ExclusionReason::Unreachable),
+            ServerHealth::Healthy | ServerHealth::Degraded => {},
+        }
+        if self.is_excluded(server) {
+            return Some(ExclusionReason::CountryRestricted);
+        }
+        if self.is_cooling_down(&server.id) {
+            return Some(ExclusionReason::Cooldown);
+        }
+        None
+    }
+
+    /// Check if a server may be selected; see
+    /// [`exclusion_reason`](Self::exclusion_reason).
+    fn is_eligible(&self, server: &VpnServer) -> bool {
+        self.exclusion_reason(server).is_none()
+    }
+
+    /// Check if a server is in an excluded country.
+    fn is_excluded(&self, server: &VpnServer) -> bool {
+        !self.excluded_countries.is_empty()
+            && self.excluded_countries.contains(&server.country.to_ascii_uppercase())
+    }
+
+    /// Selection score of a server (lower is better).
+    ///
+    /// Once a server has `MIN_OUTCOMES_FOR_PENALTY` recorded outcomes, its
+    /// failure rate adds up to `UNRELIABLE_PENALTY` to the score. A degraded
+    /// server adds `DEGRADED_PENALTY`. A user rating moves the score by up to
+    /// `rating_weight` either way.
+    fn score(&self, server: &VpnServer) -> f32 {
+        let mut score = server.load;
+        if self.server_health(&server.id) == ServerHealth::Degraded {
+            score += DEGRADED_PENALTY;
+        }
+        if self.prefer_recommended && server.recommended {
+            score -= RECOMMENDED_BONUS;
+        }
+        if let Some(counts) = self.outcomes.get(&server.id)
+            && counts.total() >= MIN_OUTCOMES_FOR_PENALTY
+            && let Some(rate) = counts.success_rate()
+        {
+            score += (1.0 - rate) * UNRELIABLE_PENALTY;
+        }
+        if let Some(&rating) = self.ratings.get(&server.id) {
+            score -= f32::from(rating) / f32::from(MAX_RATING) * self.rating_weight;
+        }
+        score
+    }
+
+    /// Order two servers by preference (best first).
+    fn compare(&self, a: &VpnServer, b: &VpnServer) -> Ordering {
+        let ordering = self.score(a).partial_cmp(&self.score(b)).unwrap_or(Ordering::Equal);
+        if self.deterministic {
+            ordering.then_with(|| a.id.cmp(&b.id))
+        } else {
+            ordering
+        }
+    }
+}
+
+/// Get the user slots left on a server, `u32::MAX` when uncapped.
+fn free_slots(server: &VpnServer) -> u32 {
+    if server.max_users == 0 {
+        return u32::MAX;
+    }
+    server.max_users.saturating_sub(server.current_users)
+}
+
+/// Great-circle distance in kilometres between two `(latitude, longitude)`
+/// points.
+fn haversine_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    let (lat1, lon1) = (a.0.to_radians(), a.1.to_radians());
+    let (lat2, lon2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+/// One entry of a JSON server list being read into a [`VpnServer`].
+struct JsonServer<'a> {
+    index: usize,
+    entry: &'a Value,
+}
+
+impl JsonServer<'_> {
+    fn parse(&self) -> VpnResult<VpnServer> {
+        if !matches!(self.entry, Value::Object(_)) {
+            return Err(self.error(format!(
+                "expected an object, got {}",
+                self.entry.type_name()
+            )));
+        }
+        let mut server = VpnServer {
+            id: self.required("id", self.string("id")?)?,
+            hostname: self.required("hostname", self.string("hostname")?)?,
+            ipv4_addr: self.string("ipv4_addr")?.map(|a| a.parse()).transpose()?,
+            ipv6_addr: self.string("ipv6_addr")?.map(|a| a.parse()).transpose()?,
+            port: self.required("port", self.integer("port")?)?,
+            country: self.required("country", self.string("country")?)?,
+            city: self.string("city")?.unwrap_or_default(),
+            latitude: self.number("latitude")?,
+            longitude: self.number("longitude")?,
+            load: (self.number("load")?.unwrap_or_default() as f32).clamp(0.0, 1.0),
+            current_users: self.integer("current_users")?.unwrap_or_default(),
+            max_users: self.integer("max_users")?.unwrap_or_default(),
+            pqc_enabled: self.bool("pqc_enabled")?.unwrap_or_default(),
+            tags: self.list("tags", |v| self.as_string("tags", v))?,
+            region: self.string("region")?.map(|r| r.parse()).transpose()?,
+            supported_encryption: self.parsed_list("supported_encryption")?,
+            supported_key_exchange: self.parsed_list("supported_key_exchange")?,
+            recommended: self.bool("recommended")?.unwrap_or_default(),
+            supports_obfuscation: self.bool("supports_obfuscation")?.unwrap_or_default(),
+            preferred_encryption: self
+                .string("preferred_encryption")?
+                .map(|e| e.parse())
+                .transpose()?,
+            ..VpnServer::default()
+        };
+        if let Some(Value::Object(ports)) = self.field("port_map") {
+            for (transport, port) in ports {
+                let port = self.as_integer("port_map", port)?;
+                server.port_map.insert(transport.parse::<TransportMode>()?, port);
+            }
+        } else if let Some(other) = self.field("port_map") {
+            return Err(self.type_error("port_map", "an object", other));
+        }
+        Ok(server)
+    }
+
+    /// Get a field, treating `null` as absent.
+    fn field(&self, key: &str) -> Option<&Value> {
+        self.entry.get(key).filter(|v| **v != Value::Null)
+    }
+
+    fn required<T>(&self, key: &str, value: Option<T>) -> VpnResult<T> {
+        value.ok_or_else(|| self.error(format!("missing `{key}`")))
+    }
+
+    fn string(&self, key: &str) -> VpnResult<Option<String>> {
+        self.field(key).map(|v| self.as_string(key, v)).transpose()
+    }
+
+    fn number(&self, key: &str) -> VpnResult<Option<f64>> {
+        self.field(key)
+            .map(|v| match v {
+                Value::Number(n) => Ok(*n),
+                _ => Err(self.type_error(key, "a number", v)),
+            })
+            .transpose()
+    }
+
+    fn integer<T: TryFrom<i64>>(&self, key: &str) -> VpnResult<Option<T>> {
+        self.field(key).map(|v| self.as_integer(key, v)).transpose()
+    }
+
+    fn bool(&self, key: &str) -> VpnResult<Option<bool>> {
+        self.field(key)
+            .map(|v| match v {
+                Value::Bool(b) => Ok(*b),
+                _ => Err(self.type_error(key, "a boolean", v)),
+            })
+            .transpose()
+    }
+
+    fn list<T>(&self, key: &str, item: impl Fn(&Value) -> VpnResult<T>) -> VpnResult<Vec<T>> {
+        match self.field(key) {
+            None => Ok(Vec::new()),
+            Some(Value::Array(items)) => items.iter().map(item).collect(),
+            Some(other) => Err(self.type_error(key, "an array", other)),
+        }
+    }
+
+    fn parsed_list<T: FromStr<Err = VpnError>>(&self, key: &str) -> VpnResult<Vec<T>> {
+        self.list(key, |v| self.as_string(key, v)?.parse())
+    }
+
+    fn as_string(&self, key: &str, value: &Value) -> VpnResult<String> {
+        match value {
+            Value::String(s) => Ok(s.clone()),
+            _ => Err(self.type_error(key, "a string", value)),
+        }
+    }
+
+    fn as_integer<T: TryFrom<i64>>(&self, key: &str, value: &Value) -> VpnResult<T> {
+        match value {
+            Value::Number(n) if n.fract() == 0.0 => T::try_from(*n as i64)
+                .map_err(|_| self.type_error(key, "an integer in range", value)),
+            _ => Err(self.type_error(key, "an integer", value)),
+        }
+    }
+
+    fn type_error(&self, key: &str, expected: &str, value: &Value) -> VpnError {
+        self.error(format!(
+            "`{key}` expects {expected}, got {}",
+            value.type_name()
+        ))
+    }
+
+    fn error(&self, message: String) -> VpnError {
+        VpnError::Configuration(format!("Server {}: {message}", self.index))
+    }
 }
 
 impl Default for NeuralRouter {
@@ -65,3 +932,732 @@ impl Default for NeuralRouter {
         Self::new()
     }
 }
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::implementation::MockClock;
+
+    fn server(id: &str, load: f32) -> Rc<RefCell<VpnServer>> {
+        Rc::new(RefCell::new(VpnServer {
+            id: id.to_string(),
+            country: String::from("DE"),
+            load,
+            pqc_enabled: true,
+            ..Default::default()
+        }))
+    }
+
+    fn id_of(server: Option<&Rc<RefCell<VpnServer>>>) -> Option<String> {
+        server.map(|s| s.borrow().id.clone())
+    }
+
+    #[test]
+    fn test_deterministic_tie_break() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("srv-b", 0.2));
+        router.add_server(server("srv-a", 0.2));
+        router.add_server(server("srv-c", 0.5));
+
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-b")
+        );
+
+        router.set_deterministic(true);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-a")
+        );
+        assert_eq!(
+            id_of(router.find_best_server("DE")).as_deref(),
+            Some("srv-a")
+        );
+    }
+
+    #[test]
+    fn test_region_selection() {
+        let mut router = NeuralRouter::new();
+        let dublin = server("srv-dub", 0.1);
+        dublin.borrow_mut().region = Some(ServerRegion::EuWest);
+        let frankfurt = server("srv-fra", 0.6);
+        frankfurt.borrow_mut().region = Some(ServerRegion::EuCentral);
+        router.add_server(dublin);
+        router.add_server(frankfurt);
+
+        // Auto near Munich resolves to the closest region, not the lowest load
+        let (chosen, region) = router
+            .connect_region_selection(ServerRegion::Auto, Some((48.1, 11.6)))
+            .expect("Should resolve");
+        assert_eq!(chosen.borrow().id, "srv-fra");
+        assert_eq!(region, ServerRegion::EuCentral);
+
+        // Auto without location falls back to load
+        let (chosen, region) = router
+            .connect_region_selection(ServerRegion::Auto, None)
+            .expect("Should resolve");
+        assert_eq!(chosen.borrow().id, "srv-dub");
+        assert_eq!(region, ServerRegion::EuWest);
+
+        assert!(router.connect_region_selection(ServerRegion::UsEast, None).is_none());
+    }
+
+    #[test]
+    fn test_find_most_secure() {
+        let mut router = NeuralRouter::new();
+        let classical = server("srv-classic", 0.1);
+        classical.borrow_mut().supported_key_exchange = vec![KeyExchangeProtocol::X25519];
+        let hybrid_busy = server("srv-hybrid-busy", 0.9);
+        let hybrid_idle = server("srv-hybrid-idle", 0.3);
+        for s in [&hybrid_busy, &hybrid_idle] {
+            let mut s = s.borrow_mut();
+            s.supported_encryption = vec![EncryptionAlgorithm::Aes256GcmPqc];
+            s.supported_key_exchange = vec![
+                KeyExchangeProtocol::X25519,
+                KeyExchangeProtocol::HybridMlKem,
+            ];
+        }
+        router.add_server(classical);
+        router.add_server(hybrid_busy);
+        router.add_server(hybrid_idle);
+
+        assert_eq!(
+            id_of(router.find_most_secure()).as_deref(),
+            Some("srv-hybrid-idle")
+        );
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-classic")
+        );
+    }
+
+    #[test]
+    fn test_prefer_recommended() {
+        let mut router = NeuralRouter::new();
+        let recommended = server("srv-new", 0.4);
+        recommended.borrow_mut().recommended = true;
+        router.add_server(server("srv-old", 0.3));
+        router.add_server(recommended);
+
+        assert_eq!(router.recommended_servers().len(), 1);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-old")
+        );
+
+        router.set_prefer_recommended(true);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-new")
+        );
+
+        router.update_server_load("srv-new", 0.9);
+        assert_eq!(router.recommended_servers().len(), 1);
+    }
+
+    #[test]
+    fn test_success_rate() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("srv-a", 0.2));
+        router.add_server(server("srv-b", 0.4));
+        assert_eq!(router.success_rate("srv-a"), None);
+
+        // A few failures do not affect selection yet
+        router.record_outcome("srv-a", false);
+        assert_eq!(router.success_rate("srv-a"), Some(0.0));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-a")
+        );
+
+        for _ in 0..4 {
+            router.record_outcome("srv-a", false);
+            router.record_outcome("srv-b", true);
+        }
+        assert_eq!(router.success_rate("srv-b"), Some(1.0));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-b")
+        );
+
+        let reliable = router.most_reliable_servers(1);
+        assert_eq!(reliable.len(), 1);
+        assert_eq!(reliable[0].borrow().id, "srv-b");
+    }
+
+    #[test]
+    fn test_add_server_checked() {
+        let mut router = NeuralRouter::new();
+        let config = VpnConfig::default();
+
+        router.add_server_checked(server("srv-a", 0.2), &config).expect("Should accept");
+        let legacy = Rc::new(RefCell::new(VpnServer {
+            pqc_enabled: false,
+            ..server("srv-b", 0.1).borrow().clone()
+        }));
+        assert!(router.add_server_checked(legacy, &config).is_err());
+
+        router.set_server_health("srv-c", ServerHealth::Unreachable);
+        assert_eq!(
+            router.add_server_checked(server("srv-c", 0.1), &config),
+            Err(VpnError::Configuration(String::from(
+                "Server srv-c rejected: server is unreachable"
+            )))
+        );
+        assert_eq!(router.servers().len(), 1);
+    }
+
+    #[test]
+    fn test_by_country_groups_and_sorts() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("de-2", 0.6));
+        router.add_server(server("de-1", 0.2));
+        let fr = server("fr-1", 0.5);
+        fr.borrow_mut().country = String::from("FR");
+        router.add_server(fr);
+        let classic = server("de-0", 0.1);
+        classic.borrow_mut().pqc_enabled = false;
+        router.add_server(classic);
+
+        let groups = router.by_country();
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["DE", "FR"]);
+        let ids: Vec<_> = groups["DE"].iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["de-1", "de-2"]);
+
+        let all = router.by_country_with_ineligible();
+        let de = &all["DE"];
+        assert_eq!(de.len(), 3);
+        assert_eq!(de[2].id, "de-0");
+        assert!(!de[2].eligible);
+    }
+
+    #[test]
+    fn test_scored_table() {
+        let mut router = NeuralRouter::new();
+        router.set_deterministic(true);
+        router.add_server(server("b", 0.4));
+        router.add_server(server("a", 0.4));
+        router.add_server(server("c", 0.1));
+        let classic = server("x", 0.0);
+        classic.borrow_mut().pqc_enabled = false;
+        router.add_server(classic);
+
+        let table = router.scored_table();
+        let rows: Vec<_> =
+            table.iter().map(|(s, score, ok)| (s.id.as_str(), *score, *ok)).collect();
+        assert_eq!(rows, [
+            ("c", 0.1, true),
+            ("a", 0.4, true),
+            ("b", 0.4, true),
+            ("x", 0.0, false)
+        ]);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some(table[0].0.id.as_str())
+        );
+    }
+
+    #[test]
+    fn test_user_ratings_adjust_score() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("liked", 0.5));
+        router.add_server(server("plain", 0.4));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("plain")
+        );
+
+        router.rate_server("liked", Some(100));
+        assert_eq!(router.server_rating("liked"), Some(5));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("liked")
+        );
+
+        router.set_rating_weight(0.0);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("plain")
+        );
+
+        router.set_rating_weight(0.2);
+        router.rate_server("liked", None);
+        router.rate_server("plain", Some(-5));
+        router.servers.clear();
+        router.add_server(server("liked", 0.5));
+        router.add_server(server("plain", 0.4));
+        assert_eq!(router.server_rating("plain"), Some(-5));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("liked")
+        );
+    }
+
+    #[test]
+    fn test_blocklist_maintenance_and_cooldown_exclude() {
+        let clock = Arc::new(MockClock::new(0));
+        let mut router = NeuralRouter::new();
+        router.set_clock(clock.clone());
+        router.add_server(server("srv-a", 0.1));
+        router.add_server(server("srv-b", 0.2));
+        router.add_server(server("srv-c", 0.3));
+        router.add_server(server("srv-d", 0.4));
+
+        router.block_server("srv-a");
+        router.set_server_health("srv-b", ServerHealth::Maintenance);
+        router.cool_down_server("srv-c", Duration::from_secs(30));
+        let reasons: Vec<_> =
+            router.servers().iter().map(|s| router.exclusion_reason(&s.borrow())).collect();
+        assert_eq!(reasons, [
+            Some(ExclusionReason::Blocklisted),
+            Some(ExclusionReason::Maintenance),
+            Some(ExclusionReason::Cooldown),
+            None
+        ]);
+        assert_eq!(id_of(router.find_most_secure()).as_deref(), Some("srv-d"));
+
+        clock.advance(Duration::from_secs(30));
+        router.unblock_server("srv-a");
+        assert!(!router.is_cooling_down("srv-c"));
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-a")
+        );
+    }
+
+    #[test]
+    fn test_health_skips_unreachable_and_penalizes_degraded() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("srv-a", 0.1));
+        router.add_server(server("srv-b", 0.3));
+        router.add_server(server("srv-c", 0.5));
+
+        router.set_server_health("srv-a", ServerHealth::Unreachable);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-b")
+        );
+
+        router.set_server_health("srv-b", ServerHealth::Degraded);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-c")
+        );
+
+        let healthy: Vec<_> =
+            router.healthy_servers().iter().map(|s| s.borrow().id.clone()).collect();
+        assert_eq!(healthy, ["srv-c"]);
+        assert!(
+            !router
+                .scored_table()
+                .iter()
+                .find(|(s, ..)| s.id == "srv-a")
+                .expect("Should find server")
+                .2
+        );
+
+        router.set_server_health("srv-a", ServerHealth::Healthy);
+        assert_eq!(
+            id_of(router.find_optimal_server()).as_deref(),
+            Some("srv-a")
+        );
+    }
+
+    #[test]
+    fn test_find_nearest_server() {
+        let located = |id: &str, load: f32, lat: f64, lon: f64| {
+            let server = server(id, load);
+            server.borrow_mut().latitude = Some(lat);
+            server.borrow_mut().longitude = Some(lon);
+            server
+        };
+        let mut router = NeuralRouter::new();
+        router.add_server(located("frankfurt", 0.6, 50.11, 8.68));
+        let london = located("london", 0.2, 51.51, -0.13);
+        router.add_server(Rc::clone(&london));
+        router.add_server(located("new-york", 0.1, 40.71, -74.01));
+        router.add_server(server("unlocated", 0.0));
+
+        // Paris is closer to London (~340 km) than to Frankfurt (~480 km)
+        assert_eq!(
+            id_of(router.find_nearest_server(48.86, 2.35)),
+            Some("london".into())
+        );
+        assert_eq!(
+            id_of(router.find_nearest_server(52.52, 13.40)),
+            Some("frankfurt".into())
+        );
+        assert_eq!(
+            id_of(router.find_nearest_server(42.36, -71.06)),
+            Some("new-york".into())
+        );
+
+        router.add_server(located("frankfurt-2", 0.3, 50.11, 8.68));
+        assert_eq!(
+            id_of(router.find_nearest_server(52.52, 13.40)),
+            Some("frankfurt-2".into())
+        );
+
+        london.borrow_mut().pqc_enabled = false;
+        assert_eq!(
+            id_of(router.find_nearest_server(51.51, -0.13)),
+            Some("frankfurt-2".into())
+        );
+    }
+
+    #[test]
+    fn test_load_servers_from_json() {
+        let mut router = NeuralRouter::new();
+        let loaded = router
+            .load_servers_from_json(
+                r#"[
+                    {"id": "fra-1", "hostname": "fra-1.example.net", "port": 443,
+                     "country": "DE", "load": 0.4, "pqc_enabled": true,
+                     "region": "eu-central", "tags": ["streaming"],
+                     "supported_key_exchange": ["ml_kem", "x25519"],
+                     "port_map": {"websocket": 8443}, "extra": {"ignored": [1]},
+                     "ipv6_addr": "2001:db8::1"},
+                    {"id": "nyc-1", "hostname": "198.51.100.4", "port": 51820,
+                     "country": "US", "city": null}
+                ]"#,
+            )
+            .expect("Should load");
+        assert_eq!(loaded, 2);
+
+        let fra = router.servers()[0].borrow();
+        assert_eq!(fra.region, Some(ServerRegion::EuCentral));
+        assert_eq!(fra.load, 0.4);
+        assert!(fra.has_tag("streaming"));
+        assert_eq!(fra.supported_key_exchange.len(), 2);
+        assert_eq!(fra.port_for(&TransportMode::Websocket), Some(8443));
+        assert_eq!(
+            fra.ipv6_addr,
+            Some("2001:db8::1".parse().expect("Should parse"))
+        );
+        drop(fra);
+        assert!(!router.servers()[1].borrow().pqc_enabled);
+        assert_eq!(id_of(router.find_optimal_server()), Some("fra-1".into()));
+
+        for payload in [
+            "[{\"id\": \"a\"",
+            "{\"id\": \"a\"}",
+            "[{\"id\": \"a\", \"hostname\": \"h\", \"country\": \"DE\"}]",
+            "[{\"id\": \"a\", \"hostname\": \"h\", \"port\": 70000, \"country\": \"DE\"}]",
+            "[{\"id\": 1, \"hostname\": \"h\", \"port\": 1, \"country\": \"DE\"}]",
+            "[{\"id\": \"nyc-1\", \"hostname\": \"h\", \"port\": 1, \"country\": \"US\"}]",
+        ] {
+            assert!(
+                matches!(
+                    router.load_servers_from_json(payload),
+                    Err(VpnError::Configuration(_))
+                ),
+                "{payload}"
+            );
+        }
+        assert_eq!(router.servers().len(), 2);
+    }
+
+    #[test]
+    fn test_load_servers_clamps_load_and_rejects_duplicates() {
+        let mut router = NeuralRouter::new();
+        router
+            .load_servers_from_json(
+                r#"[
+                    {"id": "hot", "hostname": "h", "port": 1, "country": "DE", "load": 1.7},
+                    {"id": "odd", "hostname": "h", "port": 1, "country": "DE", "load": -0.2}
+                ]"#,
+            )
+            .expect("Should load");
+        let loads: Vec<f32> = router.servers().iter().map(|s| s.borrow().load).collect();
+        assert_eq!(loads, [1.0, 0.0]);
+
+        assert_eq!(
+            router.load_servers_from_json(
+                r#"[
+                    {"id": "new", "hostname": "h", "port": 1, "country": "DE"},
+                    {"id": "new", "hostname": "h", "port": 2, "country": "DE"}
+                ]"#,
+            ),
+            Err(VpnError::Configuration(String::from(
+                "Server 1: duplicate id `new`"
+            )))
+        );
+        assert_eq!(router.servers().len(), 2);
+    }
+
+    #[test]
+    fn test_next_balanced_server_rotates() {
+        let mut router = NeuralRouter::new();
+        assert!(router.next_balanced_server().is_none());
+        for (id, load) in [
+            ("srv-d", 0.4),
+            ("srv-a", 0.1),
+            ("srv-c", 0.3),
+            ("srv-b", 0.2),
+        ] {
+            router.add_server(server(id, load));
+        }
+        router.set_balance_pool_size(3);
+
+        let mut next = || router.next_balanced_server().map(|s| s.borrow().id.clone());
+        let picks: Vec<_> = (0..4).map(|_| next()).collect();
+        assert_eq!(picks, [
+            Some("srv-a".into()),
+            Some("srv-b".into()),
+            Some("srv-c".into()),
+            Some("srv-a".into()),
+        ]);
+
+        // Cursor now points past srv-a; shrinking the pool wraps it around
+        router.set_balance_pool_size(1);
+        assert_eq!(
+            id_of(router.next_balanced_server().as_ref()),
+            Some("srv-a".into())
+        );
+        router.set_balance_pool_size(2);
+        assert_eq!(
+            id_of(router.next_balanced_server().as_ref()),
+            Some("srv-b".into())
+        );
+        assert_eq!(
+            id_of(router.next_balanced_server().as_ref()),
+            Some("srv-a".into())
+        );
+    }
+
+    #[test]
+    fn test_find_optimal_server_with_capacity_skips_full() {
+        let mut router = NeuralRouter::new();
+        let quiet = server("srv-quiet", 0.1);
+        {
+            let mut quiet = quiet.borrow_mut();
+            quiet.current_users = 500;
+            quiet.max_users = 500;
+            assert!(quiet.is_full());
+        }
+        router.add_server(quiet);
+        router.add_server(server("srv-busy", 0.7));
+        let uncapped = server("srv-open", 0.9);
+        uncapped.borrow_mut().current_users = 10_000;
+        assert!(!uncapped.borrow().is_full());
+        router.add_server(uncapped);
+
+        assert_eq!(
+            id_of(router.find_optimal_server()),
+            Some("srv-quiet".into())
+        );
+        assert_eq!(
+            id_of(router.find_optimal_server_with_capacity()),
+            Some("srv-busy".into())
+        );
+
+        router.servers()[1].borrow_mut().max_users = 1;
+        router.servers()[1].borrow_mut().current_users = 1;
+        assert_eq!(
+            id_of(router.find_optimal_server_with_capacity()),
+            Some("srv-open".into())
+        );
+    }
+
+    #[test]
+    fn test_find_server_supporting() {
+        let mut router = NeuralRouter::new();
+        let chacha = server("srv-chacha", 0.1);
+        chacha.borrow_mut().supported_encryption = vec![EncryptionAlgorithm::ChaCha20Poly1305];
+        router.add_server(chacha);
+        let pqc = server("srv-pqc", 0.5);
+        {
+            let mut pqc = pqc.borrow_mut();
+            pqc.supported_encryption = vec![EncryptionAlgorithm::Aes256GcmPqc];
+            pqc.supported_key_exchange = vec![KeyExchangeProtocol::HybridMlKem];
+        }
+        router.add_server(pqc);
+
+        let find = |e, k| id_of(router.find_server_supporting(e, k));
+        assert_eq!(
+            find(
+                EncryptionAlgorithm::Aes256GcmPqc,
+                KeyExchangeProtocol::HybridMlKem
+            ),
+            Some("srv-pqc".into())
+        );
+        assert_eq!(
+            find(
+                EncryptionAlgorithm::ChaCha20Poly1305,
+                KeyExchangeProtocol::X25519
+            ),
+            Some("srv-chacha".into())
+        );
+        assert_eq!(
+            find(
+                EncryptionAlgorithm::Aes256GcmPqc,
+                KeyExchangeProtocol::MlKem
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_excluded_countries_and_favorites() {
+        let mut router = NeuralRouter::new();
+        router.add_server(server("de-1", 0.1));
+        let us = server("us-1", 0.2);
+        us.borrow_mut().country = String::from("US");
+        router.add_server(us);
+        router.add_server(server("de-2", 0.6));
+        router.add_server(server("de-3", 0.9));
+
+        router.add_favorite("de-3");
+        assert_eq!(id_of(router.find_optimal_server()), Some("de-3".into()));
+        router.add_favorite("de-2");
+        assert_eq!(id_of(router.find_optimal_server()), Some("de-2".into()));
+
+        // Exclusions win over favorites and reach every selector
+        router.exclude_country("de");
+        assert_eq!(id_of(router.find_optimal_server()), Some("us-1".into()));
+        assert_eq!(id_of(router.find_best_server("DE")), None);
+        assert_eq!(id_of(router.find_most_secure()), Some("us-1".into()));
+
+        router.exclude_country("US");
+        assert!(router.find_optimal_server().is_none());
+    }
+
+    /// Pinger returning a fixed latency per server id.
+    struct FixedPinger(HashMap<&'static str, u32>);
+
+    impl Pinger for FixedPinger {
+        fn measure(&self, server: &VpnServer) -> VpnResult<u32> {
+            self.0
+                .get(server.id.as_str())
+                .copied()
+                .ok_or_else(|| VpnError::Network(format!("{} timed out", server.id)))
+        }
+    }
+
+    #[test]
+    fn test_refresh_latencies() {
+        let mut router = NeuralRouter::new();
+        for id in ["srv-a", "srv-b", "srv-c"] {
+            router.add_server(server(id, 0.5));
+        }
+        router.servers()[2].borrow_mut().latency_ms = Some(5);
+
+        router.refresh_latencies(&FixedPinger(HashMap::from([("srv-a", 42), ("srv-b", 7)])));
+        let latencies: Vec<_> = router.servers().iter().map(|s| s.borrow().latency_ms).collect();
+        assert_eq!(latencies, [Some(42), Some(7), None]);
+    }
+
+    #[test]
+    fn test_update_loads() {
+        let mut router = NeuralRouter::new();
+        for id in ["srv-a", "srv-b", "srv-c"] {
+            router.add_server(Rc::new(RefCell::new(VpnServer {
+                id: id.to_string(),
+                load: 0.5,
+                ..VpnServer::default()
+            })));
+        }
+
+        let loads = [
+            (String::from("srv-a"), 0.2),
+            (String::from("srv-gone"), 0.4),
+            (String::from("srv-c"), 1.7),
+            (String::from("srv-c"), -0.3),
+            (String::from("srv-new"), 0.1),
+        ];
+        assert_eq!(router.update_loads(&loads), (2, 2));
+
+        let load_of = |id: &str| {
+            router
+                .servers()
+                .iter()
+                .find(|s| s.borrow().id == id)
+                .expect("Should find server")
+                .borrow()
+                .load
+        };
+        assert!((load_of("srv-a") - 0.2).abs() < f32::EPSILON);
+        assert!((load_of("srv-b") - 0.5).abs() < f32::EPSILON);
+        assert!(
+            load_of("srv-c").abs() < f32::EPSILON,
+            "Last entry wins, clamped"
+        );
+
+        assert_eq!(router.update_loads(&[]), (0, 0));
+    }
+
+    #[test]
+    fn test_remove_replace_and_clear() {
+        let mut router = NeuralRouter::new();
+        let server = |id: &str| {
+            Rc::new(RefCell::new(VpnServer {
+                id: id.to_string(),
+                ..VpnServer::default()
+            }))
+        };
+        let held = server("srv-a");
+        router.add_server(Rc::clone(&held));
+        router.add_server(server("srv-b"));
+        router.add_server(server("srv-c"));
+
+        assert!(router.remove_server("srv-b"));
+        assert!(!router.remove_server("srv-b"));
+        let ids: Vec<String> = router.servers().iter().map(|s| s.borrow().id.clone()).collect();
+        assert_eq!(ids, ["srv-a", "srv-c"]);
+
+        let replacement = VpnServer {
+            id: "srv-a".to_string(),
+            hostname: "a2.example.com".to_string(),
+            ..VpnServer::default()
+        };
+        assert!(router.replace_server("srv-a", replacement.clone()));
+        assert!(!router.replace_server("srv-missing", replacement));
+        assert!(Rc::ptr_eq(&router.servers()[0], &held));
+        assert_eq!(held.borrow().hostname, "a2.example.com");
+
+        router.clear();
+        assert!(router.servers().is_empty());
+        assert!(router.find_optimal_server().is_none());
+        assert_eq!(
+            held.borrow().id,
+            "srv-a",
+            "Outside holders keep their server"
+        );
+    }
+
+    #[test]
+    fn test_servers_sorted() {
+        let mut router = NeuralRouter::new();
+        for (id, country, load, latency_ms, max_users) in [
+            ("srv-a", "us", 0.6, Some(40), 100),
+            ("srv-b", "DE", 0.2, None, 0),
+            ("srv-c", "CH", 0.6, Some(15), 10),
+            ("srv-d", "de", 0.1, Some(40), 100),
+        ] {
+            router.add_server(Rc::new(RefCell::new(VpnServer {
+                id: id.to_string(),
+                country: country.to_string(),
+                load,
+                latency_ms,
+                current_users: 5,
+                max_users,
+                ..VpnServer::default()
+            })));
+        }
+        let ids = |by| -> Vec<String> {
+            router.servers_sorted(by).iter().map(|s| s.borrow().id.clone()).collect()
+        };
+
+        assert_eq!(ids(SortKey::Load), ["srv-d", "srv-b", "srv-a", "srv-c"]);
+        assert_eq!(ids(SortKey::Country), ["srv-c", "srv-b", "srv-d", "srv-a"]);
+        assert_eq!(ids(SortKey::Latency), ["srv-c", "srv-a", "srv-d", "srv-b"]);
+        assert_eq!(ids(SortKey::Capacity), ["srv-b", "srv-a", "srv-d", "srv-c"]);
+
+        let pool: Vec<String> = router.servers().iter().map(|s| s.borrow().id.clone()).collect();
+        assert_eq!(
+            pool,
+            ["srv-a", "srv-b", "srv-c", "srv-d"],
+            "Pool order untouched"
+        );
+    }
+}
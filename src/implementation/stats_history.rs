@@ -0,0 +1,181 @@
+//! Bounded traffic history for throughput graphs.
+
+use std::{collections::VecDeque, time::Duration};
+
+use crate::types::TrafficSample;
+
+/// Ring buffer of traffic samples taken at a fixed interval.
+///
+/// Traffic reported between samples is accumulated, so the graph resolution
+/// is set by `sample_interval` rather than by how often traffic is recorded.
+#[derive(Debug, Clone)]
+pub struct StatsHistory {
+    samples:          VecDeque<TrafficSample>,
+    capacity:         usize,
+    sample_interval:  Duration,
+    interval_start:   Option<u64>,
+    pending_sent:     u64,
+    pending_received: u64,
+}
+
+impl StatsHistory {
+    /// Create an empty history holding at most `capacity` samples.
+    #[must_use]
+    pub fn new(capacity: usize, sample_interval: Duration) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            sample_interval,
+            interval_start: None,
+            pending_sent: 0,
+            pending_received: 0,
+        }
+    }
+
+    /// Get the sampling interval.
+    #[must_use]
+    pub fn sample_interval(&self) -> Duration {
+        self.sample_interval
+    }
+
+    /// Set the sampling interval. Traffic already accumulated is kept.
+    pub fn set_sample_interval(&mut self, interval: Duration) {
+        self.sample_interval = interval;
+    }
+
+    /// Accumulate traffic, taking a sample once the interval has elapsed.
+    ///
+    /// Returns `true` if a sample was taken.
+    pub fn record(&mut self, bytes_sent: u64, bytes_received: u64, now_millis: u64) -> bool {
+        self.pending_sent = self.pending_sent.saturating_add(bytes_sent);
+        self.pending_received = self.pending_received.saturating_add(bytes_received);
+
+        let start = *self.interval_start.get_or_insert(now_millis);
+        if u128::from(now_millis.saturating_sub(start)) < self.sample_interval.as_millis() {
+            return false;
+        }
+        self.flush(now_millis);
+        true
+    }
+
+    /// Take a sample of any accumulated traffic immediately.
+    pub fn flush(&mut self, now_millis: u64) {
+        let Some(start) = self.interval_start else {
+            return;
+        };
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(TrafficSample {
+            at_millis:       now_millis,
+            duration_millis: now_millis.saturating_sub(start),
+            bytes_sent:      self.pending_sent,
+            bytes_received:  self.pending_received,
+        });
+        self.interval_start = Some(now_millis);
+        self.pending_sent = 0;
+        self.pending_received = 0;
+    }
+
+    /// Take a final sample of pending traffic and end the current interval.
+    ///
+    /// Called when a tunnel closes so traffic recorded since the last
+    /// sample is not lost; the next `record` starts a fresh interval.
+    pub fn finish(&mut self, now_millis: u64) {
+        if self.pending_sent > 0 || self.pending_received > 0 {
+            self.flush(now_millis);
+        }
+        self.interval_start = None;
+    }
+
+    /// Get samples (oldest first).
+    pub fn samples(&self) -> impl Iterator<Item = &TrafficSample> {
+        self.samples.iter()
+    }
+
+    /// Get the most recent sample.
+    #[must_use]
+    pub fn latest(&self) -> Option<&TrafficSample> {
+        self.samples.back()
+    }
+
+    /// Get recent combined throughput scaled to `0..=255` for a sparkline.
+    ///
+    /// Returns exactly `buckets` values (oldest first), one per sample,
+    /// normalized against the busiest sample in the window. Buckets before
+    /// the first sample are zero, as is every bucket of an idle window.
+    #[must_use]
+    pub fn sparkline(&self, buckets: usize) -> Vec<u8> {
+        let rates: Vec<f64> = self
+            .samples
+            .iter()
+            .skip(self.samples.len().saturating_sub(buckets))
+            .map(|s| {
+                let bytes = s.bytes_sent.saturating_add(s.bytes_received);
+                bytes as f64 / s.duration_millis.max(1) as f64
+            })
+            .collect();
+        let max = rates.iter().copied().fold(0.0, f64::max);
+
+        let mut line = vec![0; buckets - rates.len()];
+        line.extend(rates.iter().map(|&rate| {
+            if max > 0.0 {
+                (rate / max * 255.0).round() as u8
+            } else {
+                0
+            }
+        }));
+        line
+    }
+
+    /// Get the number of stored samples.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Check if no samples have been taken.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_interval_accumulates() {
+        let mut history = StatsHistory::new(4, Duration::from_secs(1));
+
+        for at in (0..1000).step_by(100) {
+            assert!(!history.record(10, 20, at));
+        }
+        assert!(history.is_empty());
+
+        assert!(history.record(10, 20, 1000));
+        let sample = history.latest().expect("Should have sample");
+        assert_eq!((sample.bytes_sent, sample.bytes_received), (110, 220));
+
+        history.set_sample_interval(Duration::ZERO);
+        for at in 1001..1010 {
+            assert!(history.record(1, 1, at));
+        }
+        assert_eq!(history.len(), 4);
+    }
+
+    #[test]
+    fn test_sparkline_normalizes_to_window_max() {
+        let mut history = StatsHistory::new(8, Duration::ZERO);
+        assert_eq!(history.sparkline(3), vec![0, 0, 0]);
+
+        for (at, bytes) in [(0, 1000), (1000, 0), (2000, 400), (3000, 200), (4000, 100)] {
+            history.record(bytes, 0, at);
+        }
+        assert_eq!(history.sparkline(3), vec![255, 128, 64]);
+        assert_eq!(history.sparkline(8)[..3], [0, 0, 0]);
+        assert_eq!(history.sparkline(8)[3], 255);
+        assert!(history.sparkline(0).is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+//! Minimal TOML reader (std-only).
+//!
+//! Supports the subset used for configuration files: `key = value` pairs,
+//! `[table]` headers and dotted keys (both flattened to `table.key`),
+//! booleans, integers, floats, basic strings and arrays of those values.
+//! Comments and blank lines are ignored. Inline tables, literal strings,
+//! multi-line strings and dates are not supported.
+
+use crate::errors::{VpnError, VpnResult};
+
+/// Parsed TOML value.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Describe the value's type for error messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "boolean",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "float",
+            Self::String(_) => "string",
+            Self::Array(_) => "array",
+        }
+    }
+}
+
+/// Parse a document into `(key, value)` pairs in file order.
+///
+/// Keys under a `[table]` header are prefixed with `table.`.
+///
+/// # Errors
+///
+/// Returns `VpnError::Configuration` naming the line of the first syntax
+/// error or duplicate key.
+pub(crate) fn parse(input: &str) -> VpnResult<Vec<(String, Value)>> {
+    let mut entries: Vec<(String, Value)> = Vec::new();
+    let mut table = String::new();
+    let mut lines = input.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_no = index + 1;
+        let error =
+            |message: &str| VpnError::Configuration(format!("TOML line {line_no}: {message}"));
+
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[') {
+            let name = name.strip_suffix(']').ok_or_else(|| error("unclosed table header"))?;
+            table = parse_key(name).ok_or_else(|| error("invalid table name"))?;
+            continue;
+        }
+
+        let (key, raw) = line.split_once('=').ok_or_else(|| error("expected `key = value`"))?;
+        let key = parse_key(key).ok_or_else(|| error("invalid key"))?;
+        let key = if table.is_empty() {
+            key
+        } else {
+            format!("{table}.{key}")
+        };
+
+        // Arrays may span lines until their brackets balance
+        let mut raw = raw.trim().to_string();
+        while raw.starts_with('[') && !brackets_balanced(&raw) {
+            let (_, next) = lines.next().ok_or_else(|| error("unclosed array"))?;
+            raw.push(' ');
+            raw.push_str(strip_comment(next).trim());
+        }
+
+        let mut parser = Parser { input: &raw, pos: 0 };
+        let value = parser.value().ok_or_else(|| error("invalid value"))?;
+        parser.skip_whitespace();
+        if parser.pos != raw.len() {
+            return Err(error("unexpected text after value"));
+        }
+        if entries.iter().any(|(k, _)| *k == key) {
+            return Err(error(&format!("duplicate key `{key}`")));
+        }
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// Normalize a bare or dotted key, or `None` if it is not a valid bare key.
+fn parse_key(key: &str) -> Option<String> {
+    let parts: Vec<&str> = key.split('.').map(str::trim).collect();
+    let valid = |part: &&str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    };
+    parts.iter().all(valid).then(|| parts.join("."))
+}
+
+/// Drop a trailing `#` comment, ignoring `#` inside strings.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => {},
+        }
+    }
+    line
+}
+
+/// Check if every `[` outside strings has been closed.
+fn brackets_balanced(raw: &str) -> bool {
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in raw.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth -= 1,
+            _ => {},
+        }
+    }
+    depth <= 0
+}
+
+/// Recursive-descent parser over a single value.
+struct Parser<'a> {
+    input: &'a str,
+    pos:   usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start();
+        self.pos = self.input.len() - trimmed.len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        if rest.starts_with('"') {
+            return self.string().map(Value::String);
+        }
+        if rest.starts_with('[') {
+            return self.array();
+        }
+        for (word, value) in [("true", true), ("false", false)] {
+            if rest.starts_with(word) {
+                self.pos += word.len();
+                return Some(Value::Bool(value));
+            }
+        }
+
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.' | '_')))
+            .unwrap_or(rest.len());
+        let token = rest[..len].replace('_', "");
+        self.pos += len;
+        if let Ok(integer) = token.parse() {
+            return Some(Value::Integer(integer));
+        }
+        token
+            .parse()
+            .ok()
+            .filter(|f: &f64| f.is_finite() && token.contains(['.', 'e', 'E']))
+            .map(Value::Float)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Some(out);
+                },
+                '\\' => {
+                    let (_, escape) = chars.next()?;
+                    out.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let hex: String =
+                                (0..4).filter_map(|_| chars.next()).map(|(_, c)| c).collect();
+                            char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                        },
+                        _ => return None,
+                    });
+                },
+                c => out.push(c),
+            }
+        }
+        None
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            if self.eat(']') {
+                return Some(Value::Array(items));
+            }
+            items.push(self.value()?);
+            if !self.eat(',') {
+                return self.eat(']').then_some(Value::Array(items));
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subset() {
+        let entries = parse(
+            "# profile\n\
+             label = \"Work # VPN\"  # trailing\n\
+             attempts = 5\n\
+             ratio = 0.5\n\
+             [rules]\n\
+             include = [\n  \"a\",\n  \"b\",\n]\n",
+        )
+        .expect("Should parse");
+        assert_eq!(entries, [
+            ("label".to_string(), Value::String("Work # VPN".to_string())),
+            ("attempts".to_string(), Value::Integer(5)),
+            ("ratio".to_string(), Value::Float(0.5)),
+            (
+                "rules.include".to_string(),
+                Value::Array(vec![
+                    Value::String("a".to_string()),
+                    Value::String("b".to_string()),
+                ])
+            ),
+        ]);
+
+        assert!(parse("a = 1\na = 2").is_err());
+        assert!(parse("a = \"open").is_err());
+        assert!(parse("a = 1 2").is_err());
+    }
+}
@@ -1,18 +1,30 @@
 //! Tunnel management implementation.
 
-use std::rc::Rc;
+use std::{
+    collections::HashMap,
+    rc::Rc,
+    sync::{Mutex, PoisonError},
+};
 
 use crate::{
     errors::{VpnError, VpnResult},
+    traits::TunnelProvider,
     types::{
-        ConnectionStats, EncryptionAlgorithm, KeyExchangeProtocol, TunnelState, VpnServer,
-        VpnTunnel,
+        ConnectionStats, EncryptionAlgorithm, KeyExchangeProtocol, TunnelRole, TunnelState,
+        VpnServer, VpnTunnel,
     },
 };
 
 /// Tunnel manager for VPN connections.
+///
+/// Any number of tunnels may be open at once, each keyed by its id.
+///
+/// Wrapped in a [`Mutex`], the manager is also a [`TunnelProvider`]. The
+/// trait takes `&self` and must be `Sync`, while the manager hands out
+/// references to its tunnels, so the lock lives outside rather than in a
+/// `RefCell` inside.
 pub struct TunnelManager {
-    active_tunnel:  Option<VpnTunnel>,
+    tunnels:        HashMap<u64, VpnTunnel>,
     next_tunnel_id: u64,
 }
 
@@ -20,59 +32,187 @@ impl TunnelManager {
     /// Create a new tunnel manager.
     #[must_use]
     pub fn new() -> Self {
-        Self { active_tunnel: None, next_tunnel_id: 1 }
+        Self { tunnels: HashMap::new(), next_tunnel_id: 1 }
+    }
+
+    /// Create a tunnel to server, returning its id.
+    pub fn create_tunnel(&mut self, server: Rc<VpnServer>) -> u64 {
+        self.insert(&server, TunnelState::Connecting, TunnelRole::Primary)
     }
 
-    /// Create a tunnel to server.
+    /// Create a standby tunnel to server.
+    ///
+    /// Standby tunnels do not carry traffic until promoted.
+    pub fn create_standby(&mut self, server: Rc<VpnServer>) -> u64 {
+        self.insert(&server, TunnelState::Standby, TunnelRole::Backup)
+    }
+
+    /// Get all tunnels, in no particular order.
+    pub fn tunnels(&self) -> impl Iterator<Item = &VpnTunnel> {
+        self.tunnels.values()
+    }
+
+    /// Get a tunnel by id.
+    #[must_use]
+    pub fn tunnel(&self, id: u64) -> Option<&VpnTunnel> {
+        self.tunnels.get(&id)
+    }
+
+    /// Get standby tunnels (oldest first).
+    #[must_use]
+    pub fn standby_tunnels(&self) -> Vec<&VpnTunnel> {
+        self.tunnels_by_role(TunnelRole::Backup)
+    }
+
+    /// Make a standby tunnel a connected primary tunnel.
     ///
     /// # Errors
     ///
-    /// Returns `VpnError::Tunnel` if a tunnel is already active.
-    pub fn create_tunnel(&mut self, server: Rc<VpnServer>) -> VpnResult<u64> {
-        if self.active_tunnel.is_some() {
-            return Err(VpnError::Tunnel("Tunnel already active".to_string()));
-        }
-
-        let id = self.next_tunnel_id;
-        self.next_tunnel_id += 1;
+    /// Returns `VpnError::Tunnel` if no standby tunnel has the given id.
+    pub fn promote_standby(&mut self, id: u64) -> VpnResult<()> {
+        let tunnel = self
+            .tunnels
+            .get_mut(&id)
+            .filter(|t| t.role == TunnelRole::Backup)
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown standby tunnel {id}")))?;
+        tunnel.state = TunnelState::Connected;
+        tunnel.role = TunnelRole::Primary;
+        Ok(())
+    }
 
-        self.active_tunnel = Some(VpnTunnel {
-            id,
-            server: (*server).clone(),
-            state: TunnelState::Connecting,
-            encryption: EncryptionAlgorithm::Aes256GcmPqc,
-            key_exchange: KeyExchangeProtocol::HybridMlKem,
-            stats: ConnectionStats::default(),
-        });
+    /// Close all standby tunnels.
+    pub fn clear_standby(&mut self) {
+        self.tunnels.retain(|_, t| t.role != TunnelRole::Backup);
+    }
 
-        Ok(id)
+    /// Get the tunnels holding `role` (oldest first).
+    #[must_use]
+    pub fn tunnels_by_role(&self, role: TunnelRole) -> Vec<&VpnTunnel> {
+        let mut tunnels: Vec<_> = self.tunnels.values().filter(|t| t.role == role).collect();
+        tunnels.sort_by_key(|t| t.id);
+        tunnels
     }
 
-    /// Get active tunnel.
+    /// Get the most recently created connected tunnel.
     #[must_use]
     pub fn active_tunnel(&self) -> Option<&VpnTunnel> {
-        self.active_tunnel.as_ref()
+        self.tunnels
+            .values()
+            .filter(|t| t.state == TunnelState::Connected)
+            .max_by_key(|t| t.id)
     }
 
-    /// Update tunnel state.
-    pub fn update_state(&mut self, state: TunnelState) {
-        if let Some(ref mut tunnel) = self.active_tunnel {
-            tunnel.state = state;
+    /// Update a tunnel's state.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Tunnel` if no tunnel has the given id or the
+    /// transition is not allowed by [`TunnelState::can_transition_to`].
+    pub fn update_state(&mut self, id: u64, state: TunnelState) -> VpnResult<()> {
+        let tunnel = self
+            .tunnels
+            .get_mut(&id)
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {id}")))?;
+        if !tunnel.state.can_transition_to(state) {
+            return Err(VpnError::Tunnel(format!(
+                "Tunnel {id} cannot move from {} to {}",
+                tunnel.state.as_str(),
+                state.as_str()
+            )));
         }
+        tunnel.state = state;
+        Ok(())
+    }
+
+    /// Change a tunnel's role.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Tunnel` if no tunnel has the given id.
+    pub fn set_role(&mut self, id: u64, role: TunnelRole) -> VpnResult<()> {
+        let tunnel = self
+            .tunnels
+            .get_mut(&id)
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {id}")))?;
+        tunnel.role = role;
+        Ok(())
+    }
+
+    /// Record the negotiated encryption on a tunnel.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Tunnel` if no tunnel has the given id.
+    pub fn set_encryption(&mut self, id: u64, encryption: EncryptionAlgorithm) -> VpnResult<()> {
+        let tunnel = self
+            .tunnels
+            .get_mut(&id)
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {id}")))?;
+        tunnel.encryption = encryption;
+        Ok(())
     }
 
-    /// Close active tunnel.
-    pub fn close_tunnel(&mut self) {
-        if let Some(ref mut tunnel) = self.active_tunnel {
-            tunnel.state = TunnelState::Disconnecting;
+    /// Add traffic to a tunnel's byte counters. Unknown ids are ignored.
+    pub fn add_traffic(&mut self, id: u64, bytes_sent: u64, bytes_received: u64) {
+        if let Some(tunnel) = self.tunnels.get_mut(&id) {
+            tunnel.stats.bytes_sent = tunnel.stats.bytes_sent.saturating_add(bytes_sent);
+            tunnel.stats.bytes_received =
+                tunnel.stats.bytes_received.saturating_add(bytes_received);
         }
-        self.active_tunnel = None;
     }
 
-    /// Check if tunnel is connected.
+    /// Replace a tunnel's statistics with a fresh reading, e.g. polled from
+    /// the provider.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Tunnel` if no tunnel has the given id.
+    pub fn update_stats(&mut self, tunnel_id: u64, stats: ConnectionStats) -> VpnResult<()> {
+        let tunnel = self
+            .tunnels
+            .get_mut(&tunnel_id)
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {tunnel_id}")))?;
+        tunnel.stats = stats;
+        Ok(())
+    }
+
+    /// Get a tunnel's statistics.
+    #[must_use]
+    pub fn tunnel_stats(&self, id: u64) -> Option<&ConnectionStats> {
+        self.tunnels.get(&id).map(|t| &t.stats)
+    }
+
+    /// Close a tunnel. Unknown ids are ignored.
+    pub fn close_tunnel(&mut self, id: u64) {
+        self.tunnels.remove(&id);
+    }
+
+    /// Check if any tunnel is connected.
     #[must_use]
     pub fn is_connected(&self) -> bool {
-        self.active_tunnel.as_ref().is_some_and(|t| t.state == TunnelState::Connected)
+        self.tunnels.values().any(|t| t.state == TunnelState::Connected)
+    }
+
+    /// Get a tunnel by id, or `VpnError::Tunnel` if it is unknown.
+    fn require(&self, id: u64) -> VpnResult<&VpnTunnel> {
+        self.tunnel(id).ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {id}")))
+    }
+
+    /// Build a tunnel with the next id and store it.
+    fn insert(&mut self, server: &VpnServer, state: TunnelState, role: TunnelRole) -> u64 {
+        let id = self.next_tunnel_id;
+        self.next_tunnel_id += 1;
+
+        self.tunnels.insert(id, VpnTunnel {
+            id,
+            server: server.clone(),
+            state,
+            encryption: EncryptionAlgorithm::Aes256GcmPqc,
+            key_exchange: KeyExchangeProtocol::HybridMlKem,
+            stats: ConnectionStats::default(),
+            role,
+        });
+        id
     }
 }
 
@@ -81,3 +221,198 @@ impl Default for TunnelManager {
         Self::new()
     }
 }
+
+impl TunnelProvider for Mutex<TunnelManager> {
+    /// Create a tunnel in the `Connecting` state, as
+    /// [`TunnelManager::create_tunnel`] does.
+    fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel> {
+        let mut manager = self.lock().unwrap_or_else(PoisonError::into_inner);
+        let id = manager.insert(server, TunnelState::Connecting, TunnelRole::Primary);
+        manager.require(id).cloned()
+    }
+
+    fn create_tunnel_via(&self, server: &VpnServer, via_tunnel_id: u64) -> VpnResult<VpnTunnel> {
+        self.lock().unwrap_or_else(PoisonError::into_inner).require(via_tunnel_id)?;
+        self.create_tunnel(server)
+    }
+
+    fn tunnel_state(&self, tunnel_id: u64) -> VpnResult<TunnelState> {
+        let manager = self.lock().unwrap_or_else(PoisonError::into_inner);
+        manager.require(tunnel_id).map(|t| t.state)
+    }
+
+    fn tunnel_stats(&self, tunnel_id: u64) -> VpnResult<ConnectionStats> {
+        let manager = self.lock().unwrap_or_else(PoisonError::into_inner);
+        manager.require(tunnel_id).map(|t| t.stats.clone())
+    }
+
+    fn destroy_tunnel(&self, tunnel_id: u64) -> VpnResult<()> {
+        let mut manager = self.lock().unwrap_or_else(PoisonError::into_inner);
+        manager.require(tunnel_id)?;
+        manager.close_tunnel(tunnel_id);
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    fn server(id: &str) -> Rc<VpnServer> {
+        Rc::new(VpnServer { id: id.to_string(), ..Default::default() })
+    }
+
+    /// Move a `Connecting` tunnel through the handshake to `Connected`.
+    fn connect(manager: &mut TunnelManager, id: u64) {
+        manager
+            .update_state(id, TunnelState::KeyExchange)
+            .expect("Should start handshake");
+        manager.update_state(id, TunnelState::Connected).expect("Should connect");
+    }
+
+    #[test]
+    fn test_concurrent_tunnels() {
+        let mut manager = TunnelManager::new();
+        let first = manager.create_tunnel(server("srv-1"));
+        let second = manager.create_tunnel(server("srv-2"));
+        assert_ne!(first, second);
+        assert_eq!(manager.tunnels().count(), 2);
+        assert!(!manager.is_connected());
+        assert!(manager.active_tunnel().is_none());
+
+        connect(&mut manager, first);
+        assert!(manager.is_connected());
+        assert_eq!(manager.active_tunnel().map(|t| t.id), Some(first));
+
+        connect(&mut manager, second);
+        manager.add_traffic(second, 10, 20);
+        assert_eq!(manager.active_tunnel().map(|t| t.id), Some(second));
+        assert_eq!(
+            manager.tunnel(second).expect("Should find tunnel").stats.bytes_received,
+            20
+        );
+        assert_eq!(
+            manager.tunnel(first).expect("Should find tunnel").stats.bytes_received,
+            0
+        );
+
+        manager.close_tunnel(second);
+        assert!(manager.tunnel(second).is_none());
+        assert_eq!(manager.active_tunnel().map(|t| t.id), Some(first));
+
+        manager.close_tunnel(first);
+        assert!(!manager.is_connected());
+        assert_eq!(manager.tunnels().count(), 0);
+    }
+
+    #[test]
+    fn test_tunnel_provider_lifecycle() {
+        let manager = Mutex::new(TunnelManager::new());
+        let provider: &dyn TunnelProvider = &manager;
+
+        let tunnel = provider.create_tunnel(&server("srv-1")).expect("Should create");
+        assert_eq!(tunnel.server.id, "srv-1");
+        assert_eq!(
+            provider.tunnel_state(tunnel.id).expect("Should report state"),
+            TunnelState::Connecting
+        );
+
+        connect(&mut manager.lock().expect("Should lock manager"), tunnel.id);
+        manager.lock().expect("Should lock manager").add_traffic(tunnel.id, 5, 7);
+        assert_eq!(
+            provider.tunnel_state(tunnel.id).expect("Should report state"),
+            TunnelState::Connected
+        );
+        assert_eq!(
+            provider.tunnel_stats(tunnel.id).expect("Should report stats").bytes_received,
+            7
+        );
+
+        provider.destroy_tunnel(tunnel.id).expect("Should destroy");
+        assert!(matches!(
+            provider.tunnel_state(tunnel.id),
+            Err(VpnError::Tunnel(_))
+        ));
+        assert!(matches!(
+            provider.tunnel_stats(tunnel.id),
+            Err(VpnError::Tunnel(_))
+        ));
+        assert!(matches!(
+            provider.destroy_tunnel(tunnel.id),
+            Err(VpnError::Tunnel(_))
+        ));
+    }
+
+    #[test]
+    fn test_update_stats() {
+        let mut manager = TunnelManager::new();
+        let id = manager.create_tunnel(server("srv-1"));
+        assert_eq!(manager.tunnel_stats(id).map(|s| s.bytes_sent), Some(0));
+
+        let stats = ConnectionStats {
+            bytes_sent: 1_500,
+            bytes_received: 9_000,
+            latency_ms: 42,
+            ..ConnectionStats::default()
+        };
+        manager.update_stats(id, stats).expect("Should update");
+        let stored = manager.tunnel_stats(id).expect("Should exist");
+        assert_eq!(
+            (stored.bytes_sent, stored.bytes_received, stored.latency_ms),
+            (1_500, 9_000, 42)
+        );
+
+        assert_eq!(
+            manager.update_stats(id + 1, ConnectionStats::default()),
+            Err(VpnError::Tunnel(format!("Unknown tunnel {}", id + 1)))
+        );
+        assert!(manager.tunnel_stats(id + 1).is_none());
+    }
+
+    #[test]
+    fn test_update_state_enforces_transitions() {
+        use TunnelState::{
+            Connected, Connecting, Disconnected, Disconnecting, Error, KeyExchange, Reconnecting,
+            Standby,
+        };
+
+        let table = [
+            (Disconnected, Connecting, true),
+            (Connecting, KeyExchange, true),
+            (KeyExchange, Connected, true),
+            (Connected, Reconnecting, true),
+            (Reconnecting, Connecting, true),
+            (Connected, Disconnecting, true),
+            (Disconnecting, Disconnected, true),
+            (Standby, Connected, true),
+            (KeyExchange, Error, true),
+            (Error, Reconnecting, true),
+            (Reconnecting, Reconnecting, true),
+            (Disconnected, Connected, false),
+            (Connecting, Connected, false),
+            (Connected, KeyExchange, false),
+            (Disconnecting, Connected, false),
+            (Error, Connected, false),
+            (Reconnecting, Connected, false),
+            (Standby, KeyExchange, false),
+        ];
+        for (from, to, legal) in table {
+            assert_eq!(from.can_transition_to(to), legal, "{from:?} -> {to:?}");
+        }
+
+        let mut manager = TunnelManager::new();
+        let id = manager.create_tunnel(server("srv-1"));
+        assert_eq!(
+            manager.update_state(id, Connected),
+            Err(VpnError::Tunnel(
+                "Tunnel 1 cannot move from connecting to connected".to_string()
+            ))
+        );
+        assert_eq!(
+            manager.tunnel(id).expect("Should find tunnel").state,
+            Connecting
+        );
+        connect(&mut manager, id);
+        assert!(manager.update_state(99, Error).is_err());
+    }
+}
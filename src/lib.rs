@@ -1,6 +1,5 @@
 //! Essentia VPN Plugin
 
-
 // VPN plugin pedantic lint allowances (VPN-LINT-STAGING-01)
 #![allow(clippy::must_use_candidate)]
 #![allow(clippy::missing_errors_doc)]
@@ -50,17 +49,47 @@ pub mod types;
 // FlexForge Integration (standalone module)
 pub mod flexforge;
 
+// Test scaffolding for downstream users
+#[cfg(feature = "test-util")]
+pub mod test_util;
+
+// Simulated backend for UI previews
+#[cfg(any(feature = "demo", feature = "test-util"))]
+pub mod simulation;
+
 // Re-exports from errors/
-pub use errors::{VpnError, VpnResult};
+pub use errors::{VpnError, VpnErrorKind, VpnResult};
 // Re-exports from flexforge
-pub use flexforge::{ConnectionState, VpnPluginFlexForge, VpnUiConfig};
+pub use flexforge::{
+    ConfigFieldView, ConnectionState, FieldConstraint, StatusFrame, VpnPluginFlexForge, VpnUiConfig,
+};
 // Re-exports from impl/
-pub use implementation::{NeuralRouter, PqcKeyExchange, TunnelManager, VpnConfig, VpnPlugin};
+#[cfg(feature = "async")]
+pub use implementation::BlockingConnection;
+#[cfg(feature = "test-util")]
+pub use implementation::MockClock;
+pub use implementation::{
+    BackoffStrategy, CircuitBreaker, ConnectMetrics, DerivedKeys, EncryptedConfigStore,
+    FileConfigStore, HandshakeTrace, KexState, NeuralRouter, PhaseStats, PqcKeyExchange,
+    SessionTicket, StatsHistory, SystemClock, TcpPinger, TraceStep, TunnelManager, VpnConfig,
+    VpnConfigBuilder, VpnPlugin,
+};
 // Re-exports from traits/
-pub use traits::{TunnelProvider, VpnConnection};
+#[cfg(feature = "async")]
+pub use traits::AsyncVpnConnection;
+pub use traits::{
+    Authenticator, Clock, ConfigStore, ConnectionObserver, DnsLeakChecker, HandshakeStep,
+    KillSwitchBackend, Pinger, PostConnectVerifier, TunnelProvider, VpnConnection, Zeroize,
+};
 // Re-exports from types/
 pub use types::{
-    ConnectionStats, EncryptionAlgorithm, KeyExchangeProtocol, TunnelState, VpnServer, VpnTunnel,
+    Cidr, CircuitState, ConnectionAttempt, ConnectionStats, ConnectivityDiagnosis, Credentials,
+    Direction, DisconnectReason, EncryptionAlgorithm, ExclusionReason, HandshakeProgress,
+    IpPreference, KeyExchangeProtocol, KillSwitchExemptions, LATENCY_WINDOW, MlKemVariant,
+    NEUTRAL_QUALITY, PreflightReport, Role, RuleConflict, SecurityLevel, ServerHealth,
+    ServerRegion, ServerSummary, SessionSummary, SortKey, SplitTunnelMode, SplitTunnelRule,
+    SplitTunnelRules, StateTransition, TrafficSample, TransportMode, TunnelHandle, TunnelRole,
+    TunnelState, UseCase, VerificationResult, VpnEvent, VpnServer, VpnTunnel, Zeroizing,
 };
 
 #[cfg(all(test, feature = "full-tests"))]
@@ -72,4 +101,4 @@ mod tests {
         let config = VpnConfig::default();
         assert!(config.kill_switch);
     }
-}
\ No newline at end of file
+}
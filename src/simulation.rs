@@ -0,0 +1,247 @@
+//! Simulated backend for UI previews and demos.
+//!
+//! Enabled with the `demo` or `test-util` feature. [`VpnPlugin::simulate`]
+//! builds a plugin whose tunnels are provided by a
+//! [`SimulatedTunnelProvider`]: connects walk through `Connecting`,
+//! `KeyExchange` and `Connected` with realistic phase durations, and traffic
+//! counters tick up while connected. Nothing here ever touches the network.
+//!
+//! ```
+//! use essentia_vpn_plugin::{TunnelState, VpnConfig, VpnPlugin};
+//!
+//! let mut plugin = VpnPlugin::simulate(VpnConfig::default());
+//! plugin.connect_optimal().expect("Should connect");
+//! assert_eq!(plugin.state(), TunnelState::Connected);
+//! assert!(plugin.connect_metrics().handshake.min_ms >= 600);
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    implementation::{SystemClock, VpnConfig, VpnPlugin},
+    traits::{Clock, TunnelProvider},
+    types::{ConnectionStats, ServerRegion, TunnelRole, TunnelState, VpnServer, VpnTunnel},
+};
+
+/// Simulated time to bring a tunnel up.
+const CONNECT_DELAY: Duration = Duration::from_millis(350);
+
+/// Simulated key exchange round trip.
+const HANDSHAKE_DELAY: Duration = Duration::from_millis(600);
+
+/// Synthetic upload rate (bytes per second).
+const SEND_RATE_BPS: u64 = 120_000;
+
+/// Synthetic download rate (bytes per second).
+const RECV_RATE_BPS: u64 = 850_000;
+
+impl VpnPlugin {
+    /// Create a plugin backed by a simulated provider and clock.
+    ///
+    /// The router is seeded with a small demo pool. Connect delays are
+    /// skipped on the simulated clock rather than slept, so `connect`
+    /// returns immediately while [`connect_metrics`](Self::connect_metrics)
+    /// and the state history report realistic timings. Call
+    /// [`sync_traffic`](Self::sync_traffic) periodically to tick the
+    /// synthetic stats. Never touches the network.
+    #[must_use]
+    pub fn simulate(config: VpnConfig) -> Self {
+        simulate_with_clock(config, Arc::new(SimulatedClock::new()))
+    }
+}
+
+/// Build a simulated plugin driven by `clock`.
+fn simulate_with_clock(config: VpnConfig, clock: Arc<SimulatedClock>) -> VpnPlugin {
+    let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+    plugin.set_tunnel_provider(Box::new(SimulatedTunnelProvider::new(clock)));
+    for server in demo_servers() {
+        plugin.router_mut().add_server(Rc::new(RefCell::new(server)));
+    }
+    plugin
+}
+
+/// Wall clock whose `sleep` skips ahead instead of blocking.
+///
+/// Time keeps flowing in real time, so stats tick up in a live preview,
+/// but simulated delays cost nothing.
+#[derive(Debug, Default)]
+pub struct SimulatedClock {
+    skipped_millis: AtomicU64,
+}
+
+impl SimulatedClock {
+    /// Create a clock reading the current wall time.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward without waiting.
+    pub fn advance(&self, duration: Duration) {
+        self.skipped_millis.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now_millis(&self) -> u64 {
+        SystemClock
+            .now_millis()
+            .saturating_add(self.skipped_millis.load(Ordering::SeqCst))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// Tunnel provider that fakes connect delays and traffic.
+///
+/// Each tunnel reports traffic at a fixed synthetic rate since it was
+/// opened, measured on the shared clock.
+#[derive(Debug)]
+pub struct SimulatedTunnelProvider {
+    clock: Arc<SimulatedClock>,
+    state: Mutex<SimulatedState>,
+}
+
+#[derive(Debug, Default)]
+struct SimulatedState {
+    next_id: u64,
+    opened:  HashMap<u64, u64>,
+}
+
+impl SimulatedTunnelProvider {
+    /// Create a provider timing its tunnels on `clock`.
+    #[must_use]
+    pub fn new(clock: Arc<SimulatedClock>) -> Self {
+        Self { clock, state: Mutex::default() }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SimulatedState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn opened_at(&self, tunnel_id: u64) -> VpnResult<u64> {
+        self.lock()
+            .opened
+            .get(&tunnel_id)
+            .copied()
+            .ok_or_else(|| VpnError::Tunnel(format!("Unknown tunnel {tunnel_id}")))
+    }
+}
+
+impl TunnelProvider for SimulatedTunnelProvider {
+    fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel> {
+        self.clock.sleep(CONNECT_DELAY);
+
+        let mut state = self.lock();
+        state.next_id += 1;
+        let id = state.next_id;
+        state.opened.insert(id, self.clock.now_millis());
+        Ok(VpnTunnel {
+            id,
+            server: server.clone(),
+            state: TunnelState::Connected,
+            encryption: Default::default(),
+            key_exchange: Default::default(),
+            stats: ConnectionStats::default(),
+            role: TunnelRole::Primary,
+        })
+    }
+
+    fn create_tunnel_via(&self, server: &VpnServer, via_tunnel_id: u64) -> VpnResult<VpnTunnel> {
+        self.opened_at(via_tunnel_id)?;
+        self.create_tunnel(server)
+    }
+
+    fn exchange_keys(&self, tunnel_id: u64, _ciphertext: &[u8]) -> VpnResult<()> {
+        self.opened_at(tunnel_id)?;
+        self.clock.sleep(HANDSHAKE_DELAY);
+        Ok(())
+    }
+
+    fn tunnel_state(&self, tunnel_id: u64) -> VpnResult<TunnelState> {
+        self.opened_at(tunnel_id).map(|_| TunnelState::Connected)
+    }
+
+    fn tunnel_stats(&self, tunnel_id: u64) -> VpnResult<ConnectionStats> {
+        let elapsed_millis = self.clock.now_millis().saturating_sub(self.opened_at(tunnel_id)?);
+        Ok(ConnectionStats {
+            bytes_sent: elapsed_millis.saturating_mul(SEND_RATE_BPS) / 1000,
+            bytes_received: elapsed_millis.saturating_mul(RECV_RATE_BPS) / 1000,
+            uptime_secs: elapsed_millis / 1000,
+            ..ConnectionStats::default()
+        })
+    }
+
+    fn destroy_tunnel(&self, tunnel_id: u64) -> VpnResult<()> {
+        self.opened_at(tunnel_id)?;
+        self.lock().opened.remove(&tunnel_id);
+        Ok(())
+    }
+}
+
+/// Server pool seeded by [`VpnPlugin::simulate`].
+fn demo_servers() -> Vec<VpnServer> {
+    [
+        ("demo-nyc", "US", ServerRegion::UsEast, 0.35),
+        ("demo-fra", "DE", ServerRegion::EuCentral, 0.5),
+        ("demo-sgp", "SG", ServerRegion::AsiaPacific, 0.6),
+    ]
+    .into_iter()
+    .map(|(id, country, region, load)| VpnServer {
+        id: id.to_string(),
+        hostname: format!("{id}.demo.invalid"),
+        port: 443,
+        country: country.to_string(),
+        load,
+        pqc_enabled: true,
+        region: Some(region),
+        ..VpnServer::default()
+    })
+    .collect()
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_connect_and_traffic() {
+        let clock = Arc::new(SimulatedClock::new());
+        let mut plugin = simulate_with_clock(VpnConfig::default(), clock.clone());
+        plugin.set_sample_interval(Duration::ZERO);
+
+        plugin.connect_optimal().expect("Should connect");
+        let states: Vec<_> = plugin.state_history().map(|t| t.to).collect();
+        assert_eq!(states, [
+            TunnelState::Connecting,
+            TunnelState::KeyExchange,
+            TunnelState::Connected,
+        ]);
+        let metrics = plugin.connect_metrics();
+        assert!(metrics.connecting.min_ms >= 350);
+        assert!(metrics.handshake.min_ms >= 600);
+
+        clock.advance(Duration::from_secs(10));
+        plugin.sync_traffic().expect("Should sync");
+        let sample = plugin.stats_history().latest().copied().expect("Should sample");
+        assert!(sample.bytes_received >= 10 * RECV_RATE_BPS);
+
+        plugin.disconnect();
+        assert!(
+            plugin.last_session().expect("Should keep the session").bytes_sent
+                >= 10 * SEND_RATE_BPS
+        );
+    }
+}
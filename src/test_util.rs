@@ -0,0 +1,376 @@
+//! Test scaffolding for code built on the VPN plugin.
+//!
+//! Enabled with the `test-util` feature. [`VpnTestHarness`] wires a
+//! [`VpnPlugin`] to a seeded server pool, a [`MockTunnelProvider`], a
+//! [`MockClock`] and a [`RecordingObserver`], so reconnect and failover
+//! logic can be exercised without a network.
+//!
+//! ## Failover when a server refuses the tunnel
+//!
+//! ```
+//! use essentia_vpn_plugin::{TunnelState, VpnConfig, test_util::VpnTestHarness};
+//!
+//! let mut harness = VpnTestHarness::new(VpnConfig::default());
+//! harness.provider().fail_next(1);
+//!
+//! assert!(harness.connect_and_assert_state("us-east-1", TunnelState::Disconnected).is_err());
+//! harness.connect_and_assert_state("eu-west-1", TunnelState::Connected).expect("Should connect");
+//! assert_eq!(harness.provider().live_tunnels(), 1);
+//! ```
+//!
+//! ## Reconnect circuit breaker cooling down
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use essentia_vpn_plugin::{
+//!     VpnConfig,
+//!     test_util::{RecordedEvent, VpnTestHarness},
+//! };
+//!
+//! let config = VpnConfig { circuit_max_reconnects: 1, ..VpnConfig::default() };
+//! let mut harness = VpnTestHarness::new(config);
+//!
+//! harness.plugin_mut().begin_reconnect_attempt().expect("Should allow an attempt");
+//! assert!(harness.plugin_mut().begin_reconnect_attempt().is_err());
+//! assert!(matches!(harness.recorded_events()[..], [RecordedEvent::ReconnectExhausted(_)]));
+//!
+//! harness.advance_clock(Duration::from_secs(300));
+//! assert!(harness.plugin_mut().begin_reconnect_attempt().is_ok());
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use crate::{
+    errors::{VpnError, VpnResult},
+    implementation::{MockClock, VpnConfig, VpnPlugin},
+    traits::{ConnectionObserver, TunnelProvider},
+    types::{
+        ConnectionStats, Direction, ServerRegion, SessionSummary, TunnelRole, TunnelState,
+        VpnServer, VpnTunnel,
+    },
+};
+
+/// Start time of the harness clock (2024-01-01T00:00:00Z).
+const HARNESS_EPOCH_MILLIS: u64 = 1_704_067_200_000;
+
+/// Notification captured by a [`RecordingObserver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedEvent {
+    /// `on_session_end`.
+    SessionEnd(SessionSummary),
+    /// `on_warning`.
+    Warning(String),
+    /// `on_reconnect_exhausted`.
+    ReconnectExhausted(String),
+    /// `on_error`, with the error's display text.
+    Error(String),
+    /// `on_error_repeated`, with the error's display text and count.
+    ErrorRepeated(String, u32),
+    /// `on_bandwidth_alert`.
+    BandwidthAlert(Direction, f64, f64),
+}
+
+/// Observer that records every notification it receives.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingObserver {
+    events: Arc<Mutex<Vec<RecordedEvent>>>,
+}
+
+impl RecordingObserver {
+    /// Create an observer with an empty log.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a copy of the recorded events (oldest first).
+    #[must_use]
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        lock(&self.events).clone()
+    }
+
+    fn push(&self, event: RecordedEvent) {
+        lock(&self.events).push(event);
+    }
+}
+
+impl ConnectionObserver for RecordingObserver {
+    fn on_session_end(&mut self, summary: &SessionSummary) {
+        self.push(RecordedEvent::SessionEnd(summary.clone()));
+    }
+
+    fn on_warning(&mut self, message: &str) {
+        self.push(RecordedEvent::Warning(message.to_string()));
+    }
+
+    fn on_reconnect_exhausted(&mut self, message: &str) {
+        self.push(RecordedEvent::ReconnectExhausted(message.to_string()));
+    }
+
+    fn on_error(&mut self, error: &VpnError) {
+        self.push(RecordedEvent::Error(error.to_string()));
+    }
+
+    fn on_error_repeated(&mut self, error: &VpnError, count: u32) {
+        self.push(RecordedEvent::ErrorRepeated(error.to_string(), count));
+    }
+
+    fn on_bandwidth_alert(&mut self, direction: Direction, bps: f64, threshold: f64) {
+        self.push(RecordedEvent::BandwidthAlert(direction, bps, threshold));
+    }
+}
+
+/// In-memory tunnel provider with scriptable failures.
+///
+/// Clones share state, so a test can keep a handle after giving one to the
+/// plugin.
+#[derive(Debug, Clone, Default)]
+pub struct MockTunnelProvider {
+    state: Arc<Mutex<MockProviderState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockProviderState {
+    next_id:          u64,
+    failures_pending: u32,
+    created:          u32,
+    tunnels:          HashMap<u64, VpnTunnel>,
+}
+
+impl MockTunnelProvider {
+    /// Create a provider that accepts every tunnel.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next `count` tunnel creations fail with `VpnError::Tunnel`.
+    pub fn fail_next(&self, count: u32) {
+        lock(&self.state).failures_pending = count;
+    }
+
+    /// Get the number of tunnels successfully created.
+    #[must_use]
+    pub fn created_count(&self) -> u32 {
+        lock(&self.state).created
+    }
+
+    /// Get the number of tunnels created and not yet destroyed.
+    #[must_use]
+    pub fn live_tunnels(&self) -> usize {
+        lock(&self.state).tunnels.len()
+    }
+}
+
+impl TunnelProvider for MockTunnelProvider {
+    fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel> {
+        let mut state = lock(&self.state);
+        if state.failures_pending > 0 {
+            state.failures_pending -= 1;
+            return Err(VpnError::Tunnel(format!(
+                "Mock provider refused {}",
+                server.id
+            )));
+        }
+
+        state.next_id += 1;
+        state.created += 1;
+        let tunnel = VpnTunnel {
+            id:           state.next_id,
+            server:       server.clone(),
+            state:        TunnelState::Connected,
+            encryption:   Default::default(),
+            key_exchange: Default::default(),
+            stats:        ConnectionStats::default(),
+            role:         TunnelRole::Primary,
+        };
+        state.tunnels.insert(tunnel.id, tunnel.clone());
+        Ok(tunnel)
+    }
+
+    fn create_tunnel_via(&self, server: &VpnServer, via_tunnel_id: u64) -> VpnResult<VpnTunnel> {
+        if !lock(&self.state).tunnels.contains_key(&via_tunnel_id) {
+            return Err(unknown_tunnel(via_tunnel_id));
+        }
+        self.create_tunnel(server)
+    }
+
+    fn tunnel_state(&self, tunnel_id: u64) -> VpnResult<TunnelState> {
+        lock(&self.state)
+            .tunnels
+            .get(&tunnel_id)
+            .map(|t| t.state)
+            .ok_or_else(|| unknown_tunnel(tunnel_id))
+    }
+
+    fn tunnel_stats(&self, tunnel_id: u64) -> VpnResult<ConnectionStats> {
+        lock(&self.state)
+            .tunnels
+            .get(&tunnel_id)
+            .map(|t| t.stats.clone())
+            .ok_or_else(|| unknown_tunnel(tunnel_id))
+    }
+
+    fn destroy_tunnel(&self, tunnel_id: u64) -> VpnResult<()> {
+        lock(&self.state)
+            .tunnels
+            .remove(&tunnel_id)
+            .map(|_| ())
+            .ok_or_else(|| unknown_tunnel(tunnel_id))
+    }
+}
+
+/// Plugin wired to mocks, with helpers for common assertions.
+pub struct VpnTestHarness {
+    plugin:   VpnPlugin,
+    clock:    Arc<MockClock>,
+    provider: MockTunnelProvider,
+    observer: RecordingObserver,
+}
+
+impl VpnTestHarness {
+    /// Create a harness with `config` and the default seeded servers:
+    /// `us-east-1` (load 0.3), `eu-west-1` (0.5) and `ap-1` (0.7), all PQC.
+    #[must_use]
+    pub fn new(config: VpnConfig) -> Self {
+        Self::with_servers(config, seed_servers())
+    }
+
+    /// Create a harness with `config` and the given server pool.
+    #[must_use]
+    pub fn with_servers(config: VpnConfig, servers: impl IntoIterator<Item = VpnServer>) -> Self {
+        let clock = Arc::new(MockClock::new(HARNESS_EPOCH_MILLIS));
+        let provider = MockTunnelProvider::new();
+        let observer = RecordingObserver::new();
+
+        let mut plugin = VpnPlugin::with_clock(config, clock.clone());
+        plugin.set_tunnel_provider(Box::new(provider.clone()));
+        plugin.add_observer(Box::new(observer.clone()));
+        for server in servers {
+            plugin.router_mut().add_server(Rc::new(RefCell::new(server)));
+        }
+
+        Self { plugin, clock, provider, observer }
+    }
+
+    /// Get the plugin under test.
+    #[must_use]
+    pub fn plugin(&self) -> &VpnPlugin {
+        &self.plugin
+    }
+
+    /// Get the plugin under test mutably.
+    pub fn plugin_mut(&mut self) -> &mut VpnPlugin {
+        &mut self.plugin
+    }
+
+    /// Get the mock clock driving the plugin.
+    #[must_use]
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Get the mock tunnel provider.
+    #[must_use]
+    pub fn provider(&self) -> &MockTunnelProvider {
+        &self.provider
+    }
+
+    /// Connect to a pooled server and assert the resulting tunnel state.
+    ///
+    /// The state is checked whether or not the connect succeeded, so this
+    /// also covers failure paths. Returns the connect result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `server_id` is not in the pool or the state differs from
+    /// `expected`.
+    pub fn connect_and_assert_state(
+        &mut self,
+        server_id: &str,
+        expected: TunnelState,
+    ) -> VpnResult<()> {
+        let server = self
+            .plugin
+            .router()
+            .servers()
+            .iter()
+            .find(|s| s.borrow().id == server_id)
+            .map(|s| s.borrow().clone())
+            .unwrap_or_else(|| panic!("server {server_id} is not in the harness pool"));
+
+        let result = self.plugin.connect(Rc::new(server));
+        assert_eq!(
+            self.plugin.state(),
+            expected,
+            "unexpected state after connecting to {server_id}"
+        );
+        result
+    }
+
+    /// Move the plugin clock forward.
+    pub fn advance_clock(&self, duration: Duration) {
+        self.clock.advance(duration);
+    }
+
+    /// Get the observer notifications recorded so far (oldest first).
+    #[must_use]
+    pub fn recorded_events(&self) -> Vec<RecordedEvent> {
+        self.observer.events()
+    }
+}
+
+/// Default server pool used by [`VpnTestHarness::new`].
+fn seed_servers() -> Vec<VpnServer> {
+    [
+        ("us-east-1", "US", ServerRegion::UsEast, 0.3),
+        ("eu-west-1", "IE", ServerRegion::EuWest, 0.5),
+        ("ap-1", "SG", ServerRegion::AsiaPacific, 0.7),
+    ]
+    .into_iter()
+    .map(|(id, country, region, load)| VpnServer {
+        id: id.to_string(),
+        hostname: format!("{id}.vpn.test"),
+        port: 443,
+        country: country.to_string(),
+        load,
+        pqc_enabled: true,
+        region: Some(region),
+        ..VpnServer::default()
+    })
+    .collect()
+}
+
+fn unknown_tunnel(tunnel_id: u64) -> VpnError {
+    VpnError::Tunnel(format!("Unknown tunnel {tunnel_id}"))
+}
+
+/// Lock a mutex, recovering the data if a panicking test poisoned it.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_harness_disconnect_destroys_provider_tunnel() {
+        let mut harness = VpnTestHarness::new(VpnConfig::default());
+        harness
+            .connect_and_assert_state("ap-1", TunnelState::Connected)
+            .expect("Should connect");
+        assert_eq!(harness.provider().live_tunnels(), 1);
+
+        harness.plugin_mut().disconnect();
+        assert_eq!(harness.provider().live_tunnels(), 0);
+        assert_eq!(harness.provider().created_count(), 1);
+    }
+}
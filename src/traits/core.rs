@@ -1,15 +1,62 @@
 //! Core VPN traits.
 
+use std::{net::IpAddr, time::Duration};
+
 use crate::{
-    errors::VpnResult,
-    types::{ConnectionStats, TunnelState, VpnServer, VpnTunnel},
+    errors::{VpnError, VpnResult},
+    types::{
+        ConnectionStats, Credentials, Direction, HandshakeProgress, KillSwitchExemptions,
+        SessionSummary, TunnelState, VerificationResult, VpnServer, VpnTunnel,
+    },
 };
 
+/// Secret material that can be scrubbed in place.
+pub trait Zeroize {
+    /// Overwrites the value with zeros.
+    fn zeroize(&mut self);
+}
+
 /// Trait for VPN tunnel providers.
 pub trait TunnelProvider: Send + Sync {
     /// Creates a new tunnel to the specified server.
     fn create_tunnel(&self, server: &VpnServer) -> VpnResult<VpnTunnel>;
 
+    /// Creates a new tunnel to the specified server, carried inside the
+    /// existing tunnel `via_tunnel_id`, for multi-hop chains.
+    ///
+    /// The default fails with `VpnError::Tunnel`, so a provider that cannot
+    /// chain never silently exposes the exit hop to the client's address.
+    fn create_tunnel_via(&self, server: &VpnServer, via_tunnel_id: u64) -> VpnResult<VpnTunnel> {
+        Err(VpnError::Tunnel(format!(
+            "Provider cannot chain {} through tunnel {via_tunnel_id}",
+            server.id
+        )))
+    }
+
+    /// Gets the server's key exchange public key for a tunnel.
+    ///
+    /// The default returns `None`: the tunnel has no remote key exchange
+    /// peer, and the plugin stands in a local responder.
+    fn server_public_key(&self, _tunnel_id: u64) -> VpnResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Sends the shared secret encapsulated against the server's public key
+    /// for a tunnel and completes the key exchange with the server. The
+    /// default does nothing.
+    fn exchange_keys(&self, _tunnel_id: u64, _ciphertext: &[u8]) -> VpnResult<()> {
+        Ok(())
+    }
+
+    /// Asks the server to resume the previous session on a tunnel instead of
+    /// running a key exchange, sending the client's resumption nonce.
+    ///
+    /// Returns `false` if the server declines, in which case a full key
+    /// exchange follows. The default declines.
+    fn resume_session(&self, _tunnel_id: u64, _nonce: &[u8]) -> VpnResult<bool> {
+        Ok(false)
+    }
+
     /// Gets the current state of a tunnel.
     fn tunnel_state(&self, tunnel_id: u64) -> VpnResult<TunnelState>;
 
@@ -34,3 +81,125 @@ pub trait VpnConnection: Send + Sync {
     /// Gets the current connection state.
     fn connection_state(&self) -> TunnelState;
 }
+
+/// Async counterpart of [`VpnConnection`] for async runtimes.
+///
+/// The futures are `Send` so they can be spawned on multi-threaded
+/// executors. Wrap a blocking implementation in
+/// [`BlockingConnection`](crate::BlockingConnection) to use it here.
+#[cfg(feature = "async")]
+pub trait AsyncVpnConnection: Send + Sync {
+    /// Connects to a VPN server.
+    fn connect(
+        &mut self,
+        server: &VpnServer,
+    ) -> impl core::future::Future<Output = VpnResult<()>> + Send;
+
+    /// Disconnects from the current server.
+    fn disconnect(&mut self) -> impl core::future::Future<Output = VpnResult<()>> + Send;
+
+    /// Checks if currently connected.
+    fn is_connected(&self) -> bool;
+
+    /// Gets the current connection state.
+    fn connection_state(&self) -> TunnelState;
+}
+
+/// Source of time for the plugin, injectable for deterministic tests.
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_millis(&self) -> u64;
+
+    /// Seconds since the Unix epoch.
+    fn now_secs(&self) -> u64 {
+        self.now_millis() / 1000
+    }
+
+    /// Blocks for `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// Trait for driving the tunnel handshake to completion.
+///
+/// Polled after the key exchange until it reports
+/// [`HandshakeProgress::Complete`], so the plugin can give up once the
+/// connect timeout elapses.
+pub trait HandshakeStep: Send + Sync {
+    /// Advances the handshake of a tunnel and reports its progress.
+    fn poll(&self, tunnel: &VpnTunnel) -> VpnResult<HandshakeProgress>;
+}
+
+/// Trait for measuring the round-trip latency to a server.
+pub trait Pinger: Send + Sync {
+    /// Measures the round trip to `server` in milliseconds.
+    fn measure(&self, server: &VpnServer) -> VpnResult<u32>;
+}
+
+/// Trait for checks run after a tunnel reports `Connected`.
+///
+/// Used to confirm traffic is actually routed through the tunnel, e.g. by
+/// comparing the observed egress IP with the VPN server.
+pub trait PostConnectVerifier: Send + Sync {
+    /// Verifies the freshly connected tunnel.
+    fn verify(&self, tunnel: &VpnTunnel) -> VpnResult<VerificationResult>;
+}
+
+/// Trait for re-authenticating a live tunnel with fresh credentials.
+///
+/// Implementations must keep the tunnel's existing shared secret; only the
+/// authentication with the server is renewed.
+pub trait Authenticator: Send + Sync {
+    /// Re-authenticates `tunnel` using `credentials`.
+    fn reauthenticate(&self, tunnel: &VpnTunnel, credentials: &Credentials) -> VpnResult<()>;
+}
+
+/// Trait for persisting the serialized configuration.
+pub trait ConfigStore: Send + Sync {
+    /// Returns the stored bytes, or `None` if nothing has been saved.
+    fn load(&self) -> VpnResult<Option<Vec<u8>>>;
+
+    /// Replaces the stored bytes with `bytes`.
+    fn save(&self, bytes: &[u8]) -> VpnResult<()>;
+}
+
+/// Trait for the system firewall backend enforcing the kill switch.
+pub trait KillSwitchBackend: Send + Sync {
+    /// Blocks all traffic outside the tunnel except `exemptions`.
+    fn engage(&self, exemptions: &KillSwitchExemptions) -> VpnResult<()>;
+
+    /// Restores normal traffic.
+    fn release(&self) -> VpnResult<()>;
+}
+
+/// Trait for detecting DNS queries escaping the tunnel.
+pub trait DnsLeakChecker: Send + Sync {
+    /// Returns resolvers observed answering outside `tunnel`; empty when
+    /// there is no leak.
+    fn leaked_resolvers(&self, tunnel: &VpnTunnel) -> VpnResult<Vec<IpAddr>>;
+}
+
+/// Observer for VPN connection lifecycle notifications.
+///
+/// All methods have empty default implementations so observers only
+/// override the notifications they care about.
+pub trait ConnectionObserver: Send + Sync {
+    /// Called once when the plugin shuts down with the final session summary.
+    fn on_session_end(&mut self, _summary: &SessionSummary) {}
+
+    /// Called for non-fatal conditions the user may want to know about.
+    fn on_warning(&mut self, _message: &str) {}
+
+    /// Called when reconnecting is abandoned, with the reason.
+    fn on_reconnect_exhausted(&mut self, _reason: &str) {}
+
+    /// Called for the first error of a burst of identical errors.
+    fn on_error(&mut self, _error: &VpnError) {}
+
+    /// Called when a burst of identical errors ends, with the total number
+    /// of occurrences (including the one passed to `on_error`).
+    fn on_error_repeated(&mut self, _error: &VpnError, _count: u32) {}
+
+    /// Called when throughput in `direction` crosses its alert `threshold`
+    /// (bytes/s), once when rising above it and once when falling back.
+    fn on_bandwidth_alert(&mut self, _direction: Direction, _bps: f64, _threshold: f64) {}
+}
@@ -4,4 +4,9 @@
 
 mod core;
 
-pub use core::{TunnelProvider, VpnConnection};
+#[cfg(feature = "async")]
+pub use core::AsyncVpnConnection;
+pub use core::{
+    Authenticator, Clock, ConfigStore, ConnectionObserver, DnsLeakChecker, HandshakeStep,
+    KillSwitchBackend, Pinger, PostConnectVerifier, TunnelProvider, VpnConnection, Zeroize,
+};
@@ -1,22 +1,231 @@
 //! Core VPN type definitions.
 
+use core::{fmt, str::FromStr};
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs},
+};
+
+use crate::errors::{VpnError, VpnResult};
+
 /// VPN server representation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct VpnServer {
     /// Server identifier.
-    pub id:          String,
+    pub id:                     String,
     /// Server hostname or IP.
-    pub hostname:    String,
+    pub hostname:               String,
+    /// Known IPv4 address; when either address is set, DNS is skipped.
+    pub ipv4_addr:              Option<Ipv4Addr>,
+    /// Known IPv6 address; when either address is set, DNS is skipped.
+    pub ipv6_addr:              Option<Ipv6Addr>,
     /// Server port.
-    pub port:        u16,
+    pub port:                   u16,
     /// Server country code.
-    pub country:     String,
+    pub country:                String,
     /// Server city.
-    pub city:        String,
+    pub city:                   String,
+    /// Latitude in degrees, if known.
+    pub latitude:               Option<f64>,
+    /// Longitude in degrees, if known.
+    pub longitude:              Option<f64>,
     /// Server load (0.0 - 1.0).
-    pub load:        f32,
+    pub load:                   f32,
+    /// Round-trip latency from the last measurement (ms), if any.
+    pub latency_ms:             Option<u32>,
+    /// Users currently connected.
+    pub current_users:          u32,
+    /// Connection cap; `0` if the server reports none.
+    pub max_users:              u32,
     /// Supports PQC.
-    pub pqc_enabled: bool,
+    pub pqc_enabled:            bool,
+    /// Capability tags advertised by the control plane (e.g. `streaming`).
+    pub tags:                   Vec<String>,
+    /// Region the server is deployed in, if known.
+    pub region:                 Option<ServerRegion>,
+    /// Encryption algorithms the server supports.
+    pub supported_encryption:   Vec<EncryptionAlgorithm>,
+    /// Key exchange protocols the server supports.
+    pub supported_key_exchange: Vec<KeyExchangeProtocol>,
+    /// Marked as recommended by the control plane.
+    pub recommended:            bool,
+    /// Per-transport listener ports; `port` is used for unlisted transports.
+    pub port_map:               HashMap<TransportMode, u16>,
+    /// Accepts the `ObfuscatedTls` transport.
+    pub supports_obfuscation:   bool,
+    /// Cipher this server performs best with, overriding the configured one.
+    pub preferred_encryption:   Option<EncryptionAlgorithm>,
+}
+
+impl VpnServer {
+    /// Check if the server has reached its connection cap.
+    ///
+    /// A server without a cap (`max_users == 0`) is never full.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.max_users > 0 && self.current_users >= self.max_users
+    }
+
+    /// Check if the server supports an encryption algorithm.
+    ///
+    /// An empty `supported_encryption` list is treated as unknown and
+    /// accepted.
+    #[must_use]
+    pub fn supports_encryption(&self, encryption: EncryptionAlgorithm) -> bool {
+        self.supported_encryption.is_empty() || self.supported_encryption.contains(&encryption)
+    }
+
+    /// Check if the server supports a key exchange protocol.
+    ///
+    /// An empty `supported_key_exchange` list is treated as unknown and
+    /// accepted.
+    #[must_use]
+    pub fn supports_key_exchange(&self, key_exchange: KeyExchangeProtocol) -> bool {
+        self.supported_key_exchange.is_empty()
+            || self.supported_key_exchange.contains(&key_exchange)
+    }
+
+    /// Check if the server can be reached over a transport.
+    ///
+    /// `ObfuscatedTls` needs `supports_obfuscation`; the other transports
+    /// are always available.
+    #[must_use]
+    pub fn supports_transport(&self, transport: &TransportMode) -> bool {
+        *transport != TransportMode::ObfuscatedTls || self.supports_obfuscation
+    }
+
+    /// Get the `(latitude, longitude)` location, if both are known.
+    #[must_use]
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    /// Get the listener port for a transport, if the server lists one.
+    #[must_use]
+    pub fn port_for(&self, transport: &TransportMode) -> Option<u16> {
+        self.port_map.get(transport).copied()
+    }
+
+    /// Get the port to use for a transport, falling back to `port`.
+    #[must_use]
+    pub fn effective_port(&self, transport: &TransportMode) -> u16 {
+        self.port_for(transport).unwrap_or(self.port)
+    }
+
+    /// Resolve the server endpoint, ordered and filtered by address family.
+    ///
+    /// Uses `ipv4_addr`/`ipv6_addr` when either is set and resolves
+    /// `hostname` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Network` if the hostname cannot be resolved or no
+    /// address of the required family is available.
+    pub fn socket_addrs(&self, preference: IpPreference) -> VpnResult<Vec<SocketAddr>> {
+        let pinned: Vec<IpAddr> = self
+            .ipv4_addr
+            .map(IpAddr::V4)
+            .into_iter()
+            .chain(self.ipv6_addr.map(IpAddr::V6))
+            .collect();
+        let mut addrs: Vec<SocketAddr> = if pinned.is_empty() {
+            (self.hostname.as_str(), self.port)
+                .to_socket_addrs()
+                .map_err(|e| VpnError::Network(format!("Cannot resolve {}: {e}", self.hostname)))?
+                .collect()
+        } else {
+            pinned.into_iter().map(|ip| SocketAddr::new(ip, self.port)).collect()
+        };
+
+        match preference {
+            IpPreference::PreferV4 => addrs.sort_by_key(SocketAddr::is_ipv6),
+            IpPreference::PreferV6 => addrs.sort_by_key(SocketAddr::is_ipv4),
+            IpPreference::V4Only => addrs.retain(SocketAddr::is_ipv4),
+            IpPreference::V6Only => addrs.retain(SocketAddr::is_ipv6),
+        }
+
+        if addrs.is_empty() {
+            let message = match preference {
+                IpPreference::V6Only => "no v6 address available",
+                IpPreference::V4Only => "no v4 address available",
+                IpPreference::PreferV4 | IpPreference::PreferV6 => "no address available",
+            };
+            return Err(VpnError::Network(message.to_string()));
+        }
+        Ok(addrs)
+    }
+
+    /// Pick the endpoint to dial: the first address of
+    /// [`socket_addrs`](Self::socket_addrs) under `preference`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Network` if no address of the required family is
+    /// available.
+    pub fn resolve_endpoint(&self, preference: IpPreference) -> VpnResult<SocketAddr> {
+        self.socket_addrs(preference).map(|addrs| addrs[0])
+    }
+
+    /// Check if the server carries the given tag.
+    #[must_use]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Comparable security score (higher is stronger).
+    ///
+    /// Sum of three weights:
+    ///
+    /// | Component               | Weight |
+    /// |-------------------------|--------|
+    /// | `pqc_enabled`           | 100    |
+    /// | `Aes256GcmPqc`          | 30     |
+    /// | `Aes256Gcm`/`ChaCha20`  | 10     |
+    /// | `HybridMlKem`           | 50     |
+    /// | `MlKem`                 | 40     |
+    /// | `X25519`                | 10     |
+    ///
+    /// Only the strongest supported encryption and key exchange count, so the
+    /// maximum score is 180.
+    #[must_use]
+    pub fn security_rank(&self) -> u8 {
+        let pqc = if self.pqc_enabled { 100 } else { 0 };
+        let encryption = self
+            .supported_encryption
+            .iter()
+            .map(|e| match e {
+                EncryptionAlgorithm::Aes256GcmPqc => 30,
+                EncryptionAlgorithm::Aes256Gcm | EncryptionAlgorithm::ChaCha20Poly1305 => 10,
+            })
+            .max()
+            .unwrap_or(0);
+        let key_exchange = self
+            .supported_key_exchange
+            .iter()
+            .map(|k| match k {
+                KeyExchangeProtocol::HybridMlKem => 50,
+                KeyExchangeProtocol::MlKem => 40,
+                KeyExchangeProtocol::X25519 => 10,
+            })
+            .max()
+            .unwrap_or(0);
+        pqc + encryption + key_exchange
+    }
+}
+
+/// Condensed server entry for picker lists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSummary {
+    /// Server identifier.
+    pub id:       String,
+    /// City.
+    pub city:     String,
+    /// Current load (0.0-1.0).
+    pub load:     f32,
+    /// Router selection score (lower is better).
+    pub score:    f32,
+    /// Whether the router would select this server.
+    pub eligible: bool,
 }
 
 /// VPN tunnel representation.
@@ -34,6 +243,30 @@ pub struct VpnTunnel {
     pub key_exchange: KeyExchangeProtocol,
     /// Connection statistics.
     pub stats:        ConnectionStats,
+    /// Role of the tunnel when several exist.
+    pub role:         TunnelRole,
+}
+
+impl VpnTunnel {
+    /// Get the security level of this tunnel's negotiated algorithms.
+    #[must_use]
+    pub fn security_level(&self) -> SecurityLevel {
+        SecurityLevel::of(self.encryption, self.key_exchange)
+    }
+}
+
+/// Role of a tunnel in multi-tunnel mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TunnelRole {
+    /// Carries traffic; status APIs report on this tunnel. In a multi-hop
+    /// chain this is the exit hop.
+    #[default]
+    Primary,
+    /// Warm standby ready for failover.
+    Backup,
+    /// Hop ahead of the exit in a multi-hop chain, starting at 0 for the
+    /// entry hop.
+    Hop(usize),
 }
 
 /// Tunnel state.
@@ -54,25 +287,523 @@ pub enum TunnelState {
     Disconnecting,
     /// Tunnel error.
     Error,
+    /// Key exchange done, kept ready for failover but not carrying traffic.
+    Standby,
+}
+
+impl TunnelState {
+    /// Convert state to string representation.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disconnected => "disconnected",
+            Self::Connecting => "connecting",
+            Self::KeyExchange => "key_exchange",
+            Self::Connected => "connected",
+            Self::Reconnecting => "reconnecting",
+            Self::Disconnecting => "disconnecting",
+            Self::Error => "error",
+            Self::Standby => "standby",
+        }
+    }
+
+    /// Check if the state machine allows moving from this state to `next`.
+    ///
+    /// A tunnel connects through `Connecting -> KeyExchange -> Connected`,
+    /// drops to `Reconnecting` and retries from `Connecting`, and closes
+    /// through `Disconnecting` or straight to `Disconnected`. Any state may
+    /// fail to `Error`, and staying in the same state is always allowed.
+    #[must_use]
+    pub fn can_transition_to(&self, next: TunnelState) -> bool {
+        use TunnelState::{
+            Connected, Connecting, Disconnected, Disconnecting, Error, KeyExchange, Reconnecting,
+            Standby,
+        };
+
+        if *self == next || next == Error {
+            return true;
+        }
+        match self {
+            Disconnected => next == Connecting,
+            Connecting => matches!(next, KeyExchange | Disconnecting | Disconnected),
+            KeyExchange => matches!(next, Connected | Disconnecting | Disconnected),
+            Connected => matches!(next, Reconnecting | Disconnecting | Disconnected),
+            Reconnecting | Error => {
+                matches!(
+                    next,
+                    Reconnecting | Connecting | Disconnecting | Disconnected
+                )
+            },
+            Disconnecting => next == Disconnected,
+            Standby => matches!(next, Connected | Disconnected),
+        }
+    }
+}
+
+impl fmt::Display for TunnelState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
+impl FromStr for TunnelState {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "disconnected" => Ok(Self::Disconnected),
+            "connecting" => Ok(Self::Connecting),
+            "key_exchange" => Ok(Self::KeyExchange),
+            "connected" => Ok(Self::Connected),
+            "reconnecting" => Ok(Self::Reconnecting),
+            "disconnecting" => Ok(Self::Disconnecting),
+            "error" => Ok(Self::Error),
+            "standby" => Ok(Self::Standby),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown tunnel state: {s}"
+            ))),
+        }
+    }
+}
+
+/// Handle to a standby tunnel created by
+/// [`VpnPlugin::warm_standby`](crate::VpnPlugin::warm_standby).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TunnelHandle {
+    id: u64,
+}
+
+impl TunnelHandle {
+    /// Create a handle for a tunnel id.
+    pub(crate) fn new(id: u64) -> Self {
+        Self { id }
+    }
+
+    /// Get the tunnel id.
+    #[must_use]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Number of latency samples kept by [`ConnectionStats::record_latency`].
+pub const LATENCY_WINDOW: usize = 32;
+
+/// [`ConnectionStats::quality_score`] before any latency has been measured.
+pub const NEUTRAL_QUALITY: u8 = 50;
+
 /// Connection statistics.
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionStats {
     /// Bytes sent.
-    pub bytes_sent:     u64,
+    pub bytes_sent:       u64,
     /// Bytes received.
-    pub bytes_received: u64,
+    pub bytes_received:   u64,
     /// Connection uptime (seconds).
-    pub uptime_secs:    u64,
+    pub uptime_secs:      u64,
     /// Current latency (ms).
-    pub latency_ms:     u32,
+    pub latency_ms:       u32,
     /// Packets lost (percentage).
-    pub packet_loss:    f32,
+    pub packet_loss:      f32,
+    /// Send rate at the last [`update`](Self::update) (bytes/s).
+    pub send_rate_bps:    f64,
+    /// Receive rate at the last [`update`](Self::update) (bytes/s).
+    pub recv_rate_bps:    f64,
+    /// Time of the last [`update`](Self::update) (seconds since the epoch).
+    pub last_update_secs: Option<u64>,
+    /// Recent latency samples (ms), oldest first; see
+    /// [`record_latency`](Self::record_latency).
+    pub latency_samples:  VecDeque<u32>,
+}
+
+impl ConnectionStats {
+    /// Record new cumulative byte counts and recompute the rates from the
+    /// change since the previous update.
+    ///
+    /// The first update reports zero rates. An update in the same second as
+    /// the previous one keeps the previous rates and does not reset the
+    /// interval, so its bytes count towards the next rate.
+    pub fn update(&mut self, new_sent: u64, new_received: u64, now_secs: u64) {
+        match self.last_update_secs {
+            None => {
+                self.send_rate_bps = 0.0;
+                self.recv_rate_bps = 0.0;
+                self.last_update_secs = Some(now_secs);
+            },
+            Some(last) if now_secs > last => {
+                let elapsed = (now_secs - last) as f64;
+                self.send_rate_bps = new_sent.saturating_sub(self.bytes_sent) as f64 / elapsed;
+                self.recv_rate_bps =
+                    new_received.saturating_sub(self.bytes_received) as f64 / elapsed;
+                self.last_update_secs = Some(now_secs);
+            },
+            Some(_) => return,
+        }
+        self.bytes_sent = new_sent;
+        self.bytes_received = new_received;
+    }
+
+    /// Record a latency sample and make it the current latency.
+    ///
+    /// Only the last [`LATENCY_WINDOW`] samples are kept; older ones are
+    /// dropped as new ones arrive.
+    pub fn record_latency(&mut self, ms: u32) {
+        if self.latency_samples.len() == LATENCY_WINDOW {
+            self.latency_samples.pop_front();
+        }
+        self.latency_samples.push_back(ms);
+        self.latency_ms = ms;
+    }
+
+    /// Mean of the recorded latency samples (ms), rounded.
+    ///
+    /// Falls back to [`latency_ms`](Self::latency_ms) when nothing has been
+    /// recorded.
+    #[must_use]
+    pub fn avg_latency(&self) -> u32 {
+        self.latency_mean().map_or(self.latency_ms, |mean| mean.round() as u32)
+    }
+
+    /// Jitter as the mean absolute deviation of the latency samples (ms),
+    /// rounded. Zero with fewer than two samples.
+    #[must_use]
+    pub fn jitter(&self) -> u32 {
+        let Some(mean) = self.latency_mean() else {
+            return 0;
+        };
+        let deviation: f64 =
+            self.latency_samples.iter().map(|&ms| (f64::from(ms) - mean).abs()).sum();
+        (deviation / self.latency_samples.len() as f64).round() as u32
+    }
+
+    /// Single 0-100 connection quality figure for display (higher is
+    /// better).
+    ///
+    /// Starts at 100 and subtracts capped penalties:
+    ///
+    /// | Input                  | Penalty                      | Cap |
+    /// |------------------------|------------------------------|-----|
+    /// | `avg_latency`          | 1 per 5 ms above 20 ms       | 40  |
+    /// | `packet_loss`          | 10 per percentage point      | 40  |
+    /// | `jitter`               | 1 per 2 ms                   | 20  |
+    ///
+    /// Returns [`NEUTRAL_QUALITY`] while no latency has been recorded, since
+    /// zeroed stats say nothing about the link.
+    #[must_use]
+    pub fn quality_score(&self) -> u8 {
+        if self.latency_samples.is_empty() && self.latency_ms == 0 {
+            return NEUTRAL_QUALITY;
+        }
+        let latency = (self.avg_latency().saturating_sub(20) / 5).min(40);
+        let loss = (self.packet_loss * 10.0).round().clamp(0.0, 40.0) as u32;
+        let jitter = (self.jitter() / 2).min(20);
+        (100 - latency - loss - jitter) as u8
+    }
+
+    /// Render the stats in the Prometheus text exposition format, labelled
+    /// with `tunnel_id`.
+    ///
+    /// Byte counts are counters; uptime, rates, average latency, jitter and
+    /// the packet loss ratio (`packet_loss / 100`) are gauges. Numbers use
+    /// Rust's locale-independent shortest representation, with `NaN`,
+    /// `+Inf` and `-Inf` spelled as Prometheus expects.
+    #[must_use]
+    pub fn to_prometheus(&self, tunnel_id: u64) -> String {
+        let metrics: [(&str, &str, &str, String); 8] = [
+            (
+                "vpn_bytes_sent_total",
+                "counter",
+                "Bytes sent through the tunnel.",
+                self.bytes_sent.to_string(),
+            ),
+            (
+                "vpn_bytes_received_total",
+                "counter",
+                "Bytes received through the tunnel.",
+                self.bytes_received.to_string(),
+            ),
+            (
+                "vpn_uptime_seconds",
+                "gauge",
+                "Seconds the tunnel has been up.",
+                self.uptime_secs.to_string(),
+            ),
+            (
+                "vpn_latency_ms",
+                "gauge",
+                "Rolling average latency in milliseconds.",
+                self.avg_latency().to_string(),
+            ),
+            (
+                "vpn_jitter_ms",
+                "gauge",
+                "Latency jitter in milliseconds.",
+                self.jitter().to_string(),
+            ),
+            (
+                "vpn_packet_loss_ratio",
+                "gauge",
+                "Fraction of packets lost.",
+                prometheus_float(self.packet_loss / 100.0),
+            ),
+            (
+                "vpn_send_rate_bytes_per_second",
+                "gauge",
+                "Send rate at the last update.",
+                prometheus_float(self.send_rate_bps),
+            ),
+            (
+                "vpn_receive_rate_bytes_per_second",
+                "gauge",
+                "Receive rate at the last update.",
+                prometheus_float(self.recv_rate_bps),
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            out.push_str(&format!(
+                "# HELP {name} {help}\n# TYPE {name} {kind}\n{name}{{tunnel_id=\"{tunnel_id}\"}} {value}\n"
+            ));
+        }
+        out
+    }
+
+    fn latency_mean(&self) -> Option<f64> {
+        if self.latency_samples.is_empty() {
+            return None;
+        }
+        let total: u64 = self.latency_samples.iter().map(|&ms| u64::from(ms)).sum();
+        Some(total as f64 / self.latency_samples.len() as f64)
+    }
+}
+
+/// Format a sample value for Prometheus.
+fn prometheus_float<T: Into<f64> + fmt::Display + Copy>(value: T) -> String {
+    let float: f64 = value.into();
+    if float.is_nan() {
+        "NaN".to_string()
+    } else if float.is_infinite() {
+        if float > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Summary of a VPN session, produced when the plugin shuts down.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionSummary {
+    /// Tunnel identifier, if a tunnel was active.
+    pub tunnel_id:      Option<u64>,
+    /// Server identifier, if a tunnel was active.
+    pub server_id:      Option<String>,
+    /// Tunnel state at shutdown.
+    pub final_state:    TunnelState,
+    /// Bytes sent during the session.
+    pub bytes_sent:     u64,
+    /// Bytes received during the session.
+    pub bytes_received: u64,
+    /// Session uptime (seconds).
+    pub uptime_secs:    u64,
+}
+
+/// Record of a single connection attempt.
+#[derive(Debug, Clone)]
+pub struct ConnectionAttempt {
+    /// Unix time of the attempt (seconds).
+    pub at_secs:   u64,
+    /// Server the attempt targeted.
+    pub server_id: String,
+    /// Error message if the attempt failed.
+    pub error:     Option<String>,
+}
+
+/// Record of a tunnel state change.
+#[derive(Debug, Clone, Copy)]
+pub struct StateTransition {
+    /// Unix time of the transition (seconds).
+    pub at_secs: u64,
+    /// Previous state.
+    pub from:    TunnelState,
+    /// New state.
+    pub to:      TunnelState,
+}
+
+/// Credentials used to authenticate with VPN servers.
+///
+/// The `Debug` output redacts the token.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    /// Account username.
+    pub username: String,
+    /// Authentication token.
+    pub token:    String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("token", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Traffic accumulated over one [`StatsHistory`](crate::StatsHistory)
+/// sample interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficSample {
+    /// When the sample was taken (milliseconds since the Unix epoch).
+    pub at_millis:       u64,
+    /// Length of the interval the sample covers (ms).
+    pub duration_millis: u64,
+    /// Bytes sent during the interval.
+    pub bytes_sent:      u64,
+    /// Bytes received during the interval.
+    pub bytes_received:  u64,
+}
+
+impl TrafficSample {
+    /// Throughput during the interval in bytes per second, or `None` for a
+    /// zero-length interval.
+    #[must_use]
+    pub fn rate_bps(&self, direction: Direction) -> Option<f64> {
+        let bytes = match direction {
+            Direction::Up => self.bytes_sent,
+            Direction::Down => self.bytes_received,
+        };
+        (self.duration_millis > 0).then(|| bytes as f64 * 1000.0 / self.duration_millis as f64)
+    }
+}
+
+/// Traffic direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Outgoing traffic.
+    Up,
+    /// Incoming traffic.
+    Down,
+}
+
+/// Result of a pre-connect reachability check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreflightReport {
+    /// Server that was checked.
+    pub server_id:      String,
+    /// Addresses the hostname resolved to, in preference order.
+    pub resolved_addrs: Vec<SocketAddr>,
+    /// Whether the probe reached the server.
+    pub reachable:      bool,
+    /// Probe round-trip time (ms), if reachable.
+    pub rtt_ms:         Option<u32>,
+    /// Why the server was unreachable.
+    pub error:          Option<String>,
+}
+
+/// Progress reported by a [`HandshakeStep`](crate::HandshakeStep).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeProgress {
+    /// Still negotiating; poll again.
+    Pending,
+    /// Handshake finished; the tunnel may be marked connected.
+    Complete,
+}
+
+/// Outcome of a post-connect verification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationResult {
+    /// Egress IP observed by the verifier, if any.
+    pub egress_ip: Option<IpAddr>,
+    /// Whether traffic was confirmed to leave through the tunnel.
+    pub routed:    bool,
+}
+
+/// Reason a server is not eligible for connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExclusionReason {
+    /// No post-quantum key exchange.
+    NonPqc,
+    /// Blocklisted on the router.
+    Blocklisted,
+    /// Latest health probe found the server in maintenance.
+    Maintenance,
+    /// Latest health probe found the server unreachable.
+    Unreachable,
+    /// Country not in `allowed_countries`, or excluded by the router.
+    CountryRestricted,
+    /// Configured encryption not supported.
+    UnsupportedEncryption,
+    /// Configured key exchange not supported.
+    UnsupportedKeyExchange,
+    /// Configured transport not supported.
+    UnsupportedTransport,
+    /// Server is at its connection cap.
+    Overloaded,
+    /// Server is cooling down on the router.
+    Cooldown,
+}
+
+impl ExclusionReason {
+    /// Convert reason to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NonPqc => "non_pqc",
+            Self::Blocklisted => "blocklisted",
+            Self::Maintenance => "maintenance",
+            Self::Unreachable => "unreachable",
+            Self::CountryRestricted => "country_restricted",
+            Self::UnsupportedEncryption => "unsupported_encryption",
+            Self::UnsupportedKeyExchange => "unsupported_key_exchange",
+            Self::UnsupportedTransport => "unsupported_transport",
+            Self::Overloaded => "overloaded",
+            Self::Cooldown => "cooldown",
+        }
+    }
+}
+
+/// Summary of why connecting may be impossible, for support checklists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityDiagnosis {
+    /// Servers in the pool.
+    pub server_count:   usize,
+    /// Servers [`connect_optimal`](crate::VpnPlugin::connect_optimal) may
+    /// pick.
+    pub eligible_count: usize,
+    /// Ineligible servers by id, with the first check each failed.
+    pub excluded:       Vec<(String, ExclusionReason)>,
+    /// Reconnect circuit state; `Open` means reconnects are cooling down.
+    pub circuit:        CircuitState,
+    /// Whether the plugin has been shut down.
+    pub shut_down:      bool,
+    /// Whether the device has network connectivity.
+    pub network_up:     bool,
+    /// Whether a captive portal is intercepting traffic.
+    pub captive_portal: bool,
+}
+
+impl ConnectivityDiagnosis {
+    /// Count the servers excluded for `reason`.
+    #[must_use]
+    pub fn excluded_for(&self, reason: ExclusionReason) -> usize {
+        self.excluded.iter().filter(|(_, r)| *r == reason).count()
+    }
+}
+
+/// Reconnect circuit breaker state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Reconnects allowed.
+    #[default]
+    Closed,
+    /// Too many recent reconnects; reconnects suspended for the cooldown.
+    Open,
+    /// Cooldown elapsed; a single trial reconnect is allowed.
+    HalfOpen,
 }
 
 /// Encryption algorithm.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum EncryptionAlgorithm {
     /// AES-256-GCM.
     #[default]
@@ -83,8 +814,41 @@ pub enum EncryptionAlgorithm {
     Aes256GcmPqc,
 }
 
+impl EncryptionAlgorithm {
+    /// Convert algorithm to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aes256Gcm => "aes256_gcm",
+            Self::ChaCha20Poly1305 => "chacha20_poly1305",
+            Self::Aes256GcmPqc => "aes256_gcm_pqc",
+        }
+    }
+
+    /// Check if the algorithm is the post-quantum hybrid construction.
+    #[must_use]
+    pub fn is_post_quantum(&self) -> bool {
+        matches!(self, Self::Aes256GcmPqc)
+    }
+}
+
+impl FromStr for EncryptionAlgorithm {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "aes256_gcm" => Ok(Self::Aes256Gcm),
+            "chacha20_poly1305" => Ok(Self::ChaCha20Poly1305),
+            "aes256_gcm_pqc" => Ok(Self::Aes256GcmPqc),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown encryption algorithm: {s}"
+            ))),
+        }
+    }
+}
+
 /// Key exchange protocol.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum KeyExchangeProtocol {
     /// X25519.
     X25519,
@@ -94,3 +858,645 @@ pub enum KeyExchangeProtocol {
     /// Hybrid X25519 + ML-KEM.
     HybridMlKem,
 }
+
+impl KeyExchangeProtocol {
+    /// Convert protocol to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::X25519 => "x25519",
+            Self::MlKem => "ml_kem",
+            Self::HybridMlKem => "hybrid_ml_kem",
+        }
+    }
+
+    /// Check if the protocol is post-quantum or hybrid.
+    #[must_use]
+    pub fn is_post_quantum(&self) -> bool {
+        matches!(self, Self::MlKem | Self::HybridMlKem)
+    }
+}
+
+impl FromStr for KeyExchangeProtocol {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x25519" => Ok(Self::X25519),
+            "ml_kem" => Ok(Self::MlKem),
+            "hybrid_ml_kem" => Ok(Self::HybridMlKem),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown key exchange protocol: {s}"
+            ))),
+        }
+    }
+}
+
+/// ML-KEM parameter set (FIPS 203).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MlKemVariant {
+    /// ML-KEM-512 (NIST category 1).
+    MlKem512,
+    /// ML-KEM-768 (NIST category 3).
+    #[default]
+    MlKem768,
+    /// ML-KEM-1024 (NIST category 5).
+    MlKem1024,
+}
+
+impl MlKemVariant {
+    /// Convert variant to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::MlKem512 => "ml_kem_512",
+            Self::MlKem768 => "ml_kem_768",
+            Self::MlKem1024 => "ml_kem_1024",
+        }
+    }
+
+    /// Get the encapsulation (public) key size in bytes.
+    #[must_use]
+    pub fn public_key_len(&self) -> usize {
+        match self {
+            Self::MlKem512 => 800,
+            Self::MlKem768 => 1184,
+            Self::MlKem1024 => 1568,
+        }
+    }
+
+    /// Get the ciphertext size in bytes.
+    #[must_use]
+    pub fn ciphertext_len(&self) -> usize {
+        match self {
+            Self::MlKem512 => 768,
+            Self::MlKem768 => 1088,
+            Self::MlKem1024 => 1568,
+        }
+    }
+}
+
+/// Why a tunnel was torn down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisconnectReason {
+    /// The user asked to disconnect.
+    UserRequested,
+    /// The network path to the server failed.
+    NetworkLost,
+    /// The server rejected the session's credentials.
+    AuthFailed,
+    /// The server ended the session.
+    ServerClosed,
+    /// The kill switch tore the tunnel down to stop a leak.
+    KillSwitch,
+}
+
+impl DisconnectReason {
+    /// Get the reason to record for a tunnel torn down by `error`.
+    ///
+    /// Authentication errors map to `AuthFailed`, anything else to
+    /// `NetworkLost`.
+    #[must_use]
+    pub fn for_error(error: &VpnError) -> Self {
+        match error {
+            VpnError::Authentication(_) => Self::AuthFailed,
+            _ => Self::NetworkLost,
+        }
+    }
+
+    /// Convert reason to its string identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UserRequested => "user_requested",
+            Self::NetworkLost => "network_lost",
+            Self::AuthFailed => "auth_failed",
+            Self::ServerClosed => "server_closed",
+            Self::KillSwitch => "kill_switch",
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Connection lifecycle event delivered to the plugin's event listener.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VpnEvent {
+    /// Started connecting to a server.
+    Connecting,
+    /// Tunnel is up and carrying traffic.
+    Connected,
+    /// Tunnel was torn down.
+    Disconnected(DisconnectReason),
+    /// Connection dropped and a reconnect is under way.
+    Reconnecting,
+    /// Packet loss on the active tunnel rose above `packet_loss_threshold`.
+    PacketLossHigh,
+    /// A connection error occurred.
+    Error(String),
+}
+
+impl VpnEvent {
+    /// Get the event announcing a move to `state`, if the state has one.
+    ///
+    /// `Disconnected` carries the reason for the teardown, which the state
+    /// alone does not tell, so it is not derived here.
+    #[must_use]
+    pub fn for_state(state: TunnelState) -> Option<Self> {
+        match state {
+            TunnelState::Connecting => Some(Self::Connecting),
+            TunnelState::Connected => Some(Self::Connected),
+            TunnelState::Reconnecting => Some(Self::Reconnecting),
+            _ => None,
+        }
+    }
+}
+
+/// Result of the latest health probe of a server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ServerHealth {
+    /// Responding normally.
+    #[default]
+    Healthy,
+    /// Responding, but slowly or with errors.
+    Degraded,
+    /// Not responding.
+    Unreachable,
+    /// Taken out of service by the operator.
+    Maintenance,
+}
+
+/// Security level of a tunnel, ordered weakest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SecurityLevel {
+    /// Classical cryptography only.
+    Classical,
+    /// Post-quantum key exchange and encryption.
+    PostQuantum,
+    /// Post-quantum combined with classical key exchange.
+    Hybrid,
+}
+
+impl SecurityLevel {
+    /// Get the level provided by an encryption and key exchange pair.
+    ///
+    /// Anything short of post-quantum on both halves is `Classical`, matching
+    /// [`VpnConfig::is_quantum_safe`](crate::VpnConfig::is_quantum_safe).
+    #[must_use]
+    pub fn of(encryption: EncryptionAlgorithm, key_exchange: KeyExchangeProtocol) -> Self {
+        match key_exchange {
+            _ if !encryption.is_post_quantum() => Self::Classical,
+            KeyExchangeProtocol::X25519 => Self::Classical,
+            KeyExchangeProtocol::MlKem => Self::PostQuantum,
+            KeyExchangeProtocol::HybridMlKem => Self::Hybrid,
+        }
+    }
+
+    /// Convert level to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Classical => "classical",
+            Self::PostQuantum => "post_quantum",
+            Self::Hybrid => "hybrid",
+        }
+    }
+}
+
+/// Side of a key exchange handshake.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Client side: encapsulates against the peer's public key.
+    #[default]
+    Initiator,
+    /// Server side: decapsulates the initiator's ciphertext.
+    Responder,
+}
+
+/// How tunnel traffic is carried over the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TransportMode {
+    /// Plain VPN transport.
+    #[default]
+    Standard,
+    /// Wrapped in TLS so traffic looks like HTTPS.
+    ObfuscatedTls,
+    /// Carried over WebSocket.
+    Websocket,
+}
+
+impl TransportMode {
+    /// Convert transport to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::ObfuscatedTls => "obfuscated_tls",
+            Self::Websocket => "websocket",
+        }
+    }
+}
+
+impl FromStr for TransportMode {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(Self::Standard),
+            "obfuscated_tls" => Ok(Self::ObfuscatedTls),
+            "websocket" => Ok(Self::Websocket),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown transport mode: {s}"
+            ))),
+        }
+    }
+}
+
+/// Address family preference on dual-stack networks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    /// Use IPv4 first, fall back to IPv6.
+    #[default]
+    PreferV4,
+    /// Use IPv6 first, fall back to IPv4.
+    PreferV6,
+    /// Use IPv4 only.
+    V4Only,
+    /// Use IPv6 only.
+    V6Only,
+}
+
+impl IpPreference {
+    /// Convert preference to its snake_case identifier.
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreferV4 => "prefer_v4",
+            Self::PreferV6 => "prefer_v6",
+            Self::V4Only => "v4_only",
+            Self::V6Only => "v6_only",
+        }
+    }
+}
+
+impl FromStr for IpPreference {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prefer_v4" => Ok(Self::PreferV4),
+            "prefer_v6" => Ok(Self::PreferV6),
+            "v4_only" => Ok(Self::V4Only),
+            "v6_only" => Ok(Self::V6Only),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown IP preference: {s}"
+            ))),
+        }
+    }
+}
+
+/// Intended use of a connection, used for intent-based server selection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UseCase {
+    /// Video and audio streaming.
+    Streaming,
+    /// Peer-to-peer file sharing.
+    P2P,
+    /// Latency-sensitive traffic such as gaming or calls.
+    LowLatency,
+    /// No particular requirement.
+    #[default]
+    General,
+}
+
+impl UseCase {
+    /// Server tag required for this use case, if any.
+    #[must_use]
+    pub fn required_tag(&self) -> Option<&'static str> {
+        match self {
+            Self::Streaming => Some("streaming"),
+            Self::P2P => Some("p2p"),
+            Self::LowLatency => Some("low_latency"),
+            Self::General => None,
+        }
+    }
+}
+
+/// Order of a server list built by
+/// [`NeuralRouter::servers_sorted`](crate::NeuralRouter::servers_sorted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// Least loaded first.
+    Load,
+    /// Lowest measured latency first; unmeasured servers last.
+    Latency,
+    /// Country code, alphabetical ignoring case.
+    Country,
+    /// Most free user slots first; uncapped servers lead.
+    Capacity,
+}
+
+/// Server region offered by the region picker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ServerRegion {
+    /// Resolve the region automatically from location and load.
+    #[default]
+    Auto,
+    /// Eastern United States.
+    UsEast,
+    /// Western United States.
+    UsWest,
+    /// Western Europe.
+    EuWest,
+    /// Central Europe.
+    EuCentral,
+    /// Asia-Pacific.
+    AsiaPacific,
+}
+
+impl ServerRegion {
+    /// Every region, `Auto` first, in picker order.
+    pub const ALL: [Self; 6] = [
+        Self::Auto,
+        Self::UsEast,
+        Self::UsWest,
+        Self::EuWest,
+        Self::EuCentral,
+        Self::AsiaPacific,
+    ];
+
+    /// All concrete (non-`Auto`) regions.
+    pub const CONCRETE: [Self; 5] = [
+        Self::UsEast,
+        Self::UsWest,
+        Self::EuWest,
+        Self::EuCentral,
+        Self::AsiaPacific,
+    ];
+
+    /// Convert region to its string identifier.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::UsEast => "us-east",
+            Self::UsWest => "us-west",
+            Self::EuWest => "eu-west",
+            Self::EuCentral => "eu-central",
+            Self::AsiaPacific => "asia-pacific",
+        }
+    }
+
+    /// Approximate geographic center `(latitude, longitude)` of the region.
+    ///
+    /// Returns `None` for `Auto`.
+    #[must_use]
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        match self {
+            Self::Auto => None,
+            Self::UsEast => Some((39.0, -77.5)),
+            Self::UsWest => Some((37.4, -122.0)),
+            Self::EuWest => Some((53.3, -6.3)),
+            Self::EuCentral => Some((50.1, 8.7)),
+            Self::AsiaPacific => Some((1.35, 103.8)),
+        }
+    }
+}
+
+impl fmt::Display for ServerRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ServerRegion {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "us-east" => Ok(Self::UsEast),
+            "us-west" => Ok(Self::UsWest),
+            "eu-west" => Ok(Self::EuWest),
+            "eu-central" => Ok(Self::EuCentral),
+            "asia-pacific" => Ok(Self::AsiaPacific),
+            _ => Err(VpnError::Configuration(format!(
+                "Unknown server region: {s}"
+            ))),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    fn server(hostname: &str) -> VpnServer {
+        VpnServer {
+            hostname: hostname.to_string(),
+            port: 443,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_socket_addrs_preference() {
+        let v4 = server("192.0.2.10");
+        assert!(v4.socket_addrs(IpPreference::PreferV6).is_ok());
+        assert_eq!(
+            v4.socket_addrs(IpPreference::V4Only).map(|a| a.len()).ok(),
+            Some(1)
+        );
+        assert!(matches!(
+            v4.socket_addrs(IpPreference::V6Only),
+            Err(VpnError::Network(msg)) if msg == "no v6 address available"
+        ));
+
+        let v6 = server("2001:db8::10");
+        assert!(v6.socket_addrs(IpPreference::V6Only).is_ok_and(|a| a[0].is_ipv6()));
+        assert!(v6.socket_addrs(IpPreference::V4Only).is_err());
+    }
+
+    #[test]
+    fn test_stats_update_rates() {
+        let mut stats = ConnectionStats::default();
+        stats.update(1_000, 5_000, 100);
+        assert_eq!((stats.send_rate_bps, stats.recv_rate_bps), (0.0, 0.0));
+
+        stats.update(3_000, 25_000, 104);
+        assert_eq!((stats.send_rate_bps, stats.recv_rate_bps), (500.0, 5_000.0));
+        assert_eq!(stats.bytes_received, 25_000);
+
+        stats.update(9_000, 25_000, 104);
+        assert_eq!(stats.send_rate_bps, 500.0);
+        stats.update(9_000, 25_000, 106);
+        assert_eq!(stats.send_rate_bps, 3_000.0);
+    }
+
+    #[test]
+    fn test_stats_latency_window() {
+        let mut stats = ConnectionStats { latency_ms: 42, ..ConnectionStats::default() };
+        assert_eq!((stats.avg_latency(), stats.jitter()), (42, 0));
+
+        for ms in [10, 20, 30, 40] {
+            stats.record_latency(ms);
+        }
+        assert_eq!(stats.latency_ms, 40);
+        assert_eq!((stats.avg_latency(), stats.jitter()), (25, 10));
+
+        // Fill the window with a steady 50 ms; the early samples wrap out.
+        for _ in 0..LATENCY_WINDOW - 2 {
+            stats.record_latency(50);
+        }
+        assert_eq!((stats.avg_latency(), stats.jitter()), (49, 2));
+        for _ in 0..2 {
+            stats.record_latency(50);
+        }
+        assert_eq!((stats.avg_latency(), stats.jitter()), (50, 0));
+    }
+
+    #[test]
+    fn test_stats_to_prometheus() {
+        let mut stats = ConnectionStats {
+            bytes_sent: 1_500,
+            bytes_received: 9_000,
+            uptime_secs: 60,
+            packet_loss: 2.5,
+            send_rate_bps: 1_234.5,
+            recv_rate_bps: f64::INFINITY,
+            ..ConnectionStats::default()
+        };
+        stats.record_latency(40);
+        stats.record_latency(44);
+
+        assert_eq!(
+            stats.to_prometheus(7),
+            "# HELP vpn_bytes_sent_total Bytes sent through the tunnel.
+# TYPE vpn_bytes_sent_total counter
+vpn_bytes_sent_total{tunnel_id=\"7\"} 1500
+# HELP vpn_bytes_received_total Bytes received through the tunnel.
+# TYPE vpn_bytes_received_total counter
+vpn_bytes_received_total{tunnel_id=\"7\"} 9000
+# HELP vpn_uptime_seconds Seconds the tunnel has been up.
+# TYPE vpn_uptime_seconds gauge
+vpn_uptime_seconds{tunnel_id=\"7\"} 60
+# HELP vpn_latency_ms Rolling average latency in milliseconds.
+# TYPE vpn_latency_ms gauge
+vpn_latency_ms{tunnel_id=\"7\"} 42
+# HELP vpn_jitter_ms Latency jitter in milliseconds.
+# TYPE vpn_jitter_ms gauge
+vpn_jitter_ms{tunnel_id=\"7\"} 2
+# HELP vpn_packet_loss_ratio Fraction of packets lost.
+# TYPE vpn_packet_loss_ratio gauge
+vpn_packet_loss_ratio{tunnel_id=\"7\"} 0.025
+# HELP vpn_send_rate_bytes_per_second Send rate at the last update.
+# TYPE vpn_send_rate_bytes_per_second gauge
+vpn_send_rate_bytes_per_second{tunnel_id=\"7\"} 1234.5
+# HELP vpn_receive_rate_bytes_per_second Receive rate at the last update.
+# TYPE vpn_receive_rate_bytes_per_second gauge
+vpn_receive_rate_bytes_per_second{tunnel_id=\"7\"} +Inf
+"
+        );
+    }
+
+    #[test]
+    fn test_resolve_endpoint_dual_stack() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 10);
+        let v6 = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x10);
+        let pinned = |ipv4_addr, ipv6_addr| VpnServer {
+            hostname: String::from("unresolvable.invalid"),
+            port: 51820,
+            ipv4_addr,
+            ipv6_addr,
+            ..Default::default()
+        };
+        let endpoint = |server: &VpnServer, preference| {
+            server.resolve_endpoint(preference).map(|a| a.ip()).map_err(|e| e.to_string())
+        };
+        let none_v4 = Err(String::from("Network error: no v4 address available"));
+        let none_v6 = Err(String::from("Network error: no v6 address available"));
+
+        let dual = pinned(Some(v4), Some(v6));
+        assert_eq!(endpoint(&dual, IpPreference::PreferV4), Ok(IpAddr::V4(v4)));
+        assert_eq!(endpoint(&dual, IpPreference::PreferV6), Ok(IpAddr::V6(v6)));
+        assert_eq!(endpoint(&dual, IpPreference::V4Only), Ok(IpAddr::V4(v4)));
+        assert_eq!(endpoint(&dual, IpPreference::V6Only), Ok(IpAddr::V6(v6)));
+        assert_eq!(
+            dual.resolve_endpoint(IpPreference::V6Only).expect("Should resolve").port(),
+            51820
+        );
+
+        let v4_only = pinned(Some(v4), None);
+        assert_eq!(
+            endpoint(&v4_only, IpPreference::PreferV6),
+            Ok(IpAddr::V4(v4))
+        );
+        assert_eq!(endpoint(&v4_only, IpPreference::V6Only), none_v6);
+
+        let v6_only = pinned(None, Some(v6));
+        assert_eq!(
+            endpoint(&v6_only, IpPreference::PreferV4),
+            Ok(IpAddr::V6(v6))
+        );
+        assert_eq!(endpoint(&v6_only, IpPreference::V4Only), none_v4);
+
+        // Without pinned addresses the hostname is resolved
+        let literal = server("2001:db8::10");
+        assert_eq!(
+            endpoint(&literal, IpPreference::PreferV4),
+            Ok(IpAddr::V6(v6))
+        );
+    }
+
+    #[test]
+    fn test_quality_score() {
+        assert_eq!(ConnectionStats::default().quality_score(), NEUTRAL_QUALITY);
+
+        let mut pristine = ConnectionStats::default();
+        for ms in [12, 15, 14, 13] {
+            pristine.record_latency(ms);
+        }
+        assert_eq!(pristine.quality_score(), 100);
+
+        let mut degraded = ConnectionStats { packet_loss: 3.0, ..ConnectionStats::default() };
+        for ms in [200, 260, 230, 230] {
+            degraded.record_latency(ms);
+        }
+        // 40 (latency, capped) + 30 (loss) + 15 ms jitter / 2
+        assert_eq!(degraded.quality_score(), 23);
+
+        degraded.packet_loss = 100.0;
+        assert_eq!(degraded.quality_score(), 13);
+        degraded.record_latency(2_000);
+        assert_eq!(degraded.quality_score(), 0);
+    }
+
+    #[test]
+    fn test_tunnel_state_strings() {
+        let table = [
+            (TunnelState::Disconnected, "disconnected"),
+            (TunnelState::Connecting, "connecting"),
+            (TunnelState::KeyExchange, "key_exchange"),
+            (TunnelState::Connected, "connected"),
+            (TunnelState::Reconnecting, "reconnecting"),
+            (TunnelState::Disconnecting, "disconnecting"),
+            (TunnelState::Error, "error"),
+            (TunnelState::Standby, "standby"),
+        ];
+        for (state, name) in table {
+            assert_eq!(state.as_str(), name);
+            assert_eq!(state.to_string(), name);
+            assert_eq!(name.parse::<TunnelState>(), Ok(state));
+        }
+        assert_eq!(
+            "Connected".parse::<TunnelState>(),
+            Err(VpnError::Configuration(
+                "Unknown tunnel state: Connected".to_string()
+            ))
+        );
+    }
+}
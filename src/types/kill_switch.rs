@@ -0,0 +1,65 @@
+//! Kill-switch exemption types.
+
+use crate::{errors::VpnError, types::Cidr};
+
+/// Traffic allowed to bypass the kill switch while it is engaged.
+///
+/// Used for things like a captive-portal browser or a VoIP app the user
+/// explicitly wants working during a VPN drop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KillSwitchExemptions {
+    /// Destination ranges reachable outside the tunnel.
+    pub cidrs:     Vec<Cidr>,
+    /// Process names allowed to send outside the tunnel.
+    pub processes: Vec<String>,
+}
+
+impl KillSwitchExemptions {
+    /// Check if nothing is exempt.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cidrs.is_empty() && self.processes.is_empty()
+    }
+
+    /// Check that the exemptions leave the kill switch meaningful.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if a range covers every address
+    /// (`/0`), which would disable the kill switch, or a process name is
+    /// blank.
+    pub fn validate(&self) -> Result<(), VpnError> {
+        if let Some(cidr) = self.cidrs.iter().find(|c| c.prefix() == 0) {
+            return Err(VpnError::Configuration(format!(
+                "Kill switch exemption {cidr} covers all traffic"
+            )));
+        }
+        if self.processes.iter().any(|p| p.trim().is_empty()) {
+            return Err(VpnError::Configuration(
+                "Kill switch exemption has a blank process name".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_exemptions() {
+        let mut exemptions = KillSwitchExemptions {
+            cidrs:     vec!["192.168.0.0/16".parse().expect("Should parse")],
+            processes: vec!["voip".into()],
+        };
+        assert!(exemptions.validate().is_ok());
+
+        exemptions.cidrs.push("::/0".parse().expect("Should parse"));
+        assert!(exemptions.validate().is_err());
+
+        exemptions.cidrs.pop();
+        exemptions.processes.push("  ".into());
+        assert!(exemptions.validate().is_err());
+    }
+}
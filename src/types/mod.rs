@@ -4,9 +4,24 @@
 //! - VPN server and tunnel types
 //! - Connection state and statistics
 //! - Encryption and key exchange protocols
+//! - Split-tunnel rules
+//! - Kill-switch exemptions
+//! - Zeroize-on-drop secrets
 
 mod core;
+mod kill_switch;
+mod split_tunnel;
+mod zeroizing;
 
 pub use core::{
-    ConnectionStats, EncryptionAlgorithm, KeyExchangeProtocol, TunnelState, VpnServer, VpnTunnel,
+    CircuitState, ConnectionAttempt, ConnectionStats, ConnectivityDiagnosis, Credentials,
+    Direction, DisconnectReason, EncryptionAlgorithm, ExclusionReason, HandshakeProgress,
+    IpPreference, KeyExchangeProtocol, LATENCY_WINDOW, MlKemVariant, NEUTRAL_QUALITY,
+    PreflightReport, Role, SecurityLevel, ServerHealth, ServerRegion, ServerSummary,
+    SessionSummary, SortKey, StateTransition, TrafficSample, TransportMode, TunnelHandle,
+    TunnelRole, TunnelState, UseCase, VerificationResult, VpnEvent, VpnServer, VpnTunnel,
 };
+
+pub use kill_switch::KillSwitchExemptions;
+pub use split_tunnel::{Cidr, RuleConflict, SplitTunnelMode, SplitTunnelRule, SplitTunnelRules};
+pub use zeroizing::Zeroizing;
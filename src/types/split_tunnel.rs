@@ -0,0 +1,269 @@
+//! Split-tunnel rule types.
+
+use core::{fmt, str::FromStr};
+use std::net::IpAddr;
+
+use crate::errors::VpnError;
+
+/// IPv4 or IPv6 address range in CIDR notation, e.g. `10.0.0.0/8`.
+///
+/// A bare address parses as a single-host range (`/32` or `/128`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    addr:   IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Create a range from an address and prefix length.
+    ///
+    /// # Errors
+    ///
+    /// Returns `VpnError::Configuration` if `prefix` is longer than the
+    /// address family allows.
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, VpnError> {
+        if prefix > max_prefix(addr) {
+            return Err(VpnError::Configuration(format!(
+                "Invalid CIDR prefix /{prefix} for {addr}"
+            )));
+        }
+        Ok(Self { addr, prefix })
+    }
+
+    /// Get the base address.
+    #[must_use]
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Get the prefix length.
+    #[must_use]
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Check if the range contains an address.
+    #[must_use]
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        same_family(self.addr, addr)
+            && network_bits(self.addr, self.prefix) == network_bits(addr, self.prefix)
+    }
+
+    /// Check if two ranges share at least one address.
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        let prefix = self.prefix.min(other.prefix);
+        same_family(self.addr, other.addr)
+            && network_bits(self.addr, prefix) == network_bits(other.addr, prefix)
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = VpnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || VpnError::Configuration(format!("Invalid CIDR: {s}"));
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let prefix = match prefix {
+            Some(p) => p.trim().parse().map_err(|_| invalid())?,
+            None => max_prefix(addr),
+        };
+        Self::new(addr, prefix)
+    }
+}
+
+/// Entry in a split-tunnel rule list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SplitTunnelRule {
+    /// Application identifier (bundle id or executable name).
+    App(String),
+    /// Destination address range.
+    Cidr(Cidr),
+}
+
+impl SplitTunnelRule {
+    /// Check if two rules can match the same traffic.
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::App(a), Self::App(b)) => a.eq_ignore_ascii_case(b),
+            (Self::Cidr(a), Self::Cidr(b)) => a.overlaps(b),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for SplitTunnelRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::App(app) => write!(f, "app:{app}"),
+            Self::Cidr(cidr) => write!(f, "{cidr}"),
+        }
+    }
+}
+
+impl FromStr for SplitTunnelRule {
+    type Err = VpnError;
+
+    /// Parse the [`Display`](fmt::Display) form: `app:<id>` or a CIDR.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("app:") {
+            Some(app) => Ok(Self::App(app.to_string())),
+            None => s.parse().map(Self::Cidr),
+        }
+    }
+}
+
+/// Which list of a [`SplitTunnelRules`] a rule belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SplitTunnelMode {
+    /// Only matching traffic goes through the tunnel.
+    #[default]
+    Include,
+    /// Matching traffic bypasses the tunnel.
+    Exclude,
+}
+
+/// Traffic forced through the tunnel (`include`) or around it (`exclude`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitTunnelRules {
+    /// Rules whose traffic goes through the tunnel.
+    pub include: Vec<SplitTunnelRule>,
+    /// Rules whose traffic bypasses the tunnel.
+    pub exclude: Vec<SplitTunnelRule>,
+}
+
+impl SplitTunnelRules {
+    /// Get the rules for `mode`.
+    #[must_use]
+    pub fn rules(&self, mode: SplitTunnelMode) -> &[SplitTunnelRule] {
+        match mode {
+            SplitTunnelMode::Include => &self.include,
+            SplitTunnelMode::Exclude => &self.exclude,
+        }
+    }
+
+    /// Get the rules for `mode` mutably.
+    pub fn rules_mut(&mut self, mode: SplitTunnelMode) -> &mut Vec<SplitTunnelRule> {
+        match mode {
+            SplitTunnelMode::Include => &mut self.include,
+            SplitTunnelMode::Exclude => &mut self.exclude,
+        }
+    }
+
+    /// Check that no traffic is both included and excluded.
+    ///
+    /// # Errors
+    ///
+    /// Returns every include/exclude pair that overlaps, such as a CIDR
+    /// excluded from a range that is included, or the same app in both
+    /// lists.
+    pub fn validate(&self) -> Result<(), Vec<RuleConflict>> {
+        let mut conflicts = Vec::new();
+        for included in &self.include {
+            for excluded in self.exclude.iter().filter(|e| included.overlaps(e)) {
+                conflicts
+                    .push(RuleConflict { included: included.clone(), excluded: excluded.clone() });
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(conflicts)
+        }
+    }
+}
+
+/// Pair of split-tunnel rules that match the same traffic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleConflict {
+    /// Rule from the include list.
+    pub included: SplitTunnelRule,
+    /// Rule from the exclude list.
+    pub excluded: SplitTunnelRule,
+}
+
+impl fmt::Display for RuleConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is both included and excluded by {}",
+            self.included, self.excluded
+        )
+    }
+}
+
+/// Longest prefix for the address family.
+fn max_prefix(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn same_family(a: IpAddr, b: IpAddr) -> bool {
+    a.is_ipv4() == b.is_ipv4()
+}
+
+/// Leading `prefix` bits of the address, left-aligned in a `u128`.
+fn network_bits(addr: IpAddr, prefix: u8) -> u128 {
+    let bits = match addr {
+        IpAddr::V4(v4) => u128::from(u32::from(v4)) << 96,
+        IpAddr::V6(v6) => u128::from(v6),
+    };
+    match prefix {
+        0 => 0,
+        p => bits & (u128::MAX << (128 - u32::from(p))),
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> SplitTunnelRule {
+        SplitTunnelRule::Cidr(s.parse().expect("Should parse"))
+    }
+
+    #[test]
+    fn test_cidr_parse_and_overlap() {
+        let lan: Cidr = "10.0.0.0/8".parse().expect("Should parse");
+        assert!(lan.contains("10.1.2.3".parse().expect("Should parse")));
+        assert!(lan.overlaps(&"10.1.0.0/16".parse().expect("Should parse")));
+        assert!(!lan.overlaps(&"192.168.0.0/16".parse().expect("Should parse")));
+        assert_eq!(
+            "fd00::1".parse::<Cidr>().expect("Should parse").prefix(),
+            128
+        );
+
+        assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+        assert!("not-an-ip/8".parse::<Cidr>().is_err());
+    }
+
+    #[test]
+    fn test_rule_conflicts() {
+        let mut rules = SplitTunnelRules {
+            include: vec![cidr("10.0.0.0/8"), SplitTunnelRule::App("browser".into())],
+            exclude: vec![cidr("192.168.0.0/16")],
+        };
+        assert!(rules.validate().is_ok());
+
+        rules.exclude.push(cidr("10.1.0.0/16"));
+        rules.exclude.push(SplitTunnelRule::App("Browser".into()));
+        let conflicts = rules.validate().expect_err("Should conflict");
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].included, cidr("10.0.0.0/8"));
+        assert_eq!(conflicts[0].excluded, cidr("10.1.0.0/16"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Wrapper that scrubs secrets when dropped.
+
+use core::{
+    fmt,
+    mem::MaybeUninit,
+    ops::{Deref, DerefMut},
+};
+use std::{
+    hint,
+    sync::atomic::{self, Ordering},
+};
+
+use crate::traits::Zeroize;
+
+/// Value that is [zeroized](Zeroize) when dropped.
+///
+/// Derefs to the inner value. `Debug` never prints the contents, so a
+/// wrapped secret is safe to log by accident. There is no `PartialEq`;
+/// compare secrets with [`ct_eq`](Self::ct_eq).
+#[derive(Clone, Default)]
+pub struct Zeroizing<T: Zeroize>(T);
+
+impl<T: Zeroize> Zeroizing<T> {
+    /// Wrap a value.
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize + AsRef<[u8]>> Zeroizing<T> {
+    /// Check if the bytes equal `other` in constant time.
+    ///
+    /// The running time depends only on the lengths, never on where the
+    /// first differing byte is.
+    #[must_use]
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        let bytes = self.0.as_ref();
+        if bytes.len() != other.len() {
+            return false;
+        }
+        let diff = bytes.iter().zip(other).fold(0, |acc, (a, b)| acc | (a ^ b));
+        hint::black_box(diff) == 0
+    }
+}
+
+impl<T: Zeroize> From<T> for Zeroizing<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Zeroize> Deref for Zeroizing<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> DerefMut for Zeroizing<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Zeroize> Zeroize for Zeroizing<T> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> fmt::Debug for Zeroizing<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Zeroizing(<redacted>)")
+    }
+}
+
+impl<T: Zeroize> Drop for Zeroizing<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Empties the vector and zeroes its whole allocation, including spare
+/// capacity left behind by earlier truncation. The allocation is kept
+/// until the vector is dropped.
+impl Zeroize for Vec<u8> {
+    fn zeroize(&mut self) {
+        self.clear();
+        self.spare_capacity_mut().fill(MaybeUninit::new(0));
+        // Keep the stores from being elided as dead
+        hint::black_box(self.spare_capacity_mut());
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Zeroes every byte.
+impl<const N: usize> Zeroize for [u8; N] {
+    fn zeroize(&mut self) {
+        self.fill(0);
+        hint::black_box(&mut *self);
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zeroize_scrubs_in_place() {
+        let mut secret = Zeroizing::new(vec![0xA5_u8; 32]);
+        secret.truncate(16);
+        let (ptr, capacity) = (secret.as_ptr(), secret.capacity());
+
+        secret.zeroize();
+        assert!(secret.is_empty());
+        assert_eq!(secret.capacity(), capacity);
+        // SAFETY: clearing keeps the allocation, and every byte was written
+        let scrubbed = unsafe { std::slice::from_raw_parts(ptr, capacity) };
+        assert!(scrubbed.iter().all(|&b| b == 0));
+        assert_eq!(format!("{secret:?}"), "Zeroizing(<redacted>)");
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let secret = Zeroizing::new(vec![1_u8, 2, 3]);
+        assert!(secret.ct_eq(&[1, 2, 3]));
+        assert!(!secret.ct_eq(&[1, 2, 4]));
+        assert!(!secret.ct_eq(&[1, 2]));
+        assert!(Zeroizing::new([7_u8; 4]).ct_eq(&[7; 4]));
+    }
+}